@@ -4,6 +4,7 @@ use std::fmt;
 #[derive(Debug)]
 pub enum AvatarMakerError {
     MissingEntityId,
+    MissingAvatar,
 }
 
 impl error::Error for AvatarMakerError {}
@@ -14,6 +15,12 @@ impl fmt::Display for AvatarMakerError {
             AvatarMakerError::MissingEntityId => {
                 write!(f, "Missing entity ID")
             }
+            AvatarMakerError::MissingAvatar => {
+                write!(
+                    f,
+                    "could not find a decentraland avatar for the given ethereum address"
+                )
+            }
         }
     }
 }
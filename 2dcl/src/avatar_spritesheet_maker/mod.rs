@@ -24,9 +24,9 @@ use bevy_spritesheet_maker::data::ActiveRecorders;
 use bevy_spritesheet_maker::formats::png::is_ready_to_export;
 use bevy_spritesheet_maker::{CaptureState, MediaCapture};
 use catalyst::entity_files::EntityFile;
+use catalyst::LambdaClient;
 //use bevy_toon_shader::{ToonShaderMainCamera, ToonShaderMaterial, ToonShaderPlugin, ToonShaderSun};
 use crate::resources;
-use catalyst::ContentClient;
 use glob::glob;
 use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
@@ -50,11 +50,16 @@ const CAMERA_FOCAL_POINT: Vec3 = Vec3 {
     z: 0.,
 };
 
-pub async fn start(eth_adress: &str) -> dcl_common::Result<()>{
+pub async fn start(eth_adress: &str, config: resources::Config) -> dcl_common::Result<()> {
     println!("making avatar for :{:?}", eth_adress);
-    let avatar_properties =  download_avatar(eth_adress).await?;
+    let avatar_properties = download_avatar(
+        &config.catalyst.server(),
+        &config.cache.content_cache(),
+        eth_adress,
+    )
+    .await?;
     App::new()
-        .insert_resource(resources::Config::from_config_file())
+        .insert_resource(config)
         .add_plugins(
             DefaultPlugins
                 .build()
@@ -89,53 +94,6 @@ pub async fn start(eth_adress: &str) -> dcl_common::Result<()>{
     Ok(())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct CatalystColor {
-    r: f32,
-    g: f32,
-    b: f32,
-}
-#[derive(Serialize, Deserialize, Debug)]
-struct ColoredAvatarPart {
-    color: CatalystColor,
-}
-
-#[derive(Serialize)]
-struct CatalystId {
-    ids: Vec<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Avatar {
-    body_shape: String,
-    eyes: ColoredAvatarPart,
-    hair: ColoredAvatarPart,
-    skin: ColoredAvatarPart,
-    wearables: Vec<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Profile {
-    avatars: AvatarList,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct AvatarList {
-    avatars: AvatarInfo,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct AvatarInfo {
-    avatar: UserData,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct UserData {
-    avatar: Avatar,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 struct Request {
     pointers: Vec<String>,
@@ -148,8 +106,8 @@ struct Animations(Vec<Handle<AnimationClip>>);
 struct AvatarProperties {
     eth_address: String,
     body_shape: BodyShape,
-    hair_color: CatalystColor,
-    skin_color: CatalystColor,
+    hair_color: catalyst::profile::Color,
+    skin_color: catalyst::profile::Color,
     glb_loading_count: usize,
 }
 
@@ -677,13 +635,20 @@ impl Material2d for PostProcessingMaterial {
     }
 }
 
-async fn download_avatar(eth_address: &str) -> dcl_common::Result<AvatarProperties> {
-    let server = catalyst::Server::production();
-    let ids = vec![eth_address.to_string()];
-
-    let catalyst_id = CatalystId { ids };
-    let profile: Profile = server.post("/lambdas/profiles", &catalyst_id).await?;
-    let avatar = profile.avatars.avatars.avatar.avatar;
+async fn download_avatar(
+    server: &catalyst::Server,
+    cache: &catalyst::ContentCache,
+    eth_address: &str,
+) -> dcl_common::Result<AvatarProperties> {
+    let profiles = LambdaClient::profiles(server, &[eth_address]).await?;
+    let avatar = match profiles
+        .into_iter()
+        .flat_map(|profile| profile.avatars)
+        .next()
+    {
+        Some(profile_avatar) => profile_avatar.avatar,
+        None => return Err(Box::new(AvatarMakerError::MissingAvatar)),
+    };
     let mut avatar_save_path = std::env::current_exe().unwrap();
     avatar_save_path.pop();
     avatar_save_path.push("assets");
@@ -698,11 +663,11 @@ async fn download_avatar(eth_address: &str) -> dcl_common::Result<AvatarProperti
     }
 
     for urn in avatar.wearables {
-        download_urn(&urn, &avatar_save_path).await?;
+        download_urn(server, cache, &urn, &avatar_save_path).await?;
     }
 
     avatar_save_path.push("body_shape");
-    download_urn(&avatar.body_shape, &avatar_save_path).await?;
+    download_urn(server, cache, &avatar.body_shape, &avatar_save_path).await?;
     let body_shape = match avatar.body_shape.contains("Female") {
         true => BodyShape::Female,
         false => BodyShape::Male,
@@ -719,8 +684,12 @@ async fn download_avatar(eth_address: &str) -> dcl_common::Result<AvatarProperti
     Ok(new_avatar)
 }
 
-async fn download_urn(urn: &str, save_path: &Path) -> dcl_common::Result<()> {
-    let server = catalyst::Server::production();
+async fn download_urn(
+    server: &catalyst::Server,
+    cache: &catalyst::ContentCache,
+    urn: &str,
+    save_path: &Path,
+) -> dcl_common::Result<()> {
     let request = Request {
         pointers: vec![urn.to_string()],
     };
@@ -734,7 +703,9 @@ async fn download_urn(urn: &str, save_path: &Path) -> dcl_common::Result<()> {
             let mut download_path = save_path.to_path_buf();
             download_path.push(scene_file_id.to_string());
             download_path.push(downloadable.filename.to_str().unwrap());
-            ContentClient::download(&server, downloadable.cid, &download_path).await?;
+            cache
+                .download(server, downloadable.cid, &download_path)
+                .await?;
         }
     }
     Ok(())
@@ -1,12 +1,17 @@
-use std::{collections::HashMap, time::Duration};
+use std::collections::HashMap;
 
 use crate::{
     components,
-    renderer::animation::{Animation, AnimationState},
+    renderer::{
+        animation::{frame_timer, Animation, AnimationState},
+        constants::LAYERS_DISTANCE,
+    },
 };
 use bevy::prelude::*;
 use serde::Deserialize;
 
+use super::sprite_renderer::dcl_anchor_to_bevy_anchor;
+
 #[derive(Bundle)]
 pub struct Animator {
     pub sprite_sheet: SpriteSheetBundle,
@@ -35,10 +40,7 @@ impl Animator {
         );
         let texture_atlas_handle = texture_atlases.add(texture_atlas);
 
-        let duration = match json.animations.get(&json.default_state) {
-            Some(animation) => Duration::from_secs_f32(1. / animation.frame_rate),
-            None => Duration::default(),
-        };
+        let timer = frame_timer(json.animations.get(&json.default_state));
 
         Ok(Animator {
             sprite_sheet: SpriteSheetBundle {
@@ -49,10 +51,85 @@ impl Animator {
                 current_state: json.default_state,
                 state_queue: Vec::default(),
                 animations: json.animations,
-                timer: Timer::new(duration, TimerMode::Repeating),
+                timer,
             },
         })
     }
+
+    pub fn from_sprite_sheet_renderer(
+        sprite_sheet_renderer_component: &dcl2d_ecs_v1::components::SpriteSheetRenderer,
+        transform: &Transform,
+        texture: Handle<Image>,
+        texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Animator {
+        let tile_size = Vec2::new(
+            sprite_sheet_renderer_component.tile_size.width as f32,
+            sprite_sheet_renderer_component.tile_size.height as f32,
+        );
+        let texture_atlas = TextureAtlas::from_grid(
+            texture,
+            tile_size,
+            sprite_sheet_renderer_component.columns,
+            sprite_sheet_renderer_component.rows,
+            None,
+            None,
+        );
+        let texture_atlas_handle = texture_atlases.add(texture_atlas);
+
+        let animations: HashMap<AnimationState, Animation> = sprite_sheet_renderer_component
+            .animations
+            .iter()
+            .map(|(state, animation)| {
+                (
+                    state.clone(),
+                    Animation {
+                        first: animation.first,
+                        last: animation.last,
+                        flip_x: sprite_sheet_renderer_component.flip.x,
+                        flip_y: sprite_sheet_renderer_component.flip.y,
+                        frame_rate: animation.frame_rate,
+                    },
+                )
+            })
+            .collect();
+
+        let default_state = sprite_sheet_renderer_component.default_state.clone();
+        let index = animations
+            .get(&default_state)
+            .map_or(0, |animation| animation.first);
+        let timer = frame_timer(animations.get(&default_state));
+
+        let mut final_transform = *transform;
+        final_transform.translation.z +=
+            sprite_sheet_renderer_component.layer as f32 * LAYERS_DISTANCE;
+
+        let color = &sprite_sheet_renderer_component.color;
+
+        Animator {
+            sprite_sheet: SpriteSheetBundle {
+                sprite: TextureAtlasSprite {
+                    index,
+                    color: Color::rgba(color.r, color.g, color.b, color.a),
+                    flip_x: sprite_sheet_renderer_component.flip.x,
+                    flip_y: sprite_sheet_renderer_component.flip.y,
+                    anchor: dcl_anchor_to_bevy_anchor(
+                        sprite_sheet_renderer_component.anchor.clone(),
+                        tile_size,
+                    ),
+                    ..default()
+                },
+                texture_atlas: texture_atlas_handle,
+                transform: final_transform,
+                ..default()
+            },
+            animator: components::Animator {
+                current_state: default_state,
+                state_queue: Vec::default(),
+                animations,
+                timer,
+            },
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -11,6 +11,7 @@ pub use transform::Transform;
 
 mod sprite_renderer;
 pub use sprite_renderer::get_translation_by_anchor;
+pub use sprite_renderer::BlendedSpriteRenderer;
 pub use sprite_renderer::SpriteRenderer;
 
 mod downloading_scene;
@@ -3,7 +3,11 @@ use std::{
     str::FromStr,
 };
 
-use bevy::{prelude::*, sprite::Anchor, tasks::AsyncComputeTaskPool};
+use bevy::{
+    prelude::*,
+    sprite::{Anchor, MaterialMesh2dBundle},
+    tasks::AsyncComputeTaskPool,
+};
 use dcl_common::Parcel;
 use imagesize::size;
 
@@ -12,6 +16,7 @@ use crate::{
     renderer::{
         constants::{LAYERS_DISTANCE, PARCEL_SIZE_X, PARCEL_SIZE_Y},
         scene_loader::world_location_to_parcel,
+        sprite_material::{anchored_quad, dcl_blend_mode_to_blend_state, SpriteMaterial},
     },
 };
 
@@ -137,7 +142,50 @@ impl SpriteRenderer {
     }
 }
 
-fn dcl_anchor_to_bevy_anchor(anchor: dcl2d_ecs_v1::Anchor, size: Vec2) -> Anchor {
+#[derive(Bundle)]
+pub struct BlendedSpriteRenderer {
+    pub sprite: Sprite,
+    pub material_mesh: MaterialMesh2dBundle<SpriteMaterial>,
+    pub renderer: components::SpriteRenderer,
+}
+
+impl BlendedSpriteRenderer {
+    pub fn from_sprite_renderer(
+        sprite_renderer: SpriteRenderer,
+        image_size: Vec2,
+        blend_mode: &dcl2d_ecs_v1::blend_mode::BlendMode,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<SpriteMaterial>,
+    ) -> Self {
+        let sprite = sprite_renderer.sprite.sprite;
+
+        let mesh = anchored_quad(
+            image_size,
+            sprite.anchor.as_vec(),
+            sprite.flip_x,
+            sprite.flip_y,
+        );
+
+        let material = SpriteMaterial {
+            color: sprite.color,
+            texture: sprite_renderer.sprite.texture,
+            blend: dcl_blend_mode_to_blend_state(blend_mode),
+        };
+
+        BlendedSpriteRenderer {
+            material_mesh: MaterialMesh2dBundle {
+                mesh: meshes.add(mesh).into(),
+                material: materials.add(material),
+                transform: sprite_renderer.sprite.transform,
+                ..default()
+            },
+            sprite,
+            renderer: sprite_renderer.renderer,
+        }
+    }
+}
+
+pub fn dcl_anchor_to_bevy_anchor(anchor: dcl2d_ecs_v1::Anchor, size: Vec2) -> Anchor {
     match anchor {
         dcl2d_ecs_v1::Anchor::BottomCenter => Anchor::BottomCenter,
         dcl2d_ecs_v1::Anchor::BottomLeft => Anchor::BottomLeft,
@@ -150,6 +198,10 @@ fn dcl_anchor_to_bevy_anchor(anchor: dcl2d_ecs_v1::Anchor, size: Vec2) -> Anchor
             let y = vec.y.min(size.y as i32).max(0);
             Anchor::Custom(Vec2::new(x as f32 - size.x / 2.0, y as f32 - size.y / 2.0) / size)
         }
+        // The compiler uses unbounded anchors to place the tiles of images that are too big.
+        dcl2d_ecs_v1::Anchor::Unbounded(vec) => Anchor::Custom(
+            Vec2::new(vec.x as f32 - size.x / 2.0, vec.y as f32 - size.y / 2.0) / size,
+        ),
         dcl2d_ecs_v1::Anchor::TopCenter => Anchor::TopCenter,
         dcl2d_ecs_v1::Anchor::TopLeft => Anchor::TopLeft,
         dcl2d_ecs_v1::Anchor::TopRight => Anchor::TopRight,
@@ -1,4 +1,5 @@
-use crate::renderer::animation::{Animation, AnimationState};
+use crate::renderer::actions::Action;
+use crate::renderer::animation::{frame_timer, Animation, AnimationState};
 use crate::renderer::player::LevelChangeStackData;
 use bevy::prelude::*;
 use bevy::tasks::Task;
@@ -6,7 +7,7 @@ use dcl2d_ecs_v1::collision_type::CollisionType;
 use dcl_common::Parcel;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::{Duration, SystemTime};
+use std::time::SystemTime;
 
 #[derive(Component)]
 pub struct DownloadingScene {
@@ -30,9 +31,36 @@ pub struct LoadingSpriteRenderer {
     pub level_id: usize,
 }
 
+#[derive(Component)]
+pub struct LoadingSpriteSheetRenderer {
+    pub texture: Handle<Image>,
+    pub sprite_sheet_renderer_component: dcl2d_ecs_v1::components::SpriteSheetRenderer,
+    pub transform: Transform,
+}
+
 #[derive(Component)]
 pub struct GettingNewestScenes {
     pub task: Task<Option<(Vec<catalyst::entity_files::SceneFile>, Vec<Parcel>)>>,
+    pub parcels: Vec<Parcel>,
+}
+
+/// Marks the downloads started for the parcels around the player, which are dropped when the
+/// player leaves those parcels behind.
+#[derive(Component)]
+pub struct AroundPlayer;
+
+/// Marks the downloads that are no longer needed. Their threads can't be stopped, so they're kept
+/// hidden, and counted against `max_concurrent_downloads`, until they finish.
+#[derive(Component)]
+pub struct Cancelled;
+
+/// Marks the downloads started by the world sync to bring the downloaded scenes up to date.
+#[derive(Component)]
+pub struct WorldSyncDownload;
+
+#[derive(Component)]
+pub struct PollingSceneChanges {
+    pub task: Task<Option<(u64, Vec<Parcel>)>>,
 }
 
 #[derive(Debug, Component, Clone)]
@@ -43,11 +71,24 @@ pub struct BoxCollider {
     pub parcels: Vec<Parcel>,
 }
 
-#[derive(Debug, Component, Clone, PartialEq)]
+#[derive(Debug, Component, Clone)]
+pub struct CircleCollider {
+    pub center: Vec2,
+    pub radius: f32,
+    pub collision_type: CollisionType,
+    pub parcels: Vec<Parcel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct LevelChange {
     pub spawn_point: Vec2,
     pub level: usize,
-    pub parcels: Vec<Parcel>,
+}
+
+#[derive(Debug, Component, Clone, Default)]
+pub struct TriggerActions {
+    pub on_interact: Vec<Action>,
+    pub on_enter: Vec<Action>,
 }
 
 #[derive(Debug)]
@@ -160,8 +201,7 @@ impl Animator {
         self.current_state = new_state.clone();
         self.state_queue = vec![new_state];
         if let Some(current_animation) = self.get_current_animation() {
-            self.timer
-                .set_duration(Duration::from_secs_f32(1. / current_animation.frame_rate));
+            self.timer = frame_timer(Some(current_animation));
         }
         self.timer.set_elapsed(self.timer.duration());
     }
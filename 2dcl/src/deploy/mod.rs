@@ -7,7 +7,7 @@ use std::{
 
 use catalyst::EntityId;
 use chrono::{DateTime, Utc};
-use dcl2d_ecs_v1::Scene;
+use dcl2d_ecs_v1::scene_file;
 use dcl_common::Result;
 use dcl_crypto::{
     account::{EphemeralPayload, PersonalSignature},
@@ -17,14 +17,13 @@ use ethereum_adapter::EthereumAdapter;
 use scene_deployer::FileData;
 use walkdir::WalkDir;
 
-pub async fn deploy<T>(deploy_folder: T) -> Result<()>
+pub async fn deploy<T>(deploy_folder: T, server: catalyst::Server) -> Result<()>
 where
     T: AsRef<Path>,
 {
     let ephemeral_identity = dcl_crypto::Account::random();
     let mut chain = sign_ephemeral(&ephemeral_identity, 300).await?;
 
-    let server = catalyst::Server::production();
     let (deploy_data, entity_id) = prepare_deploy_data(deploy_folder, &server).await?;
 
     let payload = &entity_id.0;
@@ -61,7 +60,7 @@ where
     scene_file.push("scene.2dcl");
 
     let scene = std::fs::read(scene_file)?;
-    let scene = Scene::from_mp(&scene)?;
+    let scene = scene_file::read(&scene)?;
 
     let parcels = scene.parcels;
     let scene_files = catalyst::ContentClient::scene_files_for_parcels(server, &parcels).await?;
@@ -11,10 +11,12 @@ mod renderer;
 pub mod bundles;
 pub mod components;
 pub mod deploy;
+pub mod pull;
 pub mod resources;
 pub mod states;
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use tempdir::TempDir;
 
 /// Search for a pattern in a file and display the lines that contain it.
@@ -22,6 +24,25 @@ use tempdir::TempDir;
 struct Cli {
     #[command(subcommand)]
     action: Option<Action>,
+    /// Catalyst server used to download and deploy scenes, instead of the one in `config.toml`.
+    #[clap(long, global = true)]
+    catalyst: Option<String>,
+    /// Explore only the scenes already downloaded, without connecting to Catalyst servers.
+    #[clap(long)]
+    offline: bool,
+}
+
+impl Cli {
+    /// Only exploring the world can be done offline, the rest of the commands need the servers.
+    fn check_offline(&self) -> std::result::Result<(), clap::Error> {
+        if self.offline && self.action.is_some() {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                "--offline can't be used with a subcommand",
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(clap::Subcommand)]
@@ -42,34 +63,82 @@ enum Action {
         #[clap(default_value = "./")]
         source_path: std::path::PathBuf,
     },
+    Pull {
+        #[clap(allow_hyphen_values = true)]
+        parcel: String,
+        #[clap(default_value = "./")]
+        destination_path: std::path::PathBuf,
+    },
     Where,
     ImportAvatar {
         eth_address: String,
     },
     Clean,
+    /// Updates the parcels drawn as roads with the scenes deployed since the last update.
+    UpdateRoads,
 }
 
 #[tokio::main]
 pub async fn main() -> Result<()> {
     let args = Cli::parse();
+    if let Err(e) = args.check_offline() {
+        e.exit();
+    }
+    let catalyst = args.catalyst;
+    let offline = args.offline;
 
     match args.action {
         Some(Action::Preview {
             source_path,
             destination_path,
         }) => {
-            previewer::preview(source_path, destination_path);
+            let config = resources::Config::from_config_file().with_catalyst(catalyst);
+            previewer::preview(source_path, destination_path, config);
         }
         Some(Action::Build {
             source_path,
             destination_path,
-        }) => {
-            scene_compiler::compile(source_path, destination_path).unwrap();
-        }
+        }) => match scene_compiler::compile(source_path, destination_path) {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics {
+                    println!("{}", diagnostic);
+                }
+                println!("scene compilation finished successfully");
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
         Some(Action::Deploy { source_path }) => {
             let tmp_dir = TempDir::new("temp_build").unwrap();
-            scene_compiler::compile(source_path, &tmp_dir).unwrap();
-            deploy::deploy(tmp_dir).await?;
+            match scene_compiler::compile(source_path, &tmp_dir) {
+                Ok(diagnostics) => {
+                    for diagnostic in diagnostics {
+                        println!("{}", diagnostic);
+                    }
+                    let config = resources::Config::from_config_file().with_catalyst(catalyst);
+                    deploy::deploy(tmp_dir, config.catalyst.server()).await?;
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    drop(tmp_dir);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Action::Pull {
+            parcel,
+            destination_path,
+        }) => {
+            let config = resources::Config::from_config_file().with_catalyst(catalyst);
+            pull::pull(
+                &parcel,
+                destination_path,
+                &config.catalyst.server(),
+                &config.cache.content_cache(),
+            )
+            .await?;
         }
         Some(Action::Clean) => {
             let current_path = std::env::current_exe().unwrap();
@@ -79,17 +148,52 @@ pub async fn main() -> Result<()> {
             if let Err(e) = renderer::scenes_io::clear_all_downloaded_scenes() {
                 println!("{}", e);
             }
+
+            let config = resources::Config::from_config_file();
+            if let Err(e) = config.cache.content_cache().clear() {
+                println!("{}", e);
+            }
+        }
+        Some(Action::UpdateRoads) => {
+            let current_path = std::env::current_exe().unwrap();
+            let current_path = current_path.parent().unwrap();
+            std::env::set_current_dir(current_path).unwrap();
+
+            let config = resources::Config::from_config_file().with_catalyst(catalyst);
+            let checked_parcels =
+                renderer::roads_updater::update_roads_file(&config.catalyst.server()).await?;
+            println!("roads updated, {} parcels checked", checked_parcels);
         }
         Some(Action::Where) => {
             let scenes = content_discovery::find_2d_scenes_str().await?;
             println!("{}", scenes);
         }
         Some(Action::ImportAvatar { eth_address }) => {
-            avatar_spritesheet_maker::start(&eth_address).await?;
+            let config = resources::Config::from_config_file().with_catalyst(catalyst);
+            avatar_spritesheet_maker::start(&eth_address, config).await?;
         }
         None => {
-            renderer::start();
+            renderer::start(
+                resources::Config::from_config_file()
+                    .with_catalyst(catalyst)
+                    .with_offline(offline),
+            );
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offline_only_applies_to_exploring() {
+        let cli = Cli::try_parse_from(["2dcl", "--offline"]).unwrap();
+        assert!(cli.check_offline().is_ok());
+
+        let cli = Cli::try_parse_from(["2dcl", "--offline", "pull", "0,0"]).unwrap();
+        assert!(cli.check_offline().is_err());
+        assert!(Cli::try_parse_from(["2dcl", "deploy", "--offline"]).is_err());
+    }
+}
@@ -1,19 +1,27 @@
 use crate::components;
 use crate::resources::Config;
 use bevy::prelude::*;
+use bevy::render::render_resource::PrimitiveTopology;
 use bevy::sprite::MaterialMesh2dBundle;
 use dcl2d_ecs_v1::collision_type::CollisionType;
+use std::f32::consts::TAU;
+
+const CIRCLE_SEGMENTS: usize = 64;
 
 #[derive(Debug, Component)]
 pub struct BoxColliderDebug;
 
+#[derive(Debug, Component)]
+pub struct CircleColliderDebug;
+
 pub fn collider_debugger(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     keyboard: Res<Input<KeyCode>>,
     box_colliders: Query<(Entity, &components::BoxCollider)>,
-    debug_colliders: Query<(Entity, &BoxColliderDebug)>,
+    circle_colliders: Query<(Entity, &components::CircleCollider)>,
+    debug_colliders: Query<Entity, Or<(With<BoxColliderDebug>, With<CircleColliderDebug>)>>,
     player_query: Query<Entity, With<components::Player>>,
     config: Res<Config>,
 ) {
@@ -54,6 +62,42 @@ pub fn collider_debugger(
             commands.entity(parent).add_child(entity);
         }
 
+        for (parent, collider) in circle_colliders.iter() {
+            let transform = Transform::default()
+                .with_translation(Vec3 {
+                    x: collider.center.x,
+                    y: collider.center.y,
+                    z: 100.0,
+                })
+                .with_scale(Vec3 {
+                    x: collider.radius,
+                    y: collider.radius,
+                    z: 1.0,
+                });
+
+            let color = match collider.collision_type {
+                CollisionType::Trigger => Color::Rgba {
+                    red: 0.0,
+                    green: 1.0,
+                    blue: 0.0,
+                    alpha: 0.2,
+                },
+                CollisionType::Solid => Color::GREEN,
+            };
+
+            let entity = commands
+                .spawn(MaterialMesh2dBundle {
+                    mesh: meshes.add(circle_outline()).into(),
+                    transform,
+                    material: materials.add(ColorMaterial::from(color)),
+                    ..default()
+                })
+                .insert(CircleColliderDebug {})
+                .id();
+
+            commands.entity(parent).add_child(entity);
+        }
+
         for player in player_query.iter() {
             let transform = Transform::default()
                 .with_translation(Vec3 {
@@ -83,8 +127,22 @@ pub fn collider_debugger(
     }
 
     if keyboard.just_released(KeyCode::C) {
-        for (entity, _) in debug_colliders.iter() {
+        for entity in debug_colliders.iter() {
             commands.entity(entity).despawn_recursive();
         }
     }
 }
+
+/// Outline of a circle of radius 1, so it doesn't hide what's under the collider.
+fn circle_outline() -> Mesh {
+    let positions: Vec<[f32; 3]> = (0..=CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            [angle.cos(), angle.sin(), 0.]
+        })
+        .collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh
+}
@@ -1,6 +1,7 @@
 use std::{path::Path, time::Duration};
 
 use super::{manual_refresh::RefreshData, ui::Message};
+use crate::resources;
 use bevy::{
     prelude::*,
     tasks::{AsyncComputeTaskPool, Task},
@@ -40,6 +41,7 @@ pub fn handle_tasks(
     mut deploying_tasks: Query<(Entity, &mut Deploying)>,
     mut messages: Query<&mut Message>,
     refresh_data: Res<RefreshData>,
+    config: Res<resources::Config>,
 ) {
     for (entity, mut task) in &mut deploying_tasks {
         if let Some(state) = future::block_on(future::poll_once(&mut task.0)) {
@@ -52,8 +54,10 @@ pub fn handle_tasks(
 
                     let thread_pool = AsyncComputeTaskPool::get();
                     let deploy_folder = refresh_data.destination_path.clone();
-                    let task = thread_pool
-                        .spawn(async move { prepare_deploy_data(ephemeral, chain, deploy_folder) });
+                    let server = config.catalyst.server();
+                    let task = thread_pool.spawn(async move {
+                        prepare_deploy_data(server, ephemeral, chain, deploy_folder)
+                    });
 
                     commands.entity(entity).insert(Deploying(task));
                 }
@@ -73,8 +77,9 @@ pub fn handle_tasks(
                     let chain = AuthChain::from(chain);
 
                     let thread_pool = AsyncComputeTaskPool::get();
-                    let task =
-                        thread_pool.spawn(async move { deploy(entity_id, deploy_data, chain) });
+                    let server = config.catalyst.server();
+                    let task = thread_pool
+                        .spawn(async move { deploy(server, entity_id, deploy_data, chain) });
 
                     commands.entity(entity).insert(Deploying(task));
                 }
@@ -102,6 +107,7 @@ pub fn handle_tasks(
 
 #[tokio::main]
 async fn prepare_deploy_data<T>(
+    server: catalyst::Server,
     ephemeral_identity: dcl_crypto::Account,
     auth_chain: Vec<AuthLink>,
     deploy_folder: T,
@@ -109,7 +115,6 @@ async fn prepare_deploy_data<T>(
 where
     T: AsRef<Path>,
 {
-    let server = catalyst::Server::production();
     let (deploy_data, entity_id) =
         match crate::deploy::prepare_deploy_data(deploy_folder, &server).await {
             Ok(v) => v,
@@ -130,11 +135,11 @@ async fn sign_ephemeral(duration_in_secs: u64) -> DeployState {
 
 #[tokio::main]
 async fn deploy(
+    server: catalyst::Server,
     entity_id: EntityId,
     deploy_data: Vec<FileData>,
     auth_chain: AuthChain,
 ) -> DeployState {
-    let server = catalyst::Server::production();
     let response = match scene_deployer::deploy(entity_id, deploy_data, auth_chain, server).await {
         Ok(v) => v,
         Err(err) => return DeployState::Error(format!("{}", err)),
@@ -14,7 +14,14 @@ pub fn manual_refresh(
     asset_server: Res<AssetServer>,
 ) {
     if keyboard.just_pressed(KeyCode::R) {
-        scene_compiler::compile(&refresh_data.source_path, &refresh_data.destination_path).unwrap();
-        asset_server.reload_asset("../scene.2dcl");
+        match scene_compiler::compile(&refresh_data.source_path, &refresh_data.destination_path) {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics {
+                    println!("{}", diagnostic);
+                }
+                asset_server.reload_asset("../scene.2dcl");
+            }
+            Err(e) => println!("{}", e),
+        }
     }
 }
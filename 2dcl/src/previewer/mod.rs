@@ -20,13 +20,13 @@ use deploy::deploy_input;
 mod ui;
 
 use crate::{
-    bundles::loading_animation, renderer::scene_loader::loading_sprites_task_handler,
+    bundles::loading_animation, renderer::scene_loader::loading_sprites_task_handler, resources,
     states::AppState,
 };
 
 use self::manual_refresh::RefreshData;
 
-pub fn preview<T, U>(source_path: T, destination_path: U)
+pub fn preview<T, U>(source_path: T, destination_path: U, config: resources::Config)
 where
     T: AsRef<Path>,
     U: AsRef<Path>,
@@ -35,7 +35,17 @@ where
     let destination_path = destination_path.as_ref();
 
     // compile
-    scene_compiler::compile(source_path, destination_path).unwrap();
+    match scene_compiler::compile(source_path, destination_path) {
+        Ok(diagnostics) => {
+            for diagnostic in diagnostics {
+                println!("{}", diagnostic);
+            }
+        }
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    }
 
     let mut src_abs_path = std::fs::canonicalize(".").unwrap();
     src_abs_path.push(source_path);
@@ -44,15 +54,24 @@ where
     dst_abs_path.push(destination_path);
 
     // run preview
-    preview_scene(src_abs_path.to_path_buf(), dst_abs_path.to_path_buf());
+    preview_scene(
+        src_abs_path.to_path_buf(),
+        dst_abs_path.to_path_buf(),
+        config,
+    );
 }
 
-pub fn preview_scene(source_path: std::path::PathBuf, destination_path: std::path::PathBuf) {
+pub fn preview_scene(
+    source_path: std::path::PathBuf,
+    destination_path: std::path::PathBuf,
+    config: resources::Config,
+) {
     let mut app = App::new();
     crate::renderer::setup(
         &mut app,
         "2dcl - Scene Preview".to_string(),
         &destination_path,
+        config,
     );
 
     app.add_plugins(SceneHotReloadPlugin)
@@ -105,10 +105,14 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             if let Modify(_) = kind {
                 for path in paths {
                     if path.ends_with("scene.json") {
-                        if let Err(error) = scene_compiler::compile(&path, "./build") {
-                            println!("Error compiling: {}", error)
-                        } else {
-                            asset_server.reload_asset("../scene.2dcl");
+                        match scene_compiler::compile(&path, "./build") {
+                            Ok(diagnostics) => {
+                                for diagnostic in diagnostics {
+                                    println!("{}", diagnostic);
+                                }
+                                asset_server.reload_asset("../scene.2dcl");
+                            }
+                            Err(error) => println!("Error compiling: {}", error),
                         }
                     }
                 }
@@ -140,6 +144,7 @@ fn scene_reload(
                 if scene.timestamp != current_scene.timestamp.0 {
                     despawned_entities.entities.push(entity);
                     commands.entity(entity).despawn_recursive();
+                    collision_map.remove_scene(entity);
                     let timestamp = scene.timestamp;
                     if let Some(mut scene) = read_scene_u8(&scene.bytes) {
                         scene.timestamp = timestamp;
@@ -253,7 +258,7 @@ pub fn level_change(
             despawned_entities.entities.push(scene_entity);
             commands.entity(scene_entity).despawn_recursive();
             //Clear collision map
-            collision_map.tiles.clear();
+            collision_map.remove_scene(scene_entity);
         } else {
             should_spawn = false;
         }
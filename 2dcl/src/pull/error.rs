@@ -0,0 +1,39 @@
+use dcl_common::Parcel;
+use std::error;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum PullError {
+    InvalidParcel(String),
+    SceneNotFound(Parcel),
+    Not2dclScene(Parcel),
+    InvalidContentPath(PathBuf),
+    DestinationNotEmpty(PathBuf),
+}
+
+impl error::Error for PullError {}
+
+impl fmt::Display for PullError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PullError::InvalidParcel(parcel) => {
+                write!(f, "Invalid parcel `{}`, it should look like `x,y`.", parcel)
+            }
+            PullError::SceneNotFound(parcel) => {
+                write!(f, "There's no scene deployed in {},{}.", parcel.0, parcel.1)
+            }
+            PullError::Not2dclScene(parcel) => write!(
+                f,
+                "The scene deployed in {},{} doesn't have a 2dcl scene.",
+                parcel.0, parcel.1
+            ),
+            PullError::InvalidContentPath(path) => {
+                write!(f, "Invalid content path: {}", path.display())
+            }
+            PullError::DestinationNotEmpty(path) => {
+                write!(f, "There's already a scene in {}.", path.display())
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+mod error;
+
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use catalyst::{ContentCache, ContentClient, Server};
+use dcl2d_ecs_v1::scene_file;
+use dcl_common::{Parcel, Result};
+
+pub use error::PullError;
+
+/// Downloads the 2dcl scene deployed in `parcel` and turns it back into a project that
+/// `scene_compiler::compile` can build: a `scene.json` next to its `assets` folder.
+///
+/// Files already in `cache` aren't downloaded again. Assets are copied out of it, since they're
+/// meant to be edited.
+pub async fn pull<T>(
+    parcel: &str,
+    destination_path: T,
+    server: &Server,
+    cache: &ContentCache,
+) -> Result<()>
+where
+    T: AsRef<Path>,
+{
+    let parcel =
+        Parcel::from_str(parcel).map_err(|_| PullError::InvalidParcel(parcel.to_string()))?;
+    let destination_path = destination_path.as_ref();
+    let scene_json_path = destination_path.join("scene.json");
+    if scene_json_path.exists() {
+        return Err(Box::new(PullError::DestinationNotEmpty(
+            destination_path.to_path_buf(),
+        )));
+    }
+
+    let scene_files = ContentClient::scene_files_for_parcels(server, &vec![parcel.clone()]).await?;
+    let scene_file = match scene_files.into_iter().next() {
+        Some(v) => v,
+        None => return Err(Box::new(PullError::SceneNotFound(parcel))),
+    };
+
+    let mut compiled_scene = None;
+    let mut assets = Vec::new();
+    for content_file in scene_file.content {
+        match content_path(&content_file.filename)? {
+            Some(path) if path == Path::new("scene.2dcl") => compiled_scene = Some(content_file),
+            Some(path) if path.starts_with("assets") => assets.push((path, content_file)),
+            _ => {}
+        }
+    }
+
+    let compiled_scene = match compiled_scene {
+        Some(v) => v,
+        None => return Err(Box::new(PullError::Not2dclScene(parcel))),
+    };
+
+    let compiled_scene_path = cache.fetch(server, &compiled_scene.cid).await?;
+    let scene = scene_file::read(&std::fs::read(&compiled_scene_path)?)?;
+
+    std::fs::create_dir_all(destination_path.join("assets"))?;
+    for (path, content_file) in assets {
+        println!("downloading {}", path.display());
+        let cached_path = cache.fetch(server, &content_file.cid).await?;
+        let asset_path = destination_path.join(path);
+        if let Some(parent) = asset_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(cached_path, asset_path)?;
+    }
+
+    std::fs::write(&scene_json_path, scene_compiler::decompile(&scene)?)?;
+    println!(
+        "scene `{}` pulled to {}",
+        scene.name,
+        destination_path.display()
+    );
+    Ok(())
+}
+
+/// Path of a deployed file relative to the compiled scene, or `None` if it isn't part of it.
+fn content_path(filename: &Path) -> Result<Option<PathBuf>> {
+    let mut components = filename
+        .components()
+        .filter(|component| *component != Component::CurDir);
+
+    match components.next() {
+        Some(Component::Normal(folder)) if folder == "2dcl" => {}
+        _ => return Ok(None),
+    }
+
+    let mut path = PathBuf::new();
+    for component in components {
+        match component {
+            Component::Normal(part) => path.push(part),
+            _ => {
+                return Err(Box::new(PullError::InvalidContentPath(
+                    filename.to_path_buf(),
+                )))
+            }
+        }
+    }
+
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn content_paths_are_relative_to_the_2dcl_folder() {
+        assert_eq!(
+            content_path(Path::new("./2dcl/scene.2dcl")).unwrap(),
+            Some(PathBuf::from("scene.2dcl"))
+        );
+        assert_eq!(
+            content_path(Path::new("2dcl/assets/tree.png")).unwrap(),
+            Some(PathBuf::from("assets/tree.png"))
+        );
+        assert_eq!(content_path(Path::new("scene.json")).unwrap(), None);
+        assert!(content_path(Path::new("2dcl/../../.bashrc")).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::PathBuf;
+
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use dcl_common::Parcel;
+
+use super::scene_loader::{get_parcel_spawn_point, parcel_to_world_location};
+use super::scene_maker::RoadsData;
+use super::scenes_io::SceneFilesMap;
+use crate::components::{self, LevelChange};
+use crate::resources;
+use crate::states::AppState;
+
+const MESSAGE_FONT: &str = "fonts/Arcadepix Plus.ttf";
+const MESSAGE_FONT_SIZE: f32 = 18.0;
+const MESSAGE_BG_COLOR: Color = Color::rgba(0., 0., 0., 0.8);
+const MESSAGE_TEXT_COLOR: Color = Color::WHITE;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    ChangeLevel(LevelChange),
+    Teleport(Parcel),
+    SetVisibility { entity: String, visible: bool },
+    PlaySound { path: PathBuf, volume: f32 },
+    ShowMessage { message: String, duration: f32 },
+    OpenUrl(String),
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ActionEvent {
+    pub source: Entity,
+    pub action: Action,
+}
+
+#[derive(Component)]
+struct ActionMessage {
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct UrlConfirmation {
+    url: String,
+}
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ActionEvent>().add_systems(
+            Update,
+            (
+                teleport,
+                set_visibility,
+                play_sound,
+                show_message,
+                update_messages,
+                ask_url_confirmation,
+                url_confirmation,
+            )
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+pub fn send_actions(
+    source: Entity,
+    actions: &[Action],
+    action_events: &mut EventWriter<ActionEvent>,
+) {
+    for action in actions {
+        action_events.send(ActionEvent {
+            source,
+            action: action.clone(),
+        });
+    }
+}
+
+fn teleport(
+    mut action_events: EventReader<ActionEvent>,
+    mut player_query: Query<(&mut components::Player, &mut Transform)>,
+    mut roads_data: Option<ResMut<RoadsData>>,
+    scene_files_map: Option<Res<SceneFilesMap>>,
+    peers: Res<resources::CatalystPeers>,
+    lookup: Res<resources::SceneFilesLookup>,
+    cache: Res<resources::ContentCache>,
+    connectivity: Res<resources::Connectivity>,
+) {
+    for event in action_events.iter() {
+        if let Action::Teleport(parcel) = &event.action {
+            if let Ok((mut player, mut transform)) = player_query.get_single_mut() {
+                player.current_level = 0;
+                player.level_change_stack.clear();
+                // The previewer doesn't load the world, so there we just go to the parcel center.
+                transform.translation = match (roads_data.as_mut(), scene_files_map.as_ref()) {
+                    (Some(roads_data), Some(scene_files_map)) => get_parcel_spawn_point(
+                        parcel,
+                        0,
+                        roads_data,
+                        scene_files_map,
+                        &peers,
+                        &lookup,
+                        &cache,
+                        &connectivity,
+                    ),
+                    _ => parcel_to_world_location(parcel.clone()),
+                };
+            }
+        }
+    }
+}
+
+fn set_visibility(
+    mut action_events: EventReader<ActionEvent>,
+    mut entities_query: Query<(Entity, &Name, &mut Visibility)>,
+    parents_query: Query<&Parent>,
+) {
+    for event in action_events.iter() {
+        if let Action::SetVisibility { entity, visible } = &event.action {
+            // Entity names are only unique inside a scene, so we look for it under the same root.
+            let source_root = get_root(event.source, &parents_query);
+            for (other_entity, name, mut visibility) in entities_query.iter_mut() {
+                if name.as_str() == entity && get_root(other_entity, &parents_query) == source_root
+                {
+                    *visibility = match visible {
+                        true => Visibility::Inherited,
+                        false => Visibility::Hidden,
+                    };
+                }
+            }
+        }
+    }
+
+    fn get_root(entity: Entity, parents_query: &Query<&Parent>) -> Entity {
+        let mut root = entity;
+        while let Ok(parent) = parents_query.get(root) {
+            root = parent.get();
+        }
+        root
+    }
+}
+
+fn play_sound(
+    mut commands: Commands,
+    mut action_events: EventReader<ActionEvent>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in action_events.iter() {
+        if let Action::PlaySound { path, volume } = &event.action {
+            commands.spawn(AudioBundle {
+                source: asset_server.load(path.clone()),
+                settings: PlaybackSettings::DESPAWN.with_volume(Volume::new_relative(*volume)),
+            });
+        }
+    }
+}
+
+fn show_message(
+    mut commands: Commands,
+    mut action_events: EventReader<ActionEvent>,
+    messages_query: Query<Entity, With<ActionMessage>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in action_events.iter() {
+        if let Action::ShowMessage { message, duration } = &event.action {
+            for message_entity in messages_query.iter() {
+                commands.entity(message_entity).despawn_recursive();
+            }
+
+            spawn_message_box(
+                &mut commands,
+                &asset_server,
+                message.clone(),
+                ActionMessage {
+                    timer: Timer::from_seconds(*duration, TimerMode::Once),
+                },
+            );
+        }
+    }
+}
+
+fn update_messages(
+    mut commands: Commands,
+    mut messages_query: Query<(Entity, &mut ActionMessage)>,
+    time: Res<Time>,
+) {
+    for (entity, mut message) in messages_query.iter_mut() {
+        message.timer.tick(time.delta());
+        if message.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn ask_url_confirmation(
+    mut commands: Commands,
+    mut action_events: EventReader<ActionEvent>,
+    confirmations_query: Query<Entity, With<UrlConfirmation>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in action_events.iter() {
+        if let Action::OpenUrl(url) = &event.action {
+            if !confirmations_query.is_empty() {
+                continue;
+            }
+
+            spawn_message_box(
+                &mut commands,
+                &asset_server,
+                format!("Open {}?\n[Y] Yes   [N] No", url),
+                UrlConfirmation { url: url.clone() },
+            );
+        }
+    }
+}
+
+fn url_confirmation(
+    mut commands: Commands,
+    confirmations_query: Query<(Entity, &UrlConfirmation)>,
+    keyboard: Res<Input<KeyCode>>,
+) {
+    for (entity, confirmation) in confirmations_query.iter() {
+        if keyboard.just_pressed(KeyCode::Y) {
+            if let Err(err) = open::that(&confirmation.url) {
+                println!("Couldn't open {}: {}", confirmation.url, err);
+            }
+            commands.entity(entity).despawn_recursive();
+        } else if keyboard.just_pressed(KeyCode::N) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn spawn_message_box<B>(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    message: String,
+    marker: B,
+) where
+    B: Bundle,
+{
+    let message_box = commands
+        .spawn((
+            NodeBundle {
+                background_color: BackgroundColor(MESSAGE_BG_COLOR),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Percent(5.),
+                    left: Val::Percent(20.),
+                    width: Val::Percent(60.),
+                    padding: UiRect::all(Val::Px(10.)),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            marker,
+            Name::new("Action message"),
+        ))
+        .id();
+
+    commands
+        .spawn(TextBundle {
+            text: Text::from_section(
+                message,
+                TextStyle {
+                    font: asset_server.load(MESSAGE_FONT),
+                    font_size: MESSAGE_FONT_SIZE,
+                    color: MESSAGE_TEXT_COLOR,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            ..Default::default()
+        })
+        .set_parent(message_box);
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use serde::Deserialize;
+use std::time::Duration;
 
 use crate::{components, states::AppState};
 
@@ -15,6 +16,35 @@ pub struct Animation {
     pub frame_rate: f32,
 }
 
+impl Animation {
+    /// Returns how long each frame is shown, `None` if `frame_rate` isn't a positive number.
+    pub fn frame_duration(&self) -> Option<Duration> {
+        if !self.frame_rate.is_finite() || self.frame_rate <= 0. {
+            return None;
+        }
+        Duration::try_from_secs_f32(1. / self.frame_rate).ok()
+    }
+}
+
+/// Returns the timer that moves `animation` to its next frame. Scenes are only checked when
+/// they're compiled, so animations with an invalid `frame_rate` stay on their first frame instead.
+pub fn frame_timer(animation: Option<&Animation>) -> Timer {
+    let animation = match animation {
+        Some(v) => v,
+        None => return Timer::new(Duration::default(), TimerMode::Repeating),
+    };
+
+    match animation.frame_duration() {
+        Some(duration) => Timer::new(duration, TimerMode::Repeating),
+        None => {
+            println!("invalid animation frame rate: {}", animation.frame_rate);
+            let mut timer = Timer::new(Duration::default(), TimerMode::Repeating);
+            timer.pause();
+            timer
+        }
+    }
+}
+
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, animate_sprite.run_if(in_state(AppState::InGame)));
@@ -104,3 +134,49 @@ mod test {
     }
 }
 */
+
+#[cfg(test)]
+mod frame_rate_test {
+    use super::*;
+
+    fn animation(frame_rate: f32) -> Animation {
+        Animation {
+            first: 0,
+            last: 1,
+            flip_x: false,
+            flip_y: false,
+            frame_rate,
+        }
+    }
+
+    #[test]
+    fn invalid_frame_rates_stay_still() {
+        for frame_rate in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let mut timer = frame_timer(Some(&animation(frame_rate)));
+            assert!(timer.paused());
+            assert!(!timer.tick(Duration::from_secs(1)).just_finished());
+        }
+    }
+
+    #[test]
+    fn changing_to_an_invalid_frame_rate_stays_still() {
+        let mut animator = components::Animator {
+            current_state: "idle".to_string(),
+            state_queue: Vec::default(),
+            animations: [
+                ("idle".to_string(), animation(4.0)),
+                ("broken".to_string(), animation(0.0)),
+            ]
+            .into_iter()
+            .collect(),
+            timer: frame_timer(Some(&animation(4.0))),
+        };
+
+        animator.update_state("broken".to_string());
+        assert!(animator.timer.paused());
+
+        animator.update_state("idle".to_string());
+        assert!(!animator.timer.paused());
+        assert_eq!(animator.timer.duration(), Duration::from_millis(250));
+    }
+}
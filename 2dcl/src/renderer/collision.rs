@@ -1,10 +1,11 @@
 use crate::states::AppState;
 use crate::{
-    components::{BoxCollider, LevelChange, Scene},
+    components::{BoxCollider, CircleCollider, Scene},
     resources,
 };
 use bevy::prelude::*;
 use bevy::sprite::collide_aabb::collide;
+use dcl2d_ecs_v1::collision_mask::CollisionMask;
 use dcl2d_ecs_v1::collision_type::CollisionType;
 use dcl_common::Parcel;
 
@@ -13,17 +14,34 @@ pub const TILE_SIZE: f32 = 1.0;
 pub struct CollisionResult {
     pub hit: bool,
     pub collision_type: CollisionType,
-    pub level_change: Option<LevelChange>,
+    pub entity: Option<Entity>,
 }
 
-#[derive(Default, Clone)]
-pub struct CollisionTile {
+/// A `MaskCollider` placed in the world.
+#[derive(Clone)]
+pub struct MaskCollision {
+    /// Location of the top left pixel of the mask.
     pub location: Vec2,
-    pub colliision_type: CollisionType,
+    pub mask: CollisionMask,
+    pub collision_type: CollisionType,
     pub entity: Option<Entity>,
+    /// Scene the mask belongs to, its masks are removed from the map when it's despawned.
+    pub scene: Entity,
+    pub level: usize,
     pub parcels: Vec<Parcel>,
 }
 
+impl MaskCollision {
+    /// Bottom left and top right corners of the mask.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let size = Vec2::new(self.mask.width() as f32, self.mask.height() as f32) * TILE_SIZE;
+        (
+            Vec2::new(self.location.x, self.location.y - size.y),
+            Vec2::new(self.location.x + size.x, self.location.y),
+        )
+    }
+}
+
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
@@ -34,10 +52,7 @@ impl Plugin for CollisionPlugin {
 }
 
 fn setup(mut commands: Commands) {
-    commands.insert_resource(resources::CollisionMap {
-        tile_size: TILE_SIZE,
-        ..default()
-    });
+    commands.insert_resource(resources::CollisionMap::new(TILE_SIZE));
 }
 
 pub fn get_mask_collision(
@@ -46,40 +61,45 @@ pub fn get_mask_collision(
     position: &Vec3,
     size: &Vec2,
     collision_map: &resources::CollisionMap,
-    entities_with_level_change: &Query<(Entity, &LevelChange)>,
     scenes_query: &Query<&Scene>,
 ) -> CollisionResult {
-    for tile in &collision_map.tiles {
+    let min = Vec2::new(position.x - size.x / 2.0, position.y);
+    let max = Vec2::new(position.x + size.x / 2.0, position.y + size.y);
+
+    for mask_collision in collision_map.query(current_level, &min, &max) {
         if !collision_applies_for_current_parcel(
             current_parcel,
             current_level,
-            &tile.parcels,
+            &mask_collision.parcels,
             scenes_query,
         ) {
             continue;
         }
-        let min = Vec2::new(position.x - size.x / 2.0, position.y);
-        let max = Vec2::new(position.x + size.x / 2.0, position.y + size.y);
-        if tile.location.x > min.x
-            && tile.location.x < max.x
-            && tile.location.y > min.y
-            && tile.location.y < max.y
+
+        // Pixel (x, y) of the mask is at `location + (x, -y) * TILE_SIZE`, and it collides if it's
+        // strictly inside the player's box.
+        let location = mask_collision.location;
+        let mask = &mask_collision.mask;
+        let first_x = ((min.x - location.x) / TILE_SIZE).floor() + 1.0;
+        let last_x = ((max.x - location.x) / TILE_SIZE).ceil() - 1.0;
+        let first_y = ((location.y - max.y) / TILE_SIZE).floor() + 1.0;
+        let last_y = ((location.y - min.y) / TILE_SIZE).ceil() - 1.0;
+        if last_x < 0.0
+            || last_y < 0.0
+            || first_x >= mask.width() as f32
+            || first_y >= mask.height() as f32
         {
-            if tile.colliision_type == CollisionType::Trigger {
-                if let Some(entity) = tile.entity {
-                    let level_change =
-                        get_level_change_of_entity(entity, entities_with_level_change);
-                    return CollisionResult {
-                        hit: true,
-                        collision_type: tile.colliision_type.clone(),
-                        level_change,
-                    };
-                }
-            } else {
+            continue;
+        }
+
+        let columns = first_x.max(0.0) as u32..=last_x.min(mask.width() as f32 - 1.0) as u32;
+        let rows = first_y.max(0.0) as u32..=last_y.min(mask.height() as f32 - 1.0) as u32;
+        for y in rows {
+            if columns.clone().any(|x| mask.get(x, y)) {
                 return CollisionResult {
                     hit: true,
-                    collision_type: tile.colliision_type.clone(),
-                    level_change: None,
+                    collision_type: mask_collision.collision_type.clone(),
+                    entity: mask_collision.entity,
                 };
             }
         }
@@ -88,20 +108,8 @@ pub fn get_mask_collision(
     CollisionResult {
         hit: false,
         collision_type: CollisionType::Solid,
-        level_change: None,
-    }
-}
-
-pub fn get_level_change_of_entity(
-    entity: Entity,
-    entities_with_level_change: &Query<(Entity, &LevelChange)>,
-) -> Option<LevelChange> {
-    for (current_entity, level_change) in entities_with_level_change {
-        if entity == current_entity {
-            return Some(level_change.clone());
-        }
+        entity: None,
     }
-    None
 }
 
 pub fn get_box_collisions(
@@ -110,7 +118,6 @@ pub fn get_box_collisions(
     position: &Vec3,
     size: &Vec2,
     box_colliders: &Query<(Entity, &GlobalTransform, &BoxCollider)>,
-    entities_with_level_change: &Query<(Entity, &LevelChange)>,
     scenes_query: &Query<&Scene>,
 ) -> Vec<CollisionResult> {
     let mut collisions_result: Vec<CollisionResult> = Vec::new();
@@ -137,20 +144,49 @@ pub fn get_box_collisions(
         );
 
         if collision.is_some() {
-            if collider.collision_type == CollisionType::Trigger {
-                let level_change = get_level_change_of_entity(entity, entities_with_level_change);
-                collisions_result.push(CollisionResult {
-                    hit: true,
-                    collision_type: collider.collision_type.clone(),
-                    level_change,
-                });
-            } else {
-                collisions_result.push(CollisionResult {
-                    hit: true,
-                    collision_type: collider.collision_type.clone(),
-                    level_change: None,
-                });
-            }
+            collisions_result.push(CollisionResult {
+                hit: true,
+                collision_type: collider.collision_type.clone(),
+                entity: Some(entity),
+            });
+        }
+    }
+
+    collisions_result
+}
+
+pub fn get_circle_collisions(
+    current_parcel: &Parcel,
+    current_level: usize,
+    position: &Vec3,
+    size: &Vec2,
+    circle_colliders: &Query<(Entity, &GlobalTransform, &CircleCollider)>,
+    scenes_query: &Query<&Scene>,
+) -> Vec<CollisionResult> {
+    let mut collisions_result: Vec<CollisionResult> = Vec::new();
+
+    let min = Vec2::new(position.x - size.x / 2.0, position.y);
+    let max = Vec2::new(position.x + size.x / 2.0, position.y + size.y);
+
+    for (entity, transform, collider) in circle_colliders {
+        if !collision_applies_for_current_parcel(
+            current_parcel,
+            current_level,
+            &collider.parcels,
+            scenes_query,
+        ) {
+            continue;
+        }
+
+        let circle_center = transform.translation().truncate() + collider.center;
+        let closest_point = circle_center.clamp(min, max);
+
+        if closest_point.distance_squared(circle_center) < collider.radius * collider.radius {
+            collisions_result.push(CollisionResult {
+                hit: true,
+                collision_type: collider.collision_type.clone(),
+                entity: Some(entity),
+            });
         }
     }
 
@@ -163,7 +199,7 @@ pub fn get_collisions(
     position: &Vec3,
     size: &Vec2,
     box_colliders: &Query<(Entity, &GlobalTransform, &BoxCollider)>,
-    entities_with_level_change: &Query<(Entity, &LevelChange)>,
+    circle_colliders: &Query<(Entity, &GlobalTransform, &CircleCollider)>,
     scenes_query: &Query<&Scene>,
     collision_map: &resources::CollisionMap,
 ) -> Vec<CollisionResult> {
@@ -173,16 +209,22 @@ pub fn get_collisions(
         position,
         size,
         box_colliders,
-        entities_with_level_change,
         scenes_query,
     );
+    collision_results.append(&mut get_circle_collisions(
+        current_parcel,
+        current_level,
+        position,
+        size,
+        circle_colliders,
+        scenes_query,
+    ));
     collision_results.push(get_mask_collision(
         current_parcel,
         current_level,
         position,
         size,
         collision_map,
-        entities_with_level_change,
         scenes_query,
     ));
     collision_results
@@ -0,0 +1,158 @@
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use catalyst::ServerPool;
+
+use super::scene_loader::{cancel_download, DownloadQueue};
+use crate::components::{Cancelled, DownloadingScene, GettingNewestScenes};
+use crate::resources;
+use crate::states::AppState;
+
+/// How often the Catalyst peers are asked again while they're unreachable.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+const INDICATOR_FONT: &str = "fonts/Arcadepix Plus.ttf";
+const INDICATOR_FONT_SIZE: f32 = 18.0;
+const INDICATOR_BG_COLOR: Color = Color::rgba(0., 0., 0., 0.8);
+const INDICATOR_TEXT_COLOR: Color = Color::rgb(1., 0.6, 0.2);
+
+pub struct ConnectivityPlugin;
+
+impl Plugin for ConnectivityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, catalyst_peers_checker)
+            .add_systems(
+                Update,
+                (
+                    connectivity_checker,
+                    reconnection_checker,
+                    offline_indicator,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct OfflineIndicator;
+
+/// Goes offline when every Catalyst peer is down, cancelling the downloads in progress so the
+/// scenes waiting for them stop their loading animations.
+fn connectivity_checker(
+    mut commands: Commands,
+    mut connectivity: ResMut<resources::Connectivity>,
+    mut download_queue: ResMut<DownloadQueue>,
+    peers: Res<resources::CatalystPeers>,
+    downloads_query: Query<
+        Entity,
+        (
+            Or<(With<DownloadingScene>, With<GettingNewestScenes>)>,
+            Without<Cancelled>,
+        ),
+    >,
+) {
+    let unreachable = peers.is_unreachable();
+    if connectivity.unreachable == unreachable {
+        return;
+    }
+
+    connectivity.unreachable = unreachable;
+    if connectivity.is_offline() {
+        for entity in &downloads_query {
+            cancel_download(&mut commands, entity);
+        }
+        download_queue.clear_requested();
+    }
+}
+
+/// Finds out which Catalyst peers are up before the first scenes are downloaded.
+fn catalyst_peers_checker(
+    peers: Res<resources::CatalystPeers>,
+    connectivity: Res<resources::Connectivity>,
+) {
+    if connectivity.forced_offline {
+        return;
+    }
+
+    let pool = peers.0.clone();
+    AsyncComputeTaskPool::get()
+        .spawn(async move { check_catalyst_peers(&pool) })
+        .detach();
+}
+
+fn reconnection_checker(
+    connectivity: Res<resources::Connectivity>,
+    peers: Res<resources::CatalystPeers>,
+    mut last_check: Local<Option<Instant>>,
+) {
+    if connectivity.forced_offline || !connectivity.unreachable {
+        return;
+    }
+
+    if let Some(last_check) = *last_check {
+        if last_check.elapsed() < RECONNECT_INTERVAL {
+            return;
+        }
+    }
+    *last_check = Some(Instant::now());
+
+    let pool = peers.0.clone();
+    AsyncComputeTaskPool::get()
+        .spawn(async move { check_catalyst_peers(&pool) })
+        .detach();
+}
+
+#[tokio::main]
+async fn check_catalyst_peers(pool: &ServerPool) {
+    pool.check_health().await;
+}
+
+fn offline_indicator(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    connectivity: Res<resources::Connectivity>,
+    indicator_query: Query<Entity, With<OfflineIndicator>>,
+) {
+    if !connectivity.is_changed() {
+        return;
+    }
+
+    for entity in &indicator_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !connectivity.is_offline() {
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                background_color: BackgroundColor(INDICATOR_BG_COLOR),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.),
+                    right: Val::Px(10.),
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            OfflineIndicator,
+            Name::new("Offline indicator"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    "Offline",
+                    TextStyle {
+                        font: asset_server.load(INDICATOR_FONT),
+                        font_size: INDICATOR_FONT_SIZE,
+                        color: INDICATOR_TEXT_COLOR,
+                    },
+                ),
+                ..Default::default()
+            });
+        });
+}
@@ -84,6 +84,10 @@ fn teleport_command(
     mut player_query: Query<(&mut components::Player, &mut Transform)>,
     mut roads_data: ResMut<RoadsData>,
     scene_files_map: Res<SceneFilesMap>,
+    peers: Res<resources::CatalystPeers>,
+    lookup: Res<resources::SceneFilesLookup>,
+    cache: Res<resources::ContentCache>,
+    connectivity: Res<resources::Connectivity>,
 ) {
     if let Some(Ok(TeleportCommand { parcel_x, parcel_y })) = tp.take() {
         let (mut player, mut transform) = player_query.single_mut();
@@ -93,6 +97,10 @@ fn teleport_command(
             0,
             &mut roads_data,
             &scene_files_map,
+            &peers,
+            &lookup,
+            &cache,
+            &connectivity,
         );
         reply!(tp, "teleporting to parcel {},{}", parcel_x, parcel_y);
     }
@@ -110,7 +118,9 @@ fn reload_config(
     mut camera_query: Query<&mut OrthographicProjection>,
 ) {
     if reload_command.take().is_some() {
-        let new_config = resources::Config::from_config_file();
+        let mut new_config = resources::Config::from_config_file();
+        // Keep the server given with `--catalyst`, scenes already spawned came from it.
+        new_config.catalyst = config.catalyst.clone();
 
         update_avatar(&new_config.avatar.eth_address);
 
@@ -5,6 +5,7 @@ use dcl_common::{Parcel, Result};
 use crate::components;
 use crate::content_discovery::{find_2d_scenes, SceneDiscoveryData};
 use crate::renderer::scene_loader::get_parcel_spawn_point;
+use crate::resources;
 use crate::states::AppState;
 
 use super::scene_maker::RoadsData;
@@ -316,6 +317,10 @@ fn button_system(
     discover_ui_query: Query<Entity, With<DiscoverUI>>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    peers: Res<resources::CatalystPeers>,
+    lookup: Res<resources::SceneFilesLookup>,
+    cache: Res<resources::ContentCache>,
+    connectivity: Res<resources::Connectivity>,
 ) {
     for (interaction, mut image, button) in &mut discover_query {
         match *interaction {
@@ -350,12 +355,28 @@ fn button_system(
                 image.texture = button.settings.pressed.clone();
                 let (mut player, mut transform) = player_query.single_mut();
                 player.current_level = 0;
-                transform.translation =
-                    get_parcel_spawn_point(&button.parcel, 0, &mut roads_data, &scene_files_map);
+                transform.translation = get_parcel_spawn_point(
+                    &button.parcel,
+                    0,
+                    &mut roads_data,
+                    &scene_files_map,
+                    &peers,
+                    &lookup,
+                    &cache,
+                    &connectivity,
+                );
                 let (mut player, mut transform) = player_query.single_mut();
                 player.current_level = 0;
-                transform.translation =
-                    get_parcel_spawn_point(&button.parcel, 0, &mut roads_data, &scene_files_map);
+                transform.translation = get_parcel_spawn_point(
+                    &button.parcel,
+                    0,
+                    &mut roads_data,
+                    &scene_files_map,
+                    &peers,
+                    &lookup,
+                    &cache,
+                    &connectivity,
+                );
             }
             Interaction::Hovered => {
                 image.texture = button.settings.hovered.clone();
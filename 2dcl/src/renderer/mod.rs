@@ -14,6 +14,9 @@ pub mod player;
 use ethereum_adapter::EthAddress;
 use player::PlayerPlugin;
 
+pub mod actions;
+use actions::ActionsPlugin;
+
 pub mod animation;
 use animation::AnimationPlugin;
 
@@ -38,6 +41,9 @@ use transparency::TransparencyPlugin;
 mod screen_fade;
 use screen_fade::ScreenFadePlugin;
 
+pub mod sprite_material;
+use sprite_material::SpriteMaterialPlugin;
+
 use bevy::render::render_resource::{FilterMode, SamplerDescriptor};
 
 mod console;
@@ -47,15 +53,20 @@ mod discovery_ui;
 use crate::{metamask_login::MetamaskLoginPlugin, resources, states::AppState};
 use discovery_ui::DiscoveryUiPlugin;
 
-//mod roads_updater;
-//use roads_updater::update_roads;
+pub mod roads_updater;
+
+mod world_sync;
+use world_sync::WorldSyncPlugin;
+
+mod connectivity;
+use connectivity::ConnectivityPlugin;
 
-pub fn start() {
+pub fn start(config: resources::Config) {
     let current_path = std::env::current_exe().unwrap();
     let current_path = current_path.parent().unwrap();
 
     let mut app = App::new();
-    setup(&mut app, "2dcl".to_string(), current_path);
+    setup(&mut app, "2dcl".to_string(), current_path, config);
 
     app.add_plugins((
         SceneLoaderPlugin,
@@ -64,15 +75,20 @@ pub fn start() {
         MyConsolePlugin,
         MetamaskLoginPlugin,
         DiscoveryUiPlugin,
+        WorldSyncPlugin,
+        ConnectivityPlugin,
     ))
     .run();
 }
 
-pub fn setup<P>(app: &mut bevy::app::App, window_title: String, working_dir: P)
-where
+pub fn setup<P>(
+    app: &mut bevy::app::App,
+    window_title: String,
+    working_dir: P,
+    config: resources::Config,
+) where
     P: AsRef<Path>,
 {
-    let config = resources::Config::from_config_file();
     //update_avatar(&config.avatar.eth_address);
 
     std::env::set_current_dir(&working_dir).unwrap();
@@ -107,9 +123,15 @@ where
         PlayerPlugin,
         TransparencyPlugin,
         CollisionPlugin,
+        SpriteMaterialPlugin,
+        ActionsPlugin,
     ))
     .insert_resource(Msaa::Off)
     .add_state::<AppState>()
+    .insert_resource(resources::CatalystPeers(config.catalyst.pool()))
+    .insert_resource(resources::ContentCache(config.cache.content_cache()))
+    .init_resource::<resources::SceneFilesLookup>()
+    .insert_resource(resources::Connectivity::new(config.offline))
     .insert_resource(config);
 }
 
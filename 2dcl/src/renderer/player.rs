@@ -1,7 +1,8 @@
+use super::actions::{send_actions, Action, ActionEvent};
 use super::scene_loader::{level_changer, parcel_to_world_location};
 use super::screen_fade::FadeDirection;
 use super::{collision::*, screen_fade};
-use crate::components::{LevelChange, PlayerInputState};
+use crate::components::PlayerInputState;
 use crate::renderer::constants::*;
 use crate::states::AppState;
 use crate::{bundles, components, resources};
@@ -25,7 +26,8 @@ impl Plugin for PlayerPlugin {
         app.add_systems(OnEnter(AppState::InGame), spawn_player)
             .add_systems(
                 Update,
-                (player_interact, player_movement).run_if(in_state(AppState::InGame)),
+                (player_interact, player_movement, change_level_action)
+                    .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
                 Update,
@@ -164,7 +166,7 @@ fn player_movement(
         &mut TextureAtlasSprite,
     )>,
     box_collision_query: Query<(Entity, &GlobalTransform, &components::BoxCollider)>,
-    entities_with_level_change: Query<(Entity, &components::LevelChange)>,
+    circle_collision_query: Query<(Entity, &GlobalTransform, &components::CircleCollider)>,
     keyboard: Res<Input<KeyCode>>,
     collision_map: Res<resources::CollisionMap>,
     scenes_query: Query<&components::Scene>,
@@ -218,7 +220,7 @@ fn player_movement(
             &transform.translation,
             &target,
             &box_collision_query,
-            &entities_with_level_change,
+            &circle_collision_query,
             &scenes_query,
             &collision_map,
             &config,
@@ -233,7 +235,7 @@ fn player_movement(
             &transform.translation,
             &target,
             &box_collision_query,
-            &entities_with_level_change,
+            &circle_collision_query,
             &scenes_query,
             &collision_map,
             &config,
@@ -295,11 +297,14 @@ fn player_interact(
     mut player_query: Query<(&mut components::Player, &mut Transform)>,
     mut iteract_query: Query<&mut components::Animator, With<components::InteractIcon>>,
     box_collision_query: Query<(Entity, &GlobalTransform, &components::BoxCollider)>,
-    entities_with_level_change: Query<(Entity, &components::LevelChange)>,
+    circle_collision_query: Query<(Entity, &GlobalTransform, &components::CircleCollider)>,
+    trigger_actions_query: Query<&components::TriggerActions>,
     keyboard: Res<Input<KeyCode>>,
     collision_map: Res<resources::CollisionMap>,
     scenes_query: Query<&components::Scene>,
     mut fade: ResMut<screen_fade::Fade>,
+    mut action_events: EventWriter<ActionEvent>,
+    mut triggers_entered: Local<Vec<Entity>>,
     console: Res<ConsoleOpen>,
     config: Res<resources::Config>,
 ) {
@@ -324,18 +329,30 @@ fn player_interact(
             y: config.player.collider_size_y,
         },
         &box_collision_query,
-        &entities_with_level_change,
+        &circle_collision_query,
         &scenes_query,
         &collision_map,
     );
 
-    if keyboard.just_pressed(KeyCode::E) {
-        if let Some(level_change) = is_in_level_change_trigger(&collisions) {
-            player.input_state = PlayerInputState::LoadingLevel(level_change);
-            fade.direction = FadeDirection::FadeOut;
+    let triggers = get_triggers(&collisions);
+    let mut can_interact = false;
+
+    for trigger in triggers.iter() {
+        if let Ok(trigger_actions) = trigger_actions_query.get(*trigger) {
+            can_interact |= !trigger_actions.on_interact.is_empty();
+
+            if !triggers_entered.contains(trigger) {
+                send_actions(*trigger, &trigger_actions.on_enter, &mut action_events);
+            }
+
+            if keyboard.just_pressed(KeyCode::E) {
+                send_actions(*trigger, &trigger_actions.on_interact, &mut action_events);
+            }
         }
     }
 
+    *triggers_entered = triggers;
+
     if keyboard.just_pressed(KeyCode::Escape) && !player.level_change_stack.is_empty() {
         player.input_state = PlayerInputState::ExitingLevel;
         fade.direction = FadeDirection::FadeOut;
@@ -345,7 +362,7 @@ fn player_interact(
 
     match result {
         Ok(mut animator) => {
-            update_interact_icon_visibility(&collisions, animator.as_mut());
+            update_interact_icon_visibility(can_interact, animator.as_mut());
         }
 
         Err(e) => {
@@ -355,19 +372,11 @@ fn player_interact(
 }
 
 fn update_interact_icon_visibility(
-    collisions: &Vec<CollisionResult>,
+    can_interact: bool,
     interact_icon_animator: &mut components::Animator,
 ) {
-    let mut player_is_in_trigger = false;
-    for collision in collisions {
-        if collision.collision_type == CollisionType::Trigger {
-            player_is_in_trigger = true;
-            break;
-        }
-    }
-
     // println!("{}",interact_icon_animator.current_state);
-    if player_is_in_trigger {
+    if can_interact {
         if interact_icon_animator.current_state == "hidden"
             || interact_icon_animator.current_state == "fade_out"
         {
@@ -407,14 +416,33 @@ fn change_level(
     fade.direction = FadeDirection::FadeIn;
 }
 
-fn is_in_level_change_trigger(collisions: &Vec<CollisionResult>) -> Option<LevelChange> {
+fn get_triggers(collisions: &Vec<CollisionResult>) -> Vec<Entity> {
+    let mut triggers = Vec::default();
     for collision in collisions {
-        if collision.collision_type == CollisionType::Trigger && collision.level_change.is_some() {
-            let level_change = collision.level_change.clone().unwrap();
-            return Some(level_change);
+        if collision.hit && collision.collision_type == CollisionType::Trigger {
+            if let Some(entity) = collision.entity {
+                if !triggers.contains(&entity) {
+                    triggers.push(entity);
+                }
+            }
+        }
+    }
+    triggers
+}
+
+fn change_level_action(
+    mut player_query: Query<&mut components::Player>,
+    mut action_events: EventReader<ActionEvent>,
+    mut fade: ResMut<screen_fade::Fade>,
+) {
+    for event in action_events.iter() {
+        if let Action::ChangeLevel(level_change) = &event.action {
+            if let Ok(mut player) = player_query.get_single_mut() {
+                player.input_state = PlayerInputState::LoadingLevel(level_change.clone());
+                fade.direction = FadeDirection::FadeOut;
+            }
         }
     }
-    None
 }
 
 fn exit_level(
@@ -434,7 +462,7 @@ fn check_player_collision(
     current_location: &Vec3,
     target_location: &Vec3,
     box_collision_query: &Query<(Entity, &GlobalTransform, &components::BoxCollider)>,
-    entities_with_level_change: &Query<(Entity, &components::LevelChange)>,
+    circle_collision_query: &Query<(Entity, &GlobalTransform, &components::CircleCollider)>,
     scenes_query: &Query<&components::Scene>,
     collision_map: &resources::CollisionMap,
     config: &resources::Config,
@@ -448,7 +476,7 @@ fn check_player_collision(
             y: config.player.collider_size_y,
         },
         box_collision_query,
-        entities_with_level_change,
+        circle_collision_query,
         scenes_query,
         collision_map,
     );
@@ -468,7 +496,7 @@ fn check_player_collision(
             y: config.player.collider_size_y,
         },
         box_collision_query,
-        entities_with_level_change,
+        circle_collision_query,
         scenes_query,
         collision_map,
     );
@@ -1,115 +1,138 @@
-use std::path::{PathBuf, Path};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
-use bevy::prelude::*;
+use catalyst::entity_files::SceneFile;
 use catalyst::*;
-use dcl_common::Parcel;
-
-use crate::renderer::{scenes_io::read_3dcl_scene, scene_maker::add_road_at_parcel};
+use dcl_common::{Parcel, Result};
+use futures_util::StreamExt;
+
+use super::scene_maker::{read_roads_data, update_roads_data, RoadsData};
+
+/// Updates the roads saved in `roads.mp` with the scenes deployed to `server` since the last
+/// update. Returns the amount of parcels that were checked.
+pub async fn update_roads_file(server: &Server) -> Result<usize> {
+    // Missing or unreadable roads are rebuilt from the whole snapshot.
+    let mut roads_data = read_roads_data().unwrap_or_default();
+    let checked_parcels = update_roads(server, &mut roads_data).await?;
+    update_roads_data(&roads_data)?;
+    Ok(checked_parcels)
+}
 
-use super::scene_maker::{RoadsData, read_roads_data};
+/// Updates `roads_data` with the scenes deployed since it was last updated, using the scenes in
+/// the latest snapshot of `server`. Returns the amount of parcels that were checked.
+pub async fn update_roads(server: &Server, roads_data: &mut RoadsData) -> Result<usize> {
+    let snapshot = ContentClient::snapshot(server).await?;
+    let last_deployment_timestamp = snapshot.entities.scene.last_included_deployment_timestamp;
+    if last_deployment_timestamp <= roads_data.last_deployment_timestamp {
+        return Ok(0);
+    }
 
+    let mut scenes =
+        ContentClient::snapshot_entities_stream::<Parcel>(server, EntityType::Scene, &snapshot)
+            .await?;
 
-pub fn update_roads( 
-  mut commands: Commands)
-{
-  match read_roads_data() {
-    Ok(mut roads_data) => 
-    {
-      commands.insert_resource(roads_data.clone());
-      update_roads_async(&mut roads_data);
+    let mut changed_parcels = HashSet::new();
+    while let Some(scene) = scenes.next().await {
+        let scene = scene?;
+        if scene.local_timestamp > roads_data.last_deployment_timestamp {
+            changed_parcels.extend(scene.pointers);
+        }
     }
 
-    Err(e) => println!("error:{}", e),
-  }
+    let changed_parcels: Vec<Parcel> = changed_parcels.into_iter().collect();
+    let scene_files = ContentClient::scene_files_by_parcel(server, &changed_parcels).await?;
+    update_parcels(roads_data, &changed_parcels, &scene_files);
 
+    roads_data.last_deployment_timestamp = last_deployment_timestamp;
+    Ok(changed_parcels.len())
 }
 
-#[tokio::main]
-pub async fn update_roads_async(
-   roads_data: &mut RoadsData,
+/// Marks as roads the `parcels` owned by road scenes in `scene_files`, and the rest as not roads.
+fn update_parcels(
+    roads_data: &mut RoadsData,
+    parcels: &[Parcel],
+    scene_files: &HashMap<Parcel, Arc<SceneFile>>,
 ) {
-
-  for x in -152..152 {
-   for y in -152..152 {
-    let parcels = vec![Parcel(x,y)];
-    let server = Server::production();
-
-    let scene_files = ContentClient::scene_files_for_parcels(&server, &parcels).await.unwrap();
-
-    for scene_file in scene_files {
-      let path_str = "./assets/scenes/".to_string() + &scene_file.id.to_string();
-      let scene_path = Path::new(&path_str);
-      
-      if !scene_path.exists() {
-        std::fs::create_dir_all(format!("./assets/scenes/{}", scene_file.id));
-    
-     
-        for downloadable in scene_file.clone().content {
-            if downloadable
-                .filename
-                .to_str()
-                .unwrap()
-                .ends_with("scene.json")
-            {
-
-              
-              let filename = format!(
-                "./assets/scenes/{}/{}",
-                scene_file.id,
-                downloadable.filename.to_str().unwrap()
-                );
-
-              ContentClient::download(&server, downloadable.cid, &filename).await;
-              
-              if let Ok(scene_3d) = read_3dcl_scene(filename) {
-                
-                if scene_3d.display.title.to_lowercase().contains("road")
-                {
-                  for parcel in scene_3d.scene.parcels
-                  {
-                    add_road_at_parcel(&parcel,roads_data);
-                  }
-              
-                }
-            }
+    for parcel in parcels {
+        match scene_files.get(parcel) {
+            Some(scene_file) if is_road_scene(scene_file) => add_road_at_parcel(parcel, roads_data),
+            _ => remove_road_at_parcel(parcel, roads_data),
         }
-      }
     }
-  }
-  }
 }
+
+fn is_road_scene(scene_file: &SceneFile) -> bool {
+    scene_file
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.display.as_ref())
+        .and_then(|display| display.title.as_ref())
+        .map(|title| title.to_lowercase().contains("road"))
+        .unwrap_or(false)
+}
+
+pub fn remove_road_at_parcel(parcel: &Parcel, roads_data: &mut RoadsData) {
+    roads_data.parcel_map.remove(&(parcel.0, parcel.1));
+}
+
+pub fn add_road_at_parcel(parcel: &Parcel, roads_data: &mut RoadsData) {
+    roads_data.parcel_map.insert((parcel.0, parcel.1), ());
 }
 
-pub fn update_roads_data(new_roads_data: &RoadsData) -> Result<()> {
-  let mut serializable_roads_data = SerializableRoadsData::default();
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn scene_files(scenes: &[(&str, &[&str])]) -> HashMap<Parcel, Arc<SceneFile>> {
+        let mut scene_files = HashMap::new();
+        for (title, pointers) in scenes {
+            let scene_file = Arc::new(scene_file(title, pointers));
+            for pointer in *pointers {
+                scene_files.insert(pointer.parse().unwrap(), scene_file.clone());
+            }
+        }
+        scene_files
+    }
 
-  for key in new_roads_data.parcel_map.keys() {
-      serializable_roads_data.parcels.push(Parcel(key.0, key.1));
-  }
+    fn scene_file(title: &str, pointers: &[&str]) -> SceneFile {
+        serde_json::from_value(json!({
+            "version": "v3",
+            "type": "scene",
+            "pointers": pointers,
+            "timestamp": 0,
+            "content": [],
+            "metadata": {
+                "display": { "title": title },
+                "scene": { "base": pointers[0], "parcels": pointers }
+            }
+        }))
+        .unwrap()
+    }
 
-  let mut buf: Vec<u8> = Vec::new();
-  match serializable_roads_data.serialize(&mut Serializer::new(&mut buf)) {
-      Ok(_) => {}
-      Err(e) => return Err(Box::new(e)),
-  }
+    #[test]
+    fn parcels_of_road_scenes_are_roads() {
+        let mut roads_data = RoadsData::default();
 
-  let mut file = match File::create(&ROADS_DATA_MP_FILE) {
-      Ok(v) => v,
-      Err(e) => return Err(Box::new(e)),
-  };
+        let parcels = vec![Parcel(0, 0), Parcel(-1, 0), Parcel(0, 1)];
+        let scene_files = scene_files(&[("Road", &["0,0", "-1,0"]), ("Genesis Plaza", &["0,1"])]);
+        update_parcels(&mut roads_data, &parcels, &scene_files);
 
-  match file.write_all(&buf) {
-      Ok(v) => Ok(v),
-      Err(e) => Err(Box::new(e)),
-  }
-}
+        assert!(roads_data.parcel_map.contains_key(&(0, 0)));
+        assert!(roads_data.parcel_map.contains_key(&(-1, 0)));
+        assert!(!roads_data.parcel_map.contains_key(&(0, 1)));
+    }
 
-pub fn remove_road_at_parcel(parcel: &Parcel, roads_data: &mut RoadsData) -> Result<()> {
-  roads_data.parcel_map.remove(&(parcel.0, parcel.1));
-  update_roads_data(roads_data)
-}
+    #[test]
+    fn parcels_no_longer_owned_by_road_scenes_are_not_roads() {
+        let mut roads_data = RoadsData::default();
+        add_road_at_parcel(&Parcel(3, 3), &mut roads_data);
+        add_road_at_parcel(&Parcel(3, 4), &mut roads_data);
+
+        let scene_files = scene_files(&[("My Scene", &["3,3"])]);
+        update_parcels(&mut roads_data, &[Parcel(3, 3)], &scene_files);
 
-pub fn add_road_at_parcel(parcel: &Parcel, roads_data: &mut RoadsData) -> Result<()> {
-  roads_data.parcel_map.insert((parcel.0, parcel.1), ());
-  update_roads_data(roads_data)
-} 
\ No newline at end of file
+        assert!(!roads_data.parcel_map.contains_key(&(3, 3)));
+        assert!(roads_data.parcel_map.contains_key(&(3, 4)));
+    }
+}
@@ -1,10 +1,14 @@
-use super::collision::CollisionTile;
+use super::collision::MaskCollision;
 use super::scenes_io::{
-    get_parcel_file_data, get_scene, read_scene_file, refresh_path, SceneData, SceneFilesMap,
+    forget_incomplete_scene, get_parcel_file_data, get_scene, read_scene_file, refresh_path,
+    write_scene_contents, SceneData, SceneFilesMap,
 };
+use super::world_sync::ScenesSynced;
 use crate::bundles::{self, get_parcels_center_location, loading_animation};
+use crate::renderer::actions::Action;
 use crate::renderer::constants::*;
 use crate::renderer::scene_maker::*;
+use crate::renderer::sprite_material::SpriteMaterial;
 use crate::states::AppState;
 use crate::{
     components::{self, *},
@@ -12,25 +16,57 @@ use crate::{
 };
 use bevy::prelude::*;
 use bevy::tasks::AsyncComputeTaskPool;
+use bevy::utils::{HashMap, HashSet};
 use catalyst::entity_files::ContentFile;
-use catalyst::{ContentClient, Server};
+use catalyst::{ContentCache, ServerPool};
+use dcl2d_ecs_v1::activation::Activation;
+use dcl2d_ecs_v1::collision_mask::CollisionMask;
 use dcl_common::Parcel;
 use futures_lite::future;
 use image::io::Reader as ImageReader;
-use image::{DynamicImage, ImageFormat};
+use image::ImageFormat;
 use rmp_serde::*;
 use serde::Deserialize;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 pub struct SceneLoaderPlugin;
 
+/// How much the movement direction of the player weighs in the priority of a download, from `0`
+/// (only the distance matters) to `1` (parcels ahead are always first).
+const DOWNLOAD_DIRECTION_WEIGHT: f32 = 0.5;
+
+/// How aligned with the movement direction of the player a parcel has to be to be prefetched,
+/// `0.5` being a cone of 120 degrees in front of the player.
+const PREFETCH_MIN_ALIGNMENT: f32 = 0.5;
+
+/// Parcels looked up and downloaded by each download task.
+const PARCELS_PER_DOWNLOAD: usize = 4;
+
+/// How long to wait before downloading a parcel again after its download failed, doubled with
+/// every failure up to `MAX_DOWNLOAD_RETRY_DELAY`.
+const DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(2);
+const MAX_DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Parcels around the player, and the parcels without a downloaded scene that should be
+/// downloaded, the most urgent first.
 #[derive(Default, Resource)]
 pub struct DownloadQueue {
+    /// Parcels inside `min_render_distance` that aren't spawned yet.
     parcels: Vec<Parcel>,
+    /// Parcels to look up and download, in order of priority.
+    parcels_to_download: Vec<Parcel>,
+    /// Parcels already looked up or being looked up.
+    requested: HashSet<Parcel>,
+    /// Parcels whose download failed, with when they can be downloaded again and how many times
+    /// in a row they failed.
+    failed: HashMap<Parcel, (Instant, u32)>,
+    last_location: Option<Vec2>,
+    /// Direction the player is moving towards, or zero before it moves.
+    direction: Vec2,
 }
 
 #[derive(Default, Resource)]
@@ -64,9 +100,11 @@ impl Plugin for SceneLoaderPlugin {
                 Update,
                 (
                     scene_version_downloader,
+                    download_canceller,
                     downloading_scenes_task_handler,
                     downloading_version_task_handler,
                     loading_sprites_task_handler,
+                    loading_sprite_sheets_handler,
                     loading_animation,
                 )
                     .run_if(in_state(AppState::InGame)),
@@ -140,7 +178,7 @@ pub fn level_changer(
                         }
 
                         //Clear collision map
-                        collision_map.tiles.clear();
+                        collision_map.clear();
 
                         //Spawn correct level
                         let mut de = Deserializer::from_read_ref(&scene.serialized_data);
@@ -178,6 +216,7 @@ pub fn scene_manager(
     scene_query: Query<&mut components::Scene, Without<components::Player>>,
     mut download_queue: ResMut<DownloadQueue>,
     spawning_queue: Res<SpawningQueue>,
+    scene_files_map: Res<SceneFilesMap>,
     config: Res<resources::Config>,
 ) {
     //Find the player
@@ -197,6 +236,36 @@ pub fn scene_manager(
         return;
     }
 
+    let location = player_query.1.translation().truncate();
+    download_queue.update_direction(location);
+
+    //Prioritise the parcels without a downloaded scene, prefetching the ones ahead of the player
+    let max_render_distance = config.world.max_render_distance as i16;
+    download_queue
+        .requested
+        .retain(|parcel| parcel_distance(&player_parcel, parcel) < max_render_distance);
+    download_queue
+        .failed
+        .retain(|parcel, _| parcel_distance(&player_parcel, parcel) < max_render_distance);
+
+    let direction = download_queue.direction;
+    let requested = &download_queue.requested;
+    let failed = &download_queue.failed;
+    let parcels_to_download = get_parcels_to_download(
+        &player_parcel,
+        direction,
+        config.world.min_render_distance,
+        config.world.prefetch_distance,
+    )
+    .into_iter()
+    .filter(|parcel| {
+        !requested.contains(parcel)
+            && !matches!(failed.get(parcel), Some((retry_at, _)) if *retry_at > Instant::now())
+            && get_parcel_file_data(parcel, &scene_files_map).is_none()
+    })
+    .collect();
+    download_queue.parcels_to_download = parcels_to_download;
+
     let mut parcels_to_spawn =
         get_all_parcels_around(&player_parcel, config.world.min_render_distance);
 
@@ -227,32 +296,73 @@ pub fn scene_version_downloader(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut roads_data: ResMut<RoadsData>,
-    scene_files_map: Res<SceneFilesMap>,
+    mut scene_files_map: ResMut<SceneFilesMap>,
     mut collision_map: ResMut<resources::CollisionMap>,
     mut download_queue: ResMut<DownloadQueue>,
     mut spawning_queue: ResMut<SpawningQueue>,
+    peers: Res<resources::CatalystPeers>,
+    lookup: Res<resources::SceneFilesLookup>,
+    cache: Res<resources::ContentCache>,
+    connectivity: Res<resources::Connectivity>,
+    config: Res<resources::Config>,
+    downloads_query: Query<(), Or<(With<DownloadingScene>, With<GettingNewestScenes>)>>,
 ) {
+    //Download the scenes that weren't downloaded yet, the rest are kept up to date by the world sync.
+    //While offline, the scenes already downloaded are all there is.
+    let thread_pool = AsyncComputeTaskPool::get();
+    let mut downloads = downloads_query.iter().count();
+    while downloads < config.world.max_concurrent_downloads
+        && !download_queue.parcels_to_download.is_empty()
+        && !connectivity.is_offline()
+    {
+        let batch_size = PARCELS_PER_DOWNLOAD.min(download_queue.parcels_to_download.len());
+        let parcels_to_download: Vec<Parcel> = download_queue
+            .parcels_to_download
+            .drain(..batch_size)
+            .collect();
+        download_queue
+            .requested
+            .extend(parcels_to_download.iter().cloned());
+
+        let scene_files_map_clone = scene_files_map.as_ref().clone();
+        let pool = peers.0.clone();
+        let lookup = lookup.clone();
+        let cache = cache.0.clone();
+        let parcels = parcels_to_download.clone();
+        let task_get_scene_files_to_download = thread_pool.spawn(async move {
+            match get_newest_scene_files_for_parcels(
+                &pool,
+                &lookup,
+                &cache,
+                parcels_to_download,
+                &scene_files_map_clone,
+            ) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    println!("{:?}", e);
+                    None
+                }
+            }
+        });
+
+        commands.spawn((
+            GettingNewestScenes {
+                task: task_get_scene_files_to_download,
+                parcels,
+            },
+            AroundPlayer,
+        ));
+        downloads += 1;
+    }
+
     if download_queue.parcels.is_empty() {
         return;
     }
 
-    //Download scenes
-    let thread_pool = AsyncComputeTaskPool::get();
-    let parcels_to_download = download_queue.parcels.clone();
-
-    let scene_files_map_clone = scene_files_map.clone();
-    let task_get_scene_files_to_download = thread_pool.spawn(async move {
-        match get_newest_scene_files_for_parcels(parcels_to_download, &scene_files_map_clone) {
-            Ok(v) => Some(v),
-            Err(e) => {
-                println!("{:?}", e);
-                None
-            }
-        }
-    });
-
     //Spawm default scene or previous version
     for parcel_to_download in &download_queue.parcels {
+        //Scenes with files evicted from the cache are downloaded again, the default one is shown meanwhile
+        forget_incomplete_scene(parcel_to_download, &mut scene_files_map);
         match get_scene(&mut roads_data, &scene_files_map, parcel_to_download) {
             Some(scene_data) => {
                 spawn_scene(
@@ -276,10 +386,6 @@ pub fn scene_version_downloader(
         }
     }
 
-    commands.spawn(GettingNewestScenes {
-        task: task_get_scene_files_to_download,
-    });
-
     download_queue.parcels.clear();
 }
 
@@ -310,6 +416,138 @@ fn get_all_parcels_around(parcel: &Parcel, distance: usize) -> Vec<Parcel> {
     }
     parcels
 }
+
+/// Cancels the downloads started for the parcels around the player whose parcels are now all beyond
+/// `max_render_distance`, as the player left them behind.
+fn download_canceller(
+    mut commands: Commands,
+    player_query: Query<&components::Player>,
+    downloads_query: Query<(Entity, &DownloadingScene), (With<AroundPlayer>, Without<Cancelled>)>,
+    versions_query: Query<(Entity, &GettingNewestScenes), (With<AroundPlayer>, Without<Cancelled>)>,
+    config: Res<resources::Config>,
+) {
+    let player = match player_query.get_single() {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let max_render_distance = config.world.max_render_distance as i16;
+    let left_behind = |parcels: &Vec<Parcel>| {
+        parcels
+            .iter()
+            .all(|parcel| parcel_distance(&player.current_parcel, parcel) >= max_render_distance)
+    };
+
+    for (entity, downloading_scene) in &downloads_query {
+        if left_behind(&downloading_scene.parcels) {
+            cancel_download(&mut commands, entity);
+        }
+    }
+    for (entity, newest_scenes) in &versions_query {
+        if left_behind(&newest_scenes.parcels) {
+            cancel_download(&mut commands, entity);
+        }
+    }
+}
+
+/// Stops the loading animation of the download of `entity` and drops what it finds once it
+/// finishes. Dropping its task wouldn't stop the thread running it, so the download keeps its
+/// place among the `max_concurrent_downloads` until then.
+pub fn cancel_download(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .insert((Cancelled, Visibility::Hidden))
+        .remove::<components::Loading>();
+}
+
+impl DownloadQueue {
+    /// Forgets which parcels were looked up, so they're downloaded again if they're still needed.
+    pub fn clear_requested(&mut self) {
+        self.requested.clear();
+    }
+
+    /// Makes the `parcels` whose download failed wait before they're downloaded again, longer the
+    /// more times they failed.
+    fn download_failed(&mut self, parcels: &[Parcel]) {
+        let now = Instant::now();
+        for parcel in parcels {
+            self.requested.remove(parcel);
+            let failures = self.failed.get(parcel).map_or(0, |(_, failures)| *failures) + 1;
+            let delay = DOWNLOAD_RETRY_DELAY
+                .saturating_mul(2u32.saturating_pow(failures - 1))
+                .min(MAX_DOWNLOAD_RETRY_DELAY);
+            self.failed.insert(parcel.clone(), (now + delay, failures));
+        }
+    }
+
+    fn download_succeeded(&mut self, parcels: &[Parcel]) {
+        for parcel in parcels {
+            self.failed.remove(parcel);
+        }
+    }
+
+    /// Updates the direction the player is moving towards with its new `location`.
+    fn update_direction(&mut self, location: Vec2) {
+        if let Some(last_location) = self.last_location {
+            let movement = location - last_location;
+            if movement.length() > PARCEL_SIZE_X {
+                // Teleports don't say where the player is heading.
+                self.direction = Vec2::ZERO;
+            } else if movement != Vec2::ZERO {
+                self.direction = movement.normalize();
+            }
+        }
+        self.last_location = Some(location);
+    }
+}
+
+/// Returns the parcels inside `min_render_distance` of `parcel` and the ones up to
+/// `prefetch_distance` further in `direction`, the most urgent first.
+fn get_parcels_to_download(
+    parcel: &Parcel,
+    direction: Vec2,
+    min_render_distance: usize,
+    prefetch_distance: usize,
+) -> Vec<Parcel> {
+    let mut parcels: Vec<(f32, Parcel)> =
+        get_all_parcels_around(parcel, min_render_distance + prefetch_distance)
+            .into_iter()
+            .filter(|other| {
+                parcel_distance(parcel, other) < min_render_distance as i16
+                    || alignment(parcel, direction, other) >= PREFETCH_MIN_ALIGNMENT
+            })
+            .map(|other| (download_priority(parcel, direction, &other), other))
+            .collect();
+
+    parcels.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    parcels.into_iter().map(|(_, parcel)| parcel).collect()
+}
+
+/// Lower is more urgent: the parcels closer to `parcel` first, and the ones in `direction` before
+/// the ones behind.
+fn download_priority(parcel: &Parcel, direction: Vec2, other: &Parcel) -> f32 {
+    let distance = parcel_offset(parcel, other).length();
+    distance * (1. - DOWNLOAD_DIRECTION_WEIGHT * alignment(parcel, direction, other))
+}
+
+/// How much `other` is in `direction` from `parcel`, from `-1` (right behind) to `1` (right
+/// ahead).
+fn alignment(parcel: &Parcel, direction: Vec2, other: &Parcel) -> f32 {
+    parcel_offset(parcel, other)
+        .try_normalize()
+        .map(|offset| offset.dot(direction))
+        .unwrap_or_default()
+}
+
+fn parcel_offset(parcel: &Parcel, other: &Parcel) -> Vec2 {
+    Vec2::new((other.0 - parcel.0) as f32, (other.1 - parcel.1) as f32)
+}
+
+/// Distance in parcels, the way `get_all_parcels_around` counts it.
+fn parcel_distance(parcel: &Parcel, other: &Parcel) -> i16 {
+    (other.0 - parcel.0).abs().max((other.1 - parcel.1).abs())
+}
+
 pub fn world_location_to_parcel(location: &Vec3) -> Parcel {
     Parcel(
         (location.x / PARCEL_SIZE_X).round() as i16,
@@ -327,10 +565,10 @@ pub fn parcel_to_world_location(parcel: Parcel) -> Vec3 {
 
 #[tokio::main]
 pub async fn download_scene_files(
+    pool: &ServerPool,
+    cache: &ContentCache,
     scene_files: Vec<catalyst::entity_files::SceneFile>,
 ) -> dcl_common::Result<Vec<PathBuf>> {
-    let server = Server::production();
-
     let mut scene_paths: Vec<PathBuf> = Vec::new();
 
     for scene_file in scene_files {
@@ -341,6 +579,7 @@ pub async fn download_scene_files(
         let path_str = "./assets/scenes/".to_string() + &id_str;
         let scene_path = Path::new(&path_str);
 
+        let mut filenames = Vec::new();
         for downloadable in scene_file.content {
             let filename = format!(
                 "./assets/scenes/{}/{}",
@@ -348,31 +587,63 @@ pub async fn download_scene_files(
                 downloadable.filename.to_str().unwrap()
             );
 
-            ContentClient::download(&server, downloadable.cid, &filename).await?;
+            download_from_pool(pool, cache, &downloadable, &filename).await?;
+            filenames.push(downloadable.filename.to_str().unwrap().to_string());
         }
+        write_scene_contents(scene_path, &filenames)?;
         scene_paths.push(scene_path.to_path_buf());
     }
 
     Ok(scene_paths)
 }
 
+/// Places `content` in `filename` from `cache`, downloading it from the first server of `pool`
+/// that has it available if it isn't cached.
+async fn download_from_pool(
+    pool: &ServerPool,
+    cache: &ContentCache,
+    content: &ContentFile,
+    filename: &str,
+) -> catalyst::Result<()> {
+    if cache.download_cached(&content.cid, filename)? {
+        return Ok(());
+    }
+
+    pool.run_until_found(|server| {
+        let cid = content.cid.clone();
+        async move { cache.download(&server, cid, filename).await }
+    })
+    .await
+}
+
+/// Returns the path of `content` in `cache`, downloading it from the first server of `pool` that
+/// has it available if it isn't cached.
+async fn fetch_from_pool(
+    pool: &ServerPool,
+    cache: &ContentCache,
+    content: &ContentFile,
+) -> catalyst::Result<PathBuf> {
+    if let Some(path) = cache.get(&content.cid) {
+        return Ok(path);
+    }
+
+    pool.run_until_found(|server| async move { cache.fetch(&server, &content.cid).await })
+        .await
+}
+
 #[tokio::main]
 pub async fn get_newest_scene_files_for_parcels(
+    pool: &ServerPool,
+    lookup: &resources::SceneFilesLookup,
+    cache: &ContentCache,
     parcels: Vec<Parcel>,
     scene_files_map: &SceneFilesMap,
 ) -> dcl_common::Result<(Vec<catalyst::entity_files::SceneFile>, Vec<Parcel>)> {
     let mut scene_files_to_download: Vec<catalyst::entity_files::SceneFile> = Vec::new();
     let mut parcels_to_download: Vec<Parcel> = Vec::new();
 
-    let server = Server::production();
-    let scene_files = ContentClient::scene_files_for_parcels(&server, &parcels).await?;
+    let scene_files = lookup.scene_files(pool, &parcels).await?;
     for scene_file in scene_files {
-        let id_str = match &scene_file.id {
-            Some(id) => id.to_string(),
-            None => String::default(),
-        };
-        let path_str = "./assets/scenes/".to_string() + &id_str;
-        let scene_path = Path::new(&path_str);
         let mut downloadable_2dcl: Option<ContentFile> = None;
 
         for downloadable in scene_file.clone().content {
@@ -387,17 +658,8 @@ pub async fn get_newest_scene_files_for_parcels(
             }
         }
 
-        if !scene_path.exists() {
-            fs::create_dir_all(format!("./assets/scenes/{}", id_str))?;
-        }
-
         if let Some(downloadable_2dcl) = downloadable_2dcl {
-            let filename = format!(
-                "./assets/scenes/{}/{}-temp",
-                id_str,
-                downloadable_2dcl.filename.to_str().unwrap()
-            );
-            ContentClient::download(&server, downloadable_2dcl.cid, &filename).await?;
+            let filename = fetch_from_pool(pool, cache, &downloadable_2dcl).await?;
             let mut parcels = Vec::default();
             for parcel in &scene_file.pointers {
                 if let Ok(parcel) = Parcel::from_str(parcel) {
@@ -430,38 +692,28 @@ pub async fn get_newest_scene_files_for_parcels(
                     parcels_to_download.append(&mut parcels);
                 }
             }
-            match std::fs::remove_file(filename) {
-                Ok(_) => {}
-                Err(e) => println!("{}", e),
-            };
-        }
-
-        if scene_path.read_dir()?.next().is_none() {
-            std::fs::remove_dir(scene_path)?;
         }
     }
     Ok((scene_files_to_download, parcels_to_download))
 }
 
 #[tokio::main]
-pub async fn download_level_spawn_point(parcel: &Parcel, level_id: usize) -> Vec3 {
-    let server = Server::production();
-    let scene_files =
-        match ContentClient::scene_files_for_parcels(&server, &vec![parcel.clone()]).await {
-            Ok(v) => v,
-            Err(_) => {
-                let scene_data = SceneData { ..default() };
-                return get_parcels_center_location(&scene_data.scene.parcels);
-            }
-        };
+pub async fn download_level_spawn_point(
+    pool: &ServerPool,
+    lookup: &resources::SceneFilesLookup,
+    cache: &ContentCache,
+    parcel: &Parcel,
+    level_id: usize,
+) -> Vec3 {
+    let scene_files = match lookup.scene_files(pool, std::slice::from_ref(parcel)).await {
+        Ok(v) => v,
+        Err(_) => {
+            let scene_data = SceneData { ..default() };
+            return get_parcels_center_location(&scene_data.scene.parcels);
+        }
+    };
 
     for scene_file in scene_files {
-        let id_str = match &scene_file.id {
-            Some(id) => id.to_string(),
-            None => String::default(),
-        };
-        let path_str = "./assets/scenes/".to_string() + &id_str;
-        let scene_path = Path::new(&path_str);
         let mut downloadable_2dcl: Option<ContentFile> = None;
 
         for downloadable in scene_file.clone().content {
@@ -476,25 +728,11 @@ pub async fn download_level_spawn_point(parcel: &Parcel, level_id: usize) -> Vec
             }
         }
 
-        if !scene_path.exists()
-            && fs::create_dir_all(format!("./assets/scenes/{}", id_str)).is_err()
-        {
-            continue;
-        }
-
         if let Some(downloadable_2dcl) = downloadable_2dcl {
-            let filename = format!(
-                "./assets/scenes/{}/{}-temp",
-                id_str,
-                downloadable_2dcl.filename.to_str().unwrap()
-            );
-
-            if ContentClient::download(&server, downloadable_2dcl.cid, &filename)
-                .await
-                .is_err()
-            {
-                continue;
-            }
+            let filename = match fetch_from_pool(pool, cache, &downloadable_2dcl).await {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
 
             if let Some(scene_2d) = read_scene_file(filename) {
                 let scene_data = SceneData {
@@ -523,24 +761,59 @@ pub async fn download_level_spawn_point(parcel: &Parcel, level_id: usize) -> Vec
 
 pub fn loading_sprites_task_handler(
     mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SpriteMaterial>>,
     mut tasks_loading_sprites: Query<(Entity, &mut LoadingSpriteRenderer)>,
 ) {
     for (entity, mut sprite) in &mut tasks_loading_sprites {
         if let Some((texture, image_size)) = future::block_on(future::poll_once(&mut sprite.task)) {
             commands.entity(entity).remove::<LoadingSpriteRenderer>();
-            commands
-                .entity(entity)
-                .insert(bundles::SpriteRenderer::from_texture(
-                    &sprite.sprite_renderer_component,
-                    &sprite.transform,
-                    texture,
-                    image_size,
-                    &sprite.parcels,
-                    sprite.level_id,
-                ));
+            let sprite_renderer = bundles::SpriteRenderer::from_texture(
+                &sprite.sprite_renderer_component,
+                &sprite.transform,
+                texture,
+                image_size,
+                &sprite.parcels,
+                sprite.level_id,
+            );
+
+            match &sprite.sprite_renderer_component.blend_mode {
+                Some(blend_mode) => {
+                    commands.entity(entity).insert(
+                        bundles::BlendedSpriteRenderer::from_sprite_renderer(
+                            sprite_renderer,
+                            image_size,
+                            blend_mode,
+                            &mut meshes,
+                            &mut materials,
+                        ),
+                    );
+                }
+                None => {
+                    commands.entity(entity).insert(sprite_renderer);
+                }
+            }
         }
     }
 }
+pub fn loading_sprite_sheets_handler(
+    mut commands: Commands,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    loading_sprite_sheets: Query<(Entity, &LoadingSpriteSheetRenderer)>,
+) {
+    for (entity, sprite_sheet) in &loading_sprite_sheets {
+        commands
+            .entity(entity)
+            .remove::<LoadingSpriteSheetRenderer>()
+            .insert(bundles::Animator::from_sprite_sheet_renderer(
+                &sprite_sheet.sprite_sheet_renderer_component,
+                &sprite_sheet.transform,
+                sprite_sheet.texture.clone(),
+                &mut texture_atlases,
+            ));
+    }
+}
+
 fn downloading_scenes_task_handler(
     mut commands: Commands,
     mut collision_map: ResMut<resources::CollisionMap>,
@@ -548,13 +821,30 @@ fn downloading_scenes_task_handler(
     mut scene_files_map: ResMut<SceneFilesMap>,
     mut despawned_entities: ResMut<DespawnedEntities>,
     asset_server: Res<AssetServer>,
-    mut tasks_downloading_scenes: Query<(Entity, &mut DownloadingScene)>,
+    mut tasks_downloading_scenes: Query<(
+        Entity,
+        &mut DownloadingScene,
+        Option<&AroundPlayer>,
+        Option<&WorldSyncDownload>,
+    )>,
     scenes_query: Query<(Entity, &components::Scene)>,
     mut spawning_queue: ResMut<SpawningQueue>,
+    mut download_queue: ResMut<DownloadQueue>,
+    mut scenes_synced: EventWriter<ScenesSynced>,
 ) {
-    for (entity, mut downloading_scene) in &mut tasks_downloading_scenes {
+    //Cancelled downloads are handled too, their files are already there
+    for (entity, mut downloading_scene, around_player, world_sync_download) in
+        &mut tasks_downloading_scenes
+    {
         if let Some(new_paths) = future::block_on(future::poll_once(&mut downloading_scene.task)) {
             commands.entity(entity).despawn_recursive();
+            if around_player.is_some() {
+                match new_paths {
+                    Some(_) => download_queue.download_succeeded(&downloading_scene.parcels),
+                    //Download the parcels again later if they're still around the player
+                    None => download_queue.download_failed(&downloading_scene.parcels),
+                }
+            }
             if let Some(new_paths) = new_paths {
                 for new_path in new_paths {
                     match refresh_path(new_path.clone(), &mut scene_files_map) {
@@ -562,6 +852,11 @@ fn downloading_scenes_task_handler(
                         Err(e) => println!("{}", e),
                     }
                 }
+                if world_sync_download.is_some() {
+                    scenes_synced.send(ScenesSynced {
+                        parcels: downloading_scene.parcels.clone(),
+                    });
+                }
             }
 
             for (entity, scene) in &scenes_query {
@@ -574,6 +869,7 @@ fn downloading_scenes_task_handler(
                                 if scene.timestamp.0 != scene_data.scene.timestamp {
                                     despawned_entities.entities.push(entity);
                                     commands.entity(entity).despawn_recursive();
+                                    collision_map.remove_scene(entity);
                                     spawn_scene(
                                         &mut commands,
                                         &asset_server,
@@ -594,17 +890,47 @@ fn downloading_scenes_task_handler(
 
 fn downloading_version_task_handler(
     mut commands: Commands,
-    mut tasks_check_scene_version: Query<(Entity, &mut GettingNewestScenes)>,
+    mut tasks_check_scene_version: Query<(
+        Entity,
+        &mut GettingNewestScenes,
+        Option<&AroundPlayer>,
+        Option<&WorldSyncDownload>,
+        Option<&Cancelled>,
+    )>,
     asset_server: Res<AssetServer>,
+    peers: Res<resources::CatalystPeers>,
+    cache: Res<resources::ContentCache>,
+    mut download_queue: ResMut<DownloadQueue>,
+    mut scenes_synced: EventWriter<ScenesSynced>,
 ) {
-    for (entity, mut newest_scenes) in &mut tasks_check_scene_version {
+    for (entity, mut newest_scenes, around_player, world_sync_download, cancelled) in
+        &mut tasks_check_scene_version
+    {
         if let Some(task_result) = future::block_on(future::poll_once(&mut newest_scenes.task)) {
             commands.entity(entity).despawn_recursive();
+            //The parcels were left behind, so there's nothing to download
+            if cancelled.is_some() {
+                continue;
+            }
 
             if let Some((scene_files, parcels)) = task_result {
+                //The parcels already up to date are synced, the rest once they're downloaded
+                if world_sync_download.is_some() {
+                    scenes_synced.send(ScenesSynced {
+                        parcels: newest_scenes
+                            .parcels
+                            .iter()
+                            .filter(|parcel| !parcels.contains(parcel))
+                            .cloned()
+                            .collect(),
+                    });
+                }
+
                 let thread_pool = AsyncComputeTaskPool::get();
+                let pool = peers.0.clone();
+                let cache = cache.0.clone();
                 let task_download_scene_files = thread_pool.spawn(async move {
-                    match download_scene_files(scene_files) {
+                    match download_scene_files(&pool, &cache, scene_files) {
                         Ok(v) => Some(v),
                         Err(e) => {
                             println!("{:?}", e);
@@ -614,18 +940,29 @@ fn downloading_version_task_handler(
                 });
 
                 if !parcels.is_empty() {
-                    commands.spawn(bundles::DownloadingScene::from_task_and_parcels(
-                        task_download_scene_files,
-                        parcels,
-                        &asset_server,
-                    ));
+                    let mut downloading_scene =
+                        commands.spawn(bundles::DownloadingScene::from_task_and_parcels(
+                            task_download_scene_files,
+                            parcels,
+                            &asset_server,
+                        ));
+                    if around_player.is_some() {
+                        downloading_scene.insert(AroundPlayer);
+                    }
+                    if world_sync_download.is_some() {
+                        downloading_scene.insert(WorldSyncDownload);
+                    }
                 }
+            } else if around_player.is_some() {
+                //Look the parcels up again later if they're still around the player
+                download_queue.download_failed(&newest_scenes.parcels);
             }
         }
     }
 }
 fn default_scenes_despawner(
     mut commands: Commands,
+    mut collision_map: ResMut<resources::CollisionMap>,
     mut despawned_entities: ResMut<DespawnedEntities>,
     player_query: Query<(&components::Player, &GlobalTransform)>,
     scenes_query: Query<(Entity, &components::Scene)>,
@@ -648,6 +985,7 @@ fn default_scenes_despawner(
         }
         despawned_entities.entities.push(entity);
         commands.entity(entity).despawn_recursive();
+        collision_map.remove_scene(entity);
     }
 
     for (entity_1, scene_1) in &scenes_query {
@@ -665,10 +1003,12 @@ fn default_scenes_despawner(
                             if scene_1.is_default {
                                 despawned_entities.entities.push(entity_1);
                                 commands.entity(entity_1).despawn_recursive();
+                                collision_map.remove_scene(entity_1);
                                 break 'outer;
                             } else if scene_2.is_default {
                                 despawned_entities.entities.push(entity_2);
                                 commands.entity(entity_2).despawn_recursive();
+                                collision_map.remove_scene(entity_2);
                                 break 'outer;
                             }
                         }
@@ -708,6 +1048,7 @@ pub fn spawn_level(
     commands: &mut Commands,
     asset_server: &AssetServer,
     scene_data: &SceneData,
+    scene_entity: Entity,
     level_id: usize,
     collision_map: &mut resources::CollisionMap,
     timestamp: SystemTime,
@@ -741,7 +1082,9 @@ pub fn spawn_level(
             asset_server,
             collision_map,
             entity,
+            Vec3::ZERO,
             scene_data,
+            scene_entity,
             level_id,
         );
         commands.entity(level_entity).add_child(spawned_entity);
@@ -755,6 +1098,10 @@ pub fn get_parcel_spawn_point(
     level_id: usize,
     roads_data: &mut RoadsData,
     scene_files_map: &SceneFilesMap,
+    pool: &ServerPool,
+    lookup: &resources::SceneFilesLookup,
+    cache: &ContentCache,
+    connectivity: &resources::Connectivity,
 ) -> Vec3 {
     match get_scene(roads_data, scene_files_map, parcel) {
         Some(scene_data) => {
@@ -771,7 +1118,8 @@ pub fn get_parcel_spawn_point(
                 false => scene_center,
             }
         }
-        None => download_level_spawn_point(parcel, level_id),
+        None if connectivity.is_offline() => get_parcels_center_location(&vec![parcel.clone()]),
+        None => download_level_spawn_point(pool, lookup, cache, parcel, level_id),
     }
 }
 
@@ -796,6 +1144,7 @@ pub fn spawn_scene(
             commands,
             asset_server,
             scene_data,
+            scene_entity,
             level_id,
             collision_map,
             SystemTime::now(),
@@ -843,7 +1192,7 @@ fn get_fixed_translation_by_anchor(
             x: translation.x - size.x,
             y: translation.y + size.y / 2.0,
         },
-        dcl2d_ecs_v1::Anchor::Custom(vec) => Vec2 {
+        dcl2d_ecs_v1::Anchor::Custom(vec) | dcl2d_ecs_v1::Anchor::Unbounded(vec) => Vec2 {
             x: translation.x - vec.x as f32,
             y: translation.y + size.y - vec.y as f32,
         },
@@ -859,15 +1208,18 @@ fn get_fixed_translation_by_anchor(
     }
 }
 
+/// `parent_translation` is the sum of the translations of the ancestors of `entity`, used to place
+/// the collision tiles of mask colliders.
 fn spawn_entity(
     commands: &mut Commands,
     asset_server: &AssetServer,
     collision_map: &mut resources::CollisionMap,
     entity: &dcl2d_ecs_v1::Entity,
+    parent_translation: Vec3,
     scene_data: &SceneData,
+    scene_entity: Entity,
     level_id: usize,
 ) -> Entity {
-    let scene = &scene_data.scene;
     let mut transform = Transform::default();
     let spawned_entity = commands
         .spawn(Name::new(entity.name.clone()))
@@ -893,6 +1245,7 @@ fn spawn_entity(
         .insert(VisibilityBundle::default());
 
     // Inserting components
+    let mut trigger_actions = TriggerActions::default();
     for component in entity.components.iter() {
         if let Some(sprite_renderer) = component
             .as_any()
@@ -915,6 +1268,24 @@ fn spawn_entity(
                 ));
         }
 
+        if let Some(sprite_sheet_renderer) = component
+            .as_any()
+            .downcast_ref::<dcl2d_ecs_v1::components::SpriteSheetRenderer>(
+        ) {
+            let mut image_path = scene_data.path.clone();
+
+            image_path.push("assets");
+            image_path.push(&sprite_sheet_renderer.spritesheet);
+
+            commands
+                .entity(spawned_entity)
+                .insert(LoadingSpriteSheetRenderer {
+                    texture: asset_server.load(image_path),
+                    sprite_sheet_renderer_component: sprite_sheet_renderer.clone(),
+                    transform,
+                });
+        }
+
         if let Some(collider) = component
             .as_any()
             .downcast_ref::<dcl2d_ecs_v1::components::BoxCollider>()
@@ -930,91 +1301,73 @@ fn spawn_entity(
 
         if let Some(collider) = component
             .as_any()
-            .downcast_ref::<dcl2d_ecs_v1::components::MaskCollider>()
+            .downcast_ref::<dcl2d_ecs_v1::components::CircleCollider>()
         {
-            let mut sprite_path = std::fs::canonicalize(PathBuf::from_str(".").unwrap()).unwrap();
-            sprite_path.push("assets");
-            sprite_path.push(&scene_data.path);
-            sprite_path.push("assets");
-            sprite_path.push(&collider.sprite);
-
-            if let Ok(mut reader) = ImageReader::open(sprite_path) {
-                reader.set_format(ImageFormat::Png);
-                if let Ok(DynamicImage::ImageRgba8(image)) = reader.decode() {
-                    let mut pixels = image.pixels();
-                    let rows = image.rows().len();
-                    let columns = pixels.len() / rows;
-                    let world_transform = transform.translation
-                        + get_parcels_center_location(&scene_data.scene.parcels);
-
-                    let fixed_translation = get_fixed_translation_by_anchor(
-                        &Vec2 {
-                            x: columns as f32,
-                            y: rows as f32,
-                        },
-                        &world_transform.truncate(),
-                        &collider.anchor,
-                    );
-
-                    let mut index = 0;
-                    let channel = collider.channel.clone() as usize;
-
-                    while pixels.len() > 0 {
-                        if pixels.next().unwrap()[channel] > 0 {
-                            let tile_location = fixed_translation
-                                + (Vec2::new(
-                                    (index % columns) as f32,
-                                    (index / columns) as f32 * -1.0,
-                                ) * super::collision::TILE_SIZE);
-                            let collision_tile = CollisionTile {
-                                location: tile_location,
-                                colliision_type: collider.collision_type.clone(),
-                                entity: Some(spawned_entity),
-                                parcels: scene_data.scene.parcels.clone(),
-                            };
-                            collision_map.tiles.push(collision_tile);
-                        }
-                        index += 1;
-                    }
-                }
-            }
+            let circle_collider = CircleCollider {
+                center: Vec2::new(collider.center.x as f32, collider.center.y as f32),
+                radius: collider.radius as f32,
+                collision_type: collider.collision_type.clone(),
+                parcels: scene_data.scene.parcels.clone(),
+            };
+            commands.entity(spawned_entity).insert(circle_collider);
         }
 
-        if let Some(level_change) = component
+        if let Some(collider) = component
             .as_any()
-            .downcast_ref::<dcl2d_ecs_v1::components::triggers::LevelChange>(
-        ) {
-            let mut new_level_id = 0;
+            .downcast_ref::<dcl2d_ecs_v1::components::MaskCollider>()
+        {
+            let mut assets_path = std::fs::canonicalize(PathBuf::from_str(".").unwrap()).unwrap();
+            assets_path.push("assets");
+            assets_path.push(&scene_data.path);
+            assets_path.push("assets");
+
+            if let Some(mask) = load_collision_mask(&assets_path, collider) {
+                let world_transform = parent_translation
+                    + transform.translation
+                    + get_parcels_center_location(&scene_data.scene.parcels);
+
+                let location = get_fixed_translation_by_anchor(
+                    &Vec2 {
+                        x: mask.width() as f32,
+                        y: mask.height() as f32,
+                    },
+                    &world_transform.truncate(),
+                    &collider.anchor,
+                );
 
-            for i in 0..scene.levels.len() {
-                if scene.levels[i].name == level_change.level {
-                    new_level_id = i;
-                    break;
-                }
+                collision_map.insert(MaskCollision {
+                    location,
+                    mask,
+                    collision_type: collider.collision_type.clone(),
+                    entity: Some(spawned_entity),
+                    scene: scene_entity,
+                    level: level_id,
+                    parcels: scene_data.scene.parcels.clone(),
+                });
             }
+        }
 
-            let scene_center_location = get_parcels_center_location(&scene_data.scene.parcels);
-            let level_change_component = LevelChange {
-                level: new_level_id,
-                spawn_point: Vec2::new(
-                    level_change.spawn_point.x as f32 + scene_center_location.x,
-                    level_change.spawn_point.y as f32 + scene_center_location.y,
-                ),
-                parcels: scene_data.scene.parcels.clone(),
-            };
-            commands
-                .entity(spawned_entity)
-                .insert(level_change_component);
+        if let Some((action, activation)) = get_trigger_action(component.as_ref(), scene_data) {
+            match activation {
+                Activation::Interact => trigger_actions.on_interact.push(action),
+                Activation::Enter => trigger_actions.on_enter.push(action),
+            }
         }
     }
 
+    if !trigger_actions.on_interact.is_empty() || !trigger_actions.on_enter.is_empty() {
+        commands.entity(spawned_entity).insert(trigger_actions);
+    }
+
     for child_entity in entity.children.iter() {
         let spawned_child_entity = spawn_entity(
             commands,
             asset_server,
             collision_map,
             child_entity,
+            parent_translation + transform.translation,
             scene_data,
+            scene_entity,
             level_id,
         );
         commands
@@ -1023,3 +1376,180 @@ fn spawn_entity(
     }
     spawned_entity
 }
+
+/// Loads the collision mask the compiler wrote for `collider`. Scenes compiled before masks were
+/// precomputed only have the sprite, so it gets decoded instead.
+fn load_collision_mask(
+    assets_path: &Path,
+    collider: &dcl2d_ecs_v1::components::MaskCollider,
+) -> Option<CollisionMask> {
+    if let Ok(data) = fs::read(assets_path.join(collider.mask_path())) {
+        match CollisionMask::read(&data) {
+            Ok(mask) => return Some(mask),
+            Err(e) => println!("{}: {}", collider.mask_path(), e),
+        }
+    }
+
+    let mut reader = ImageReader::open(assets_path.join(&collider.sprite)).ok()?;
+    reader.set_format(ImageFormat::Png);
+    let image = reader.decode().ok()?.to_rgba8();
+    Some(CollisionMask::from_rgba(
+        image.width(),
+        image.height(),
+        &image,
+        &collider.channel,
+    ))
+}
+
+fn get_trigger_action(
+    component: &dyn dcl2d_ecs_v1::Component,
+    scene_data: &SceneData,
+) -> Option<(Action, Activation)> {
+    let component = component.as_any();
+
+    if let Some(level_change) =
+        component.downcast_ref::<dcl2d_ecs_v1::components::triggers::LevelChange>()
+    {
+        let mut new_level_id = 0;
+
+        for i in 0..scene_data.scene.levels.len() {
+            if scene_data.scene.levels[i].name == level_change.level {
+                new_level_id = i;
+                break;
+            }
+        }
+
+        let scene_center_location = get_parcels_center_location(&scene_data.scene.parcels);
+        let level_change_action = Action::ChangeLevel(LevelChange {
+            level: new_level_id,
+            spawn_point: Vec2::new(
+                level_change.spawn_point.x as f32 + scene_center_location.x,
+                level_change.spawn_point.y as f32 + scene_center_location.y,
+            ),
+        });
+        return Some((level_change_action, level_change.activation.clone()));
+    }
+
+    if let Some(teleport) = component.downcast_ref::<dcl2d_ecs_v1::components::triggers::Teleport>()
+    {
+        return Some((
+            Action::Teleport(Parcel(teleport.parcel.x, teleport.parcel.y)),
+            teleport.activation.clone(),
+        ));
+    }
+
+    if let Some(set_visibility) =
+        component.downcast_ref::<dcl2d_ecs_v1::components::triggers::SetVisibility>()
+    {
+        return Some((
+            Action::SetVisibility {
+                entity: set_visibility.entity.clone(),
+                visible: set_visibility.visible,
+            },
+            set_visibility.activation.clone(),
+        ));
+    }
+
+    if let Some(play_sound) =
+        component.downcast_ref::<dcl2d_ecs_v1::components::triggers::PlaySound>()
+    {
+        let mut sound_path = scene_data.path.clone();
+        sound_path.push("assets");
+        sound_path.push(&play_sound.sound);
+
+        return Some((
+            Action::PlaySound {
+                path: sound_path,
+                volume: play_sound.volume,
+            },
+            play_sound.activation.clone(),
+        ));
+    }
+
+    if let Some(show_message) =
+        component.downcast_ref::<dcl2d_ecs_v1::components::triggers::ShowMessage>()
+    {
+        return Some((
+            Action::ShowMessage {
+                message: show_message.message.clone(),
+                duration: show_message.duration,
+            },
+            show_message.activation.clone(),
+        ));
+    }
+
+    if let Some(open_url) = component.downcast_ref::<dcl2d_ecs_v1::components::triggers::OpenUrl>()
+    {
+        return Some((
+            Action::OpenUrl(open_url.url.clone()),
+            open_url.activation.clone(),
+        ));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parcels_to_download_start_with_the_closest_ones_ahead() {
+        let parcels = get_parcels_to_download(&Parcel(0, 0), Vec2::X, 3, 0);
+
+        assert_eq!(parcels[0], Parcel(0, 0));
+        assert_eq!(parcels[1], Parcel(1, 0));
+        let ahead = parcels.iter().position(|p| *p == Parcel(2, 0)).unwrap();
+        let behind = parcels.iter().position(|p| *p == Parcel(-2, 0)).unwrap();
+        assert!(ahead < behind);
+    }
+
+    #[test]
+    fn parcels_ahead_are_prefetched() {
+        let parcels = get_parcels_to_download(&Parcel(0, 0), Vec2::Y, 2, 2);
+
+        assert!(parcels.contains(&Parcel(0, 3)));
+        assert!(!parcels.contains(&Parcel(0, -3)));
+        assert!(!parcels.contains(&Parcel(3, 0)));
+        assert!(parcels
+            .iter()
+            .all(|parcel| parcel_distance(&Parcel(0, 0), parcel) < 4));
+    }
+
+    #[test]
+    fn nothing_is_prefetched_without_a_direction() {
+        let parcels = get_parcels_to_download(&Parcel(0, 0), Vec2::ZERO, 2, 2);
+
+        assert_eq!(
+            parcels.len(),
+            get_all_parcels_around(&Parcel(0, 0), 2).len()
+        );
+        assert!(parcels
+            .iter()
+            .all(|parcel| parcel_distance(&Parcel(0, 0), parcel) < 2));
+    }
+
+    #[test]
+    fn failed_parcels_wait_longer_every_time() {
+        let mut download_queue = DownloadQueue::default();
+        download_queue.requested.insert(Parcel(0, 0));
+
+        download_queue.download_failed(&[Parcel(0, 0)]);
+        assert!(!download_queue.requested.contains(&Parcel(0, 0)));
+        let (first_retry, _) = download_queue.failed[&Parcel(0, 0)];
+
+        download_queue.download_failed(&[Parcel(0, 0)]);
+        let (second_retry, failures) = download_queue.failed[&Parcel(0, 0)];
+        assert_eq!(failures, 2);
+        assert!(second_retry - first_retry > DOWNLOAD_RETRY_DELAY / 2);
+
+        for _ in 0..100 {
+            download_queue.download_failed(&[Parcel(0, 0)]);
+        }
+        let (last_retry, _) = download_queue.failed[&Parcel(0, 0)];
+        assert!(last_retry <= Instant::now() + MAX_DOWNLOAD_RETRY_DELAY);
+
+        download_queue.download_succeeded(&[Parcel(0, 0)]);
+        assert!(download_queue.failed.is_empty());
+    }
+}
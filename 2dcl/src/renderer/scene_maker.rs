@@ -8,7 +8,7 @@ use rmp_serde::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Write};
 use std::path::PathBuf;
 use std::str::FromStr;
 
@@ -65,11 +65,15 @@ const RANDOM_OBSTACLE_SPAWN_CHANCE: f64 = 0.1;
 #[derive(Debug, Clone, Default, Resource)]
 pub struct RoadsData {
     pub parcel_map: HashMap<(i16, i16), ()>,
+    /// Timestamp of the last scene deployment taken into account.
+    pub last_deployment_timestamp: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct SerializableRoadsData {
     parcels: Vec<Parcel>,
+    #[serde(default)]
+    last_deployment_timestamp: u64,
 }
 
 enum Border {
@@ -1427,7 +1431,10 @@ pub fn read_roads_data() -> Result<RoadsData> {
         Err(e) => return Err(Box::new(e)),
     };
 
-    let mut roads_data = RoadsData::default();
+    let mut roads_data = RoadsData {
+        last_deployment_timestamp: serializable_roads_data.last_deployment_timestamp,
+        ..default()
+    };
 
     for parcel in serializable_roads_data.parcels {
         roads_data.parcel_map.insert((parcel.0, parcel.1), ());
@@ -1436,6 +1443,24 @@ pub fn read_roads_data() -> Result<RoadsData> {
     Ok(roads_data)
 }
 
+pub fn update_roads_data(new_roads_data: &RoadsData) -> Result<()> {
+    let serializable_roads_data = SerializableRoadsData {
+        parcels: new_roads_data
+            .parcel_map
+            .keys()
+            .map(|key| Parcel(key.0, key.1))
+            .collect(),
+        last_deployment_timestamp: new_roads_data.last_deployment_timestamp,
+    };
+
+    let mut buf: Vec<u8> = Vec::new();
+    serializable_roads_data.serialize(&mut Serializer::new(&mut buf))?;
+
+    let mut file = File::create(ROADS_DATA_MP_FILE)?;
+    file.write_all(&buf)?;
+    Ok(())
+}
+
 pub fn is_road(parcel: &Parcel, roads_data: &RoadsData) -> bool {
     roads_data.parcel_map.get(&(parcel.0, parcel.1)).is_some()
 }
@@ -3,16 +3,17 @@ use super::scene_maker::make_default_background_entities;
 use super::scene_maker::{is_road, make_road_scene, RoadsData};
 use crate::states::AppState;
 use bevy::prelude::*;
+use dcl2d_ecs_v1::scene_file;
 use dcl_common::Parcel;
 use glob::glob;
-use rmp_serde::*;
-use serde::Deserialize;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
 use std::path::Path;
 use std::path::PathBuf;
 
+/// File next to a downloaded `scene.2dcl` listing the content files of the scene, one per line.
+/// The content cache removes files it evicts, so scenes missing any of them are downloaded again.
+const SCENE_CONTENTS_FILE: &str = ".contents";
+
 #[derive(Debug, Clone, Default, Resource)]
 pub struct SceneFilesMap {
     pub map: HashMap<(i16, i16), SceneFileData>,
@@ -72,24 +73,19 @@ pub fn read_scene_file<P>(file_path: P) -> Option<dcl2d_ecs_v1::Scene>
 where
     P: AsRef<Path>,
 {
-    if let Ok(file) = File::open(&file_path) {
-        let reader = BufReader::new(file);
-        let mut de = Deserializer::new(reader);
-        let scene: Result<dcl2d_ecs_v1::Scene, rmp_serde::decode::Error> =
-            Deserialize::deserialize(&mut de);
-
-        match scene {
-            Ok(v) => return Some(v),
+    match std::fs::read(&file_path) {
+        Ok(content) => match scene_file::read(&content) {
+            Ok(scene) => Some(scene),
             Err(e) => {
-                println!("error at scene des {:?}", e);
-                return None;
+                println!("{:?}: {}", file_path.as_ref(), e);
+                None
             }
+        },
+        Err(_) => {
+            println!("no path: {:?}", file_path.as_ref());
+            None
         }
-    } else {
-        println!("no path: {:?}", file_path.as_ref());
     }
-
-    None
 }
 
 pub fn get_parcel_file_data(
@@ -138,14 +134,52 @@ pub fn get_scene(
     None
 }
 
-pub fn read_scene_u8(content: &[u8]) -> Option<dcl2d_ecs_v1::Scene> {
-    let mut de = Deserializer::new(content);
-    let scene: Result<dcl2d_ecs_v1::Scene, rmp_serde::decode::Error> =
-        Deserialize::deserialize(&mut de);
+/// Writes the list of `filenames` of the scene downloaded in `scene_path`.
+pub fn write_scene_contents<P>(scene_path: P, filenames: &[String]) -> std::io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    std::fs::write(
+        scene_path.as_ref().join(SCENE_CONTENTS_FILE),
+        filenames.join("\n"),
+    )
+}
+
+/// Forgets the scene of `parcel` if any of its content files are missing, so it's downloaded
+/// again. Returns whether the scene was forgotten. Scenes without a list of files are kept.
+pub fn forget_incomplete_scene(parcel: &Parcel, scene_files_map: &mut SceneFilesMap) -> bool {
+    let scene_file_data = match scene_files_map.map.get(&(parcel.0, parcel.1)) {
+        Some(v) => v,
+        None => return false,
+    };
 
-    match scene {
+    let mut scene_path = scene_file_data.path.clone();
+    scene_path.pop();
+    let contents = match std::fs::read_to_string(scene_path.join(SCENE_CONTENTS_FILE)) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    if contents
+        .lines()
+        .all(|filename| scene_path.join(filename).is_file())
+    {
+        return false;
+    }
+
+    let path = scene_file_data.path.clone();
+    scene_files_map
+        .map
+        .retain(|_, scene_file_data| scene_file_data.path != path);
+    true
+}
+
+pub fn read_scene_u8(content: &[u8]) -> Option<dcl2d_ecs_v1::Scene> {
+    match scene_file::read(content) {
         Ok(v) => Some(v),
-        Err(_) => None,
+        Err(e) => {
+            println!("{}", e);
+            None
+        }
     }
 }
 
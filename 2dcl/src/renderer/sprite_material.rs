@@ -0,0 +1,191 @@
+use bevy::{
+    asset::load_internal_asset,
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    render::{
+        mesh::{Indices, MeshVertexBufferLayout},
+        render_resource::{
+            AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState,
+            PrimitiveTopology, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+    sprite::{Material2d, Material2dKey, Material2dPlugin},
+};
+use dcl2d_ecs_v1::blend_mode;
+
+use crate::states::AppState;
+
+pub const SPRITE_MATERIAL_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 4_329_171_508_284_633_141);
+
+pub struct SpriteMaterialPlugin;
+
+impl Plugin for SpriteMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            SPRITE_MATERIAL_SHADER_HANDLE,
+            "sprite_material.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.add_plugins(Material2dPlugin::<SpriteMaterial>::default())
+            .add_systems(
+                Update,
+                update_sprite_materials.run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// Material used to render a `SpriteRenderer` that has a `blend_mode`.
+/// The shader outputs premultiplied colors, so every blend state is expressed in those terms.
+#[derive(AsBindGroup, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "6f1d3f2c-2b0e-4bb4-9a4c-8d1b1f3c5e27"]
+#[bind_group_data(SpriteMaterialKey)]
+pub struct SpriteMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub texture: Handle<Image>,
+    pub blend: BlendState,
+}
+
+#[derive(Eq, PartialEq, Hash, Clone)]
+pub struct SpriteMaterialKey {
+    blend: BlendState,
+}
+
+impl From<&SpriteMaterial> for SpriteMaterialKey {
+    fn from(material: &SpriteMaterial) -> Self {
+        SpriteMaterialKey {
+            blend: material.blend,
+        }
+    }
+}
+
+impl Material2d for SpriteMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SPRITE_MATERIAL_SHADER_HANDLE.typed().into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            for target in fragment.targets.iter_mut().flatten() {
+                target.blend = Some(key.bind_group_data.blend);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The fade and transparency systems work on the `Sprite` color, so we mirror it into the material.
+fn update_sprite_materials(
+    mut materials: ResMut<Assets<SpriteMaterial>>,
+    sprites_query: Query<(&Sprite, &Handle<SpriteMaterial>), Changed<Sprite>>,
+) {
+    for (sprite, material_handle) in sprites_query.iter() {
+        if let Some(material) = materials.get_mut(material_handle) {
+            material.color = sprite.color;
+        }
+    }
+}
+
+pub fn dcl_blend_mode_to_blend_state(blend_mode: &blend_mode::BlendMode) -> BlendState {
+    let over = BlendComponent {
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::OneMinusSrcAlpha,
+        operation: BlendOperation::Add,
+    };
+
+    match blend_mode {
+        blend_mode::BlendMode::AlphaBlend => BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        blend_mode::BlendMode::Add => BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: over,
+        },
+        blend_mode::BlendMode::Multiply => BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::Dst,
+                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                operation: BlendOperation::Add,
+            },
+            alpha: over,
+        },
+        blend_mode::BlendMode::Custom { color, alpha } => BlendState {
+            color: BlendComponent {
+                src_factor: dcl_blend_factor_to_blend_factor(&color.src),
+                dst_factor: dcl_blend_factor_to_blend_factor(&color.dst),
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent {
+                src_factor: dcl_blend_factor_to_blend_factor(&alpha.src),
+                dst_factor: dcl_blend_factor_to_blend_factor(&alpha.dst),
+                operation: BlendOperation::Add,
+            },
+        },
+    }
+}
+
+fn dcl_blend_factor_to_blend_factor(factor: &blend_mode::BlendFactor) -> BlendFactor {
+    match factor {
+        blend_mode::BlendFactor::Zero => BlendFactor::Zero,
+        blend_mode::BlendFactor::One => BlendFactor::One,
+        blend_mode::BlendFactor::Src => BlendFactor::Src,
+        blend_mode::BlendFactor::OneMinusSrc => BlendFactor::OneMinusSrc,
+        blend_mode::BlendFactor::SrcAlpha => BlendFactor::SrcAlpha,
+        blend_mode::BlendFactor::OneMinusSrcAlpha => BlendFactor::OneMinusSrcAlpha,
+        blend_mode::BlendFactor::Dst => BlendFactor::Dst,
+        blend_mode::BlendFactor::OneMinusDst => BlendFactor::OneMinusDst,
+        blend_mode::BlendFactor::DstAlpha => BlendFactor::DstAlpha,
+        blend_mode::BlendFactor::OneMinusDstAlpha => BlendFactor::OneMinusDstAlpha,
+        blend_mode::BlendFactor::SrcAlphaSaturated => BlendFactor::SrcAlphaSaturated,
+        blend_mode::BlendFactor::Constant => BlendFactor::Constant,
+        blend_mode::BlendFactor::OneMinusConstant => BlendFactor::OneMinusConstant,
+    }
+}
+
+/// Builds a quad of `size` pixels with its origin placed at the sprite `anchor`, the same way
+/// the sprite pipeline does it. Flipping is done on the UVs so the winding order doesn't change.
+pub fn anchored_quad(size: Vec2, anchor: Vec2, flip_x: bool, flip_y: bool) -> Mesh {
+    let corners = [
+        Vec2::new(-0.5, -0.5),
+        Vec2::new(0.5, -0.5),
+        Vec2::new(0.5, 0.5),
+        Vec2::new(-0.5, 0.5),
+    ];
+
+    let mut positions = Vec::with_capacity(4);
+    let mut normals = Vec::with_capacity(4);
+    let mut uvs = Vec::with_capacity(4);
+
+    for corner in corners {
+        let position = (corner - anchor) * size;
+        positions.push([position.x, position.y, 0.0]);
+        normals.push([0.0, 0.0, 1.0]);
+
+        let mut uv = Vec2::new(corner.x + 0.5, 0.5 - corner.y);
+        if flip_x {
+            uv.x = 1.0 - uv.x;
+        }
+        if flip_y {
+            uv.y = 1.0 - uv.y;
+        }
+        uvs.push([uv.x, uv.y]);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 2, 0, 2, 3])));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh
+}
@@ -0,0 +1,16 @@
+#import bevy_sprite::mesh2d_vertex_output MeshVertexOutput
+
+@group(1) @binding(0)
+var<uniform> color: vec4<f32>;
+@group(1) @binding(1)
+var texture: texture_2d<f32>;
+@group(1) @binding(2)
+var texture_sampler: sampler;
+
+@fragment
+fn fragment(
+    mesh: MeshVertexOutput
+) -> @location(0) vec4<f32> {
+    let output_color = textureSample(texture, texture_sampler, mesh.uv) * color;
+    return vec4<f32>(output_color.rgb * output_color.a, output_color.a);
+}
@@ -0,0 +1,328 @@
+use super::scene_loader::get_newest_scene_files_for_parcels;
+use super::scenes_io::SceneFilesMap;
+use crate::components::{self, GettingNewestScenes, PollingSceneChanges, WorldSyncDownload};
+use crate::resources;
+use crate::states::AppState;
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use catalyst::pointer_changes::PointerChange;
+use catalyst::{ContentClient, EntityType, ServerPool};
+use dcl_common::Parcel;
+use futures_lite::future;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Where the timestamp of the last deployment taken into account is kept, followed by the parcels
+/// still to bring up to date, one per line. It's in the scenes folder so it's removed along with
+/// the scenes it belongs to.
+const LAST_SYNC_FILE: &str = "./assets/scenes/last_sync";
+
+pub struct WorldSyncPlugin;
+
+impl Plugin for WorldSyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScenesSynced>()
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(
+                Update,
+                (
+                    scene_changes_poller,
+                    scene_changes_handler,
+                    scenes_synced_handler,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// How far the downloaded scenes are in sync with the deployments of the Catalyst servers.
+#[derive(Resource, Default)]
+pub struct WorldSync {
+    /// Timestamp of the last deployment taken into account, `None` if the downloaded scenes were
+    /// never synced.
+    last_deployment_timestamp: Option<u64>,
+    /// Parcels whose scene changed and weren't brought up to date yet. They're checked again on
+    /// every poll until their downloads succeed.
+    pending_parcels: HashSet<Parcel>,
+    last_poll: Option<Instant>,
+}
+
+/// Sent when the scenes of `parcels` were brought up to date by a download of the world sync.
+#[derive(Event)]
+pub struct ScenesSynced {
+    pub parcels: Vec<Parcel>,
+}
+
+impl WorldSync {
+    fn save(&self) {
+        let timestamp = match self.last_deployment_timestamp {
+            Some(v) => v,
+            None => return,
+        };
+        let contents = last_sync_contents(timestamp, &self.pending_parcels);
+        if let Err(e) = std::fs::write(LAST_SYNC_FILE, contents) {
+            println!("{}", e);
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    let (last_deployment_timestamp, pending_parcels) = std::fs::read_to_string(LAST_SYNC_FILE)
+        .map(|contents| read_last_sync(&contents))
+        .unwrap_or_default();
+
+    commands.insert_resource(WorldSync {
+        last_deployment_timestamp,
+        pending_parcels,
+        ..default()
+    });
+}
+
+fn scene_changes_poller(
+    mut commands: Commands,
+    mut world_sync: ResMut<WorldSync>,
+    config: Res<resources::Config>,
+    peers: Res<resources::CatalystPeers>,
+    syncing_query: Query<(), Or<(With<PollingSceneChanges>, With<WorldSyncDownload>)>>,
+    connectivity: Res<resources::Connectivity>,
+) {
+    if !syncing_query.is_empty() || connectivity.is_offline() {
+        return;
+    }
+
+    let sync_interval = Duration::from_secs(config.catalyst.sync_interval);
+    if let Some(last_poll) = world_sync.last_poll {
+        if last_poll.elapsed() < sync_interval {
+            return;
+        }
+    }
+    world_sync.last_poll = Some(Instant::now());
+
+    let pool = peers.0.clone();
+    let last_deployment_timestamp = world_sync.last_deployment_timestamp;
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let result = match last_deployment_timestamp {
+            Some(timestamp) => get_changed_parcels(&pool, timestamp),
+            // Scenes downloaded before syncing could be outdated, so they're all checked once and
+            // the deployments are followed from the ones the server already has.
+            None => get_last_deployment_timestamp(&pool).map(|timestamp| (timestamp, Vec::new())),
+        };
+        match result {
+            Ok(v) => Some(v),
+            Err(e) => {
+                println!("{:?}", e);
+                None
+            }
+        }
+    });
+
+    commands.spawn(PollingSceneChanges { task });
+}
+
+fn scene_changes_handler(
+    mut commands: Commands,
+    mut world_sync: ResMut<WorldSync>,
+    mut tasks_polling_changes: Query<(Entity, &mut PollingSceneChanges)>,
+    scenes_query: Query<&components::Scene>,
+    scene_files_map: Res<SceneFilesMap>,
+    peers: Res<resources::CatalystPeers>,
+    lookup: Res<resources::SceneFilesLookup>,
+    cache: Res<resources::ContentCache>,
+) {
+    for (entity, mut polling) in &mut tasks_polling_changes {
+        let result = match future::block_on(future::poll_once(&mut polling.task)) {
+            Some(v) => v,
+            None => continue,
+        };
+        commands.entity(entity).despawn_recursive();
+
+        let (last_deployment_timestamp, changed_parcels) = match result {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let parcels_to_check: Vec<Parcel> = match world_sync.last_deployment_timestamp {
+            Some(_) => changed_parcels
+                .into_iter()
+                .chain(world_sync.pending_parcels.iter().cloned())
+                .collect::<HashSet<Parcel>>()
+                .into_iter()
+                .filter(|parcel| {
+                    scene_files_map.map.contains_key(&(parcel.0, parcel.1))
+                        || scenes_query
+                            .iter()
+                            .any(|scene| scene.parcels.contains(parcel))
+                })
+                .collect(),
+            None => scene_files_map
+                .map
+                .keys()
+                .map(|(x, y)| Parcel(*x, *y))
+                .collect(),
+        };
+
+        // The parcels are kept along with the timestamp until they're up to date, so the ones that
+        // fail to download or are left when closing are checked again.
+        world_sync.last_deployment_timestamp = Some(last_deployment_timestamp);
+        world_sync.pending_parcels = parcels_to_check.iter().cloned().collect();
+        world_sync.save();
+
+        if parcels_to_check.is_empty() {
+            continue;
+        }
+
+        lookup.forget(&parcels_to_check);
+        let pool = peers.0.clone();
+        let lookup = lookup.clone();
+        let cache = cache.0.clone();
+        let scene_files_map = scene_files_map.clone();
+        let parcels = parcels_to_check.clone();
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            match get_newest_scene_files_for_parcels(
+                &pool,
+                &lookup,
+                &cache,
+                parcels_to_check,
+                &scene_files_map,
+            ) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    println!("{:?}", e);
+                    None
+                }
+            }
+        });
+
+        commands.spawn((GettingNewestScenes { task, parcels }, WorldSyncDownload));
+    }
+}
+
+fn scenes_synced_handler(
+    mut world_sync: ResMut<WorldSync>,
+    mut scenes_synced: EventReader<ScenesSynced>,
+) {
+    if scenes_synced.is_empty() {
+        return;
+    }
+
+    for event in scenes_synced.iter() {
+        for parcel in &event.parcels {
+            world_sync.pending_parcels.remove(parcel);
+        }
+    }
+    world_sync.save();
+}
+
+/// Returns the timestamp of the last scene deployment included in the latest snapshot of the
+/// first server of `pool` that answers.
+#[tokio::main]
+async fn get_last_deployment_timestamp(pool: &ServerPool) -> catalyst::Result<u64> {
+    let snapshot = pool
+        .run(|server| async move { ContentClient::snapshot(&server).await })
+        .await?;
+    Ok(snapshot.entities.scene.last_included_deployment_timestamp)
+}
+
+/// Returns the timestamp of the last scene deployment made after `last_deployment_timestamp`,
+/// and the parcels whose scene changed with them.
+#[tokio::main]
+async fn get_changed_parcels(
+    pool: &ServerPool,
+    last_deployment_timestamp: u64,
+) -> catalyst::Result<(u64, Vec<Parcel>)> {
+    let changes = pool
+        .run(|server| async move {
+            // Deployments made at `last_deployment_timestamp` were already taken into account.
+            ContentClient::pointer_changes(
+                &server,
+                EntityType::Scene,
+                last_deployment_timestamp + 1,
+            )
+            .await
+        })
+        .await?;
+
+    Ok(changed_parcels(last_deployment_timestamp, changes))
+}
+
+fn changed_parcels(
+    last_deployment_timestamp: u64,
+    changes: Vec<PointerChange<Parcel>>,
+) -> (u64, Vec<Parcel>) {
+    let mut last_deployment_timestamp = last_deployment_timestamp;
+    let mut unique_parcels = HashSet::new();
+    let mut parcels = Vec::new();
+    for change in changes {
+        last_deployment_timestamp = last_deployment_timestamp.max(change.local_timestamp);
+        for parcel in change.pointers {
+            if unique_parcels.insert(parcel.clone()) {
+                parcels.push(parcel);
+            }
+        }
+    }
+    (last_deployment_timestamp, parcels)
+}
+
+/// Reads the timestamp and the pending parcels saved in `LAST_SYNC_FILE`.
+fn read_last_sync(contents: &str) -> (Option<u64>, HashSet<Parcel>) {
+    let mut lines = contents.lines();
+    let last_deployment_timestamp = lines.next().and_then(|line| line.trim().parse().ok());
+    let pending_parcels = lines
+        .filter_map(|line| Parcel::from_str(line).ok())
+        .collect();
+    (last_deployment_timestamp, pending_parcels)
+}
+
+fn last_sync_contents(last_deployment_timestamp: u64, pending_parcels: &HashSet<Parcel>) -> String {
+    let mut contents = last_deployment_timestamp.to_string();
+    for parcel in pending_parcels {
+        contents.push_str(&format!("\n{},{}", parcel.0, parcel.1));
+    }
+    contents
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use catalyst::EntityId;
+
+    fn change(local_timestamp: u64, pointers: Vec<Parcel>) -> PointerChange<Parcel> {
+        PointerChange {
+            entity_id: EntityId::new("an-entity"),
+            entity_type: EntityType::Scene,
+            pointers,
+            local_timestamp,
+            auth_chain: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn changed_parcels_are_listed_once_with_the_last_timestamp() {
+        let changes = vec![
+            change(200, vec![Parcel(0, 0), Parcel(0, 1)]),
+            change(300, vec![Parcel(0, 1), Parcel(5, 5)]),
+        ];
+
+        let (timestamp, parcels) = changed_parcels(100, changes);
+
+        assert_eq!(timestamp, 300);
+        assert_eq!(parcels, vec![Parcel(0, 0), Parcel(0, 1), Parcel(5, 5)]);
+    }
+
+    #[test]
+    fn the_timestamp_is_kept_without_changes() {
+        assert_eq!(changed_parcels(100, Vec::new()), (100, Vec::new()));
+    }
+
+    #[test]
+    fn pending_parcels_are_saved_with_the_timestamp() {
+        let pending_parcels = HashSet::from([Parcel(0, 0), Parcel(-5, 12)]);
+
+        let contents = last_sync_contents(300, &pending_parcels);
+
+        assert_eq!(read_last_sync(&contents), (Some(300), pending_parcels));
+        // Files saved before keeping the pending parcels only have the timestamp.
+        assert_eq!(read_last_sync("200\n"), (Some(200), HashSet::new()));
+    }
+}
@@ -1,7 +1,14 @@
 use super::renderer::collision;
+use super::renderer::scene_loader::world_location_to_parcel;
 use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use catalyst::entity_files::SceneFile;
+use catalyst::ContentClient;
+use dcl_common::Parcel;
 use ethereum_adapter::EthAddress;
 use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 //Config defaults
 const ETH_ADDRESS: &str = "0x270722b5222968603E4650C3b70A7DfB971Ed5B6";
@@ -14,11 +21,19 @@ const STARTING_PARCEL_Y: i16 = 0;
 const STARTING_PARCEL_X: i16 = 0;
 const MIN_RENDERING_DISTANCE_IN_PARCELS: usize = 4;
 const MAX_RENDERING_DISTANCE_IN_PARCELS: usize = 7;
+const PREFETCH_DISTANCE_IN_PARCELS: usize = 3;
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
 const CAMERA_SIZE: f32 = 1.0;
 const PLAYER_SPEED: f32 = 400.0;
 const PLAYER_SCALE: f32 = 0.5;
 const PLAYER_COLLIDER_SIZE_X: f32 = 25.;
 const PLAYER_COLLIDER_SIZE_Y: f32 = 25.;
+const CATALYST_SERVER: &str = "https://peer.decentraland.org";
+const CATALYST_CONNECT_TIMEOUT_IN_SECONDS: u64 = 10;
+const CATALYST_READ_TIMEOUT_IN_SECONDS: u64 = 30;
+const CATALYST_RETRIES: u32 = 3;
+const CATALYST_SYNC_INTERVAL_IN_SECONDS: u64 = 60;
+const CACHE_MAX_SIZE_IN_MEGABYTES: u64 = 1024;
 
 #[derive(Resource, Deserialize, Default, PartialEq)]
 pub struct Config {
@@ -28,6 +43,12 @@ pub struct Config {
     pub world: World,
     #[serde(default)]
     pub player: Player,
+    #[serde(default)]
+    pub catalyst: Catalyst,
+    #[serde(default)]
+    pub cache: Cache,
+    #[serde(skip)]
+    pub offline: bool,
 }
 
 impl Config {
@@ -56,6 +77,20 @@ impl Config {
 
         Config::default()
     }
+
+    /// Uses the catalyst server given in the command line instead of the one in `config.toml`.
+    pub fn with_catalyst(mut self, catalyst: Option<String>) -> Self {
+        if let Some(server) = catalyst {
+            self.catalyst.server = server;
+        }
+        self
+    }
+
+    /// Keeps 2dcl offline when asked in the command line.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
 }
 
 #[derive(Deserialize, PartialEq)]
@@ -121,6 +156,10 @@ pub struct World {
     pub min_render_distance: usize,
     #[serde(default = "max_render_distance_default")]
     pub max_render_distance: usize,
+    #[serde(default = "prefetch_distance_default")]
+    pub prefetch_distance: usize,
+    #[serde(default = "max_concurrent_downloads_default")]
+    pub max_concurrent_downloads: usize,
     #[serde(default = "camera_size_default")]
     pub camera_size: f32,
 }
@@ -132,6 +171,8 @@ impl Default for World {
             starting_parcel_y: starting_parcel_y_default(),
             min_render_distance: min_render_distance_default(),
             max_render_distance: max_render_distance_default(),
+            prefetch_distance: prefetch_distance_default(),
+            max_concurrent_downloads: max_concurrent_downloads_default(),
             camera_size: camera_size_default(),
         }
     }
@@ -149,6 +190,12 @@ fn min_render_distance_default() -> usize {
 fn max_render_distance_default() -> usize {
     MAX_RENDERING_DISTANCE_IN_PARCELS
 }
+fn prefetch_distance_default() -> usize {
+    PREFETCH_DISTANCE_IN_PARCELS
+}
+fn max_concurrent_downloads_default() -> usize {
+    MAX_CONCURRENT_DOWNLOADS
+}
 fn camera_size_default() -> f32 {
     CAMERA_SIZE
 }
@@ -189,8 +236,432 @@ fn player_collider_size_y_default() -> f32 {
     PLAYER_COLLIDER_SIZE_Y
 }
 
+#[derive(Deserialize, PartialEq, Clone)]
+pub struct Catalyst {
+    #[serde(default = "catalyst_server_default")]
+    pub server: String,
+    #[serde(default)]
+    pub fallback_servers: Vec<String>,
+    #[serde(default = "catalyst_connect_timeout_default")]
+    pub connect_timeout: u64,
+    #[serde(default = "catalyst_read_timeout_default", alias = "timeout")]
+    pub read_timeout: u64,
+    #[serde(default = "catalyst_retries_default")]
+    pub retries: u32,
+    #[serde(default = "catalyst_sync_interval_default")]
+    pub sync_interval: u64,
+}
+
+impl Catalyst {
+    /// The server used to download and deploy scenes.
+    pub fn server(&self) -> catalyst::Server {
+        catalyst::Server::with_policy(&self.server, self.policy())
+    }
+
+    /// The server followed by the fallback servers, used to download scenes while exploring.
+    pub fn pool(&self) -> catalyst::ServerPool {
+        let mut servers = vec![self.server()];
+        for server in &self.fallback_servers {
+            servers.push(catalyst::Server::with_policy(server, self.policy()));
+        }
+        catalyst::ServerPool::new(servers)
+    }
+
+    fn policy(&self) -> catalyst::ClientPolicy {
+        catalyst::ClientPolicy {
+            connect_timeout: std::time::Duration::from_secs(self.connect_timeout),
+            read_timeout: std::time::Duration::from_secs(self.read_timeout),
+            retries: self.retries,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for Catalyst {
+    fn default() -> Self {
+        Catalyst {
+            server: catalyst_server_default(),
+            fallback_servers: Vec::default(),
+            connect_timeout: catalyst_connect_timeout_default(),
+            read_timeout: catalyst_read_timeout_default(),
+            retries: catalyst_retries_default(),
+            sync_interval: catalyst_sync_interval_default(),
+        }
+    }
+}
+
+fn catalyst_server_default() -> String {
+    CATALYST_SERVER.to_string()
+}
+fn catalyst_connect_timeout_default() -> u64 {
+    CATALYST_CONNECT_TIMEOUT_IN_SECONDS
+}
+fn catalyst_read_timeout_default() -> u64 {
+    CATALYST_READ_TIMEOUT_IN_SECONDS
+}
+fn catalyst_retries_default() -> u32 {
+    CATALYST_RETRIES
+}
+fn catalyst_sync_interval_default() -> u64 {
+    CATALYST_SYNC_INTERVAL_IN_SECONDS
+}
+
+#[derive(Deserialize, PartialEq, Clone)]
+pub struct Cache {
+    #[serde(default)]
+    pub path: Option<std::path::PathBuf>,
+    #[serde(default = "cache_max_size_default")]
+    pub max_size: u64,
+}
+
+impl Cache {
+    /// The cache of downloaded content, in `path` or in the cache folder of the platform.
+    pub fn content_cache(&self) -> catalyst::ContentCache {
+        let dir = self
+            .path
+            .clone()
+            .or_else(catalyst::ContentCache::default_dir)
+            .unwrap_or_else(|| std::path::PathBuf::from("./assets/cache"));
+        catalyst::ContentCache::new(dir).with_max_size(self.max_size.saturating_mul(1024 * 1024))
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache {
+            path: None,
+            max_size: cache_max_size_default(),
+        }
+    }
+}
+
+fn cache_max_size_default() -> u64 {
+    CACHE_MAX_SIZE_IN_MEGABYTES
+}
+
+/// The Catalyst servers of the config, shared by the tasks that download scenes so they all know
+/// which servers are down.
+#[derive(Resource, Clone, Deref)]
+pub struct CatalystPeers(pub catalyst::ServerPool);
+
+/// The cache of the config, where scenes are downloaded before being placed in `assets/scenes`.
+#[derive(Resource, Clone, Deref)]
+pub struct ContentCache(pub catalyst::ContentCache);
+
+/// Whether the Catalyst servers can be used. While offline, only the scenes already downloaded
+/// are shown and nothing is asked to the servers.
+#[derive(Resource, Default)]
+pub struct Connectivity {
+    /// Offline for the whole run, asked with `--offline`.
+    pub forced_offline: bool,
+    /// Every Catalyst peer failed the last time it was asked.
+    pub unreachable: bool,
+}
+
+impl Connectivity {
+    pub fn new(forced_offline: bool) -> Self {
+        Connectivity {
+            forced_offline,
+            unreachable: false,
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.forced_offline || self.unreachable
+    }
+}
+
+/// How long the scenes of a parcel looked up by `SceneFilesLookup` are used before asking again,
+/// and kept before being dropped.
+const SCENE_FILES_LOOKUP_TTL: Duration = Duration::from_secs(30);
+
+/// The scene files recently looked up for each parcel, shared by the checks for newer scenes and
+/// the spawn points so the same parcels aren't asked for several times in a row.
+#[derive(Resource, Clone, Default)]
+pub struct SceneFilesLookup(Arc<Mutex<HashMap<Parcel, (Instant, Option<Arc<SceneFile>>)>>>);
+
+impl SceneFilesLookup {
+    /// Returns the scene files of the scenes that own `parcels`, asking the first server of `pool`
+    /// that answers for the parcels that weren't looked up recently.
+    pub async fn scene_files(
+        &self,
+        pool: &catalyst::ServerPool,
+        parcels: &[Parcel],
+    ) -> catalyst::Result<Vec<SceneFile>> {
+        let missing_parcels: Vec<Parcel> = {
+            let entries = self.0.lock().unwrap();
+            parcels
+                .iter()
+                .filter(|parcel| match entries.get(*parcel) {
+                    Some((looked_up, _)) => looked_up.elapsed() > SCENE_FILES_LOOKUP_TTL,
+                    None => true,
+                })
+                .cloned()
+                .collect()
+        };
+
+        if !missing_parcels.is_empty() {
+            let missing_parcels = &missing_parcels;
+            let found = pool
+                .run(|server| async move {
+                    ContentClient::scene_files_by_parcel(&server, missing_parcels).await
+                })
+                .await?;
+            self.remember(missing_parcels, found);
+        }
+
+        let entries = self.0.lock().unwrap();
+        let mut entities = HashSet::new();
+        let mut scene_files = Vec::new();
+        for parcel in parcels {
+            if let Some((_, Some(scene_file))) = entries.get(parcel) {
+                if entities.insert(scene_file.id.clone()) {
+                    scene_files.push(scene_file.as_ref().clone());
+                }
+            }
+        }
+        Ok(scene_files)
+    }
+
+    /// Keeps the scene files `found` for `parcels`, dropping the ones looked up too long ago.
+    fn remember(
+        &self,
+        parcels: &[Parcel],
+        mut found: std::collections::HashMap<Parcel, Arc<SceneFile>>,
+    ) {
+        let now = Instant::now();
+        let mut entries = self.0.lock().unwrap();
+        // Only the parcels around the player are looked up, so the old ones aren't needed.
+        entries.retain(|_, (looked_up, _)| looked_up.elapsed() <= SCENE_FILES_LOOKUP_TTL);
+        for parcel in parcels {
+            let scene_file = found.remove(parcel);
+            entries.insert(parcel.clone(), (now, scene_file));
+        }
+        // The rest of the parcels of the scenes found come for free.
+        for (parcel, scene_file) in found {
+            entries.insert(parcel, (now, Some(scene_file)));
+        }
+    }
+
+    /// Forgets the scene files of `parcels`, so they're asked for again the next time.
+    pub fn forget(&self, parcels: &[Parcel]) {
+        let mut entries = self.0.lock().unwrap();
+        for parcel in parcels {
+            entries.remove(parcel);
+        }
+    }
+}
+
+/// Mask collisions of the spawned scenes, indexed in a grid with a cell per parcel and level so
+/// movement checks only look at the masks around the player.
 #[derive(Default, Clone, Resource)]
 pub struct CollisionMap {
-    pub tiles: Vec<collision::CollisionTile>,
+    masks: HashMap<usize, collision::MaskCollision>,
+    cells: HashMap<(usize, i16, i16), Vec<usize>>,
+    scenes: HashMap<Entity, Vec<usize>>,
+    next_id: usize,
     pub tile_size: f32,
 }
+
+impl CollisionMap {
+    pub fn new(tile_size: f32) -> Self {
+        CollisionMap {
+            tile_size,
+            ..default()
+        }
+    }
+
+    pub fn insert(&mut self, mask_collision: collision::MaskCollision) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let (min, max) = mask_collision.bounds();
+        for parcel in cells(&min, &max) {
+            self.cells
+                .entry((mask_collision.level, parcel.0, parcel.1))
+                .or_default()
+                .push(id);
+        }
+        self.scenes
+            .entry(mask_collision.scene)
+            .or_default()
+            .push(id);
+        self.masks.insert(id, mask_collision);
+    }
+
+    /// Removes the masks of a scene, call it when the scene is despawned.
+    pub fn remove_scene(&mut self, scene: Entity) {
+        for id in self.scenes.remove(&scene).unwrap_or_default() {
+            let Some(mask_collision) = self.masks.remove(&id) else {
+                continue;
+            };
+
+            let (min, max) = mask_collision.bounds();
+            for parcel in cells(&min, &max) {
+                let key = (mask_collision.level, parcel.0, parcel.1);
+                if let Some(ids) = self.cells.get_mut(&key) {
+                    ids.retain(|other| *other != id);
+                    if ids.is_empty() {
+                        self.cells.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.masks.clear();
+        self.cells.clear();
+        self.scenes.clear();
+    }
+
+    /// Returns the masks of `level` in the cells touched by the area between `min` and `max`.
+    pub fn query(&self, level: usize, min: &Vec2, max: &Vec2) -> Vec<&collision::MaskCollision> {
+        let mut ids: Vec<usize> = cells(min, max)
+            .filter_map(|parcel| self.cells.get(&(level, parcel.0, parcel.1)))
+            .flatten()
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids.iter().filter_map(|id| self.masks.get(id)).collect()
+    }
+}
+
+/// Cells of the grid touched by the area between `min` and `max`.
+fn cells(min: &Vec2, max: &Vec2) -> impl Iterator<Item = Parcel> {
+    let min = world_location_to_parcel(&min.extend(0.0));
+    let max = world_location_to_parcel(&max.extend(0.0));
+    (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| Parcel(x, y)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::renderer::collision::MaskCollision;
+    use dcl2d_ecs_v1::collision_mask::CollisionMask;
+    use dcl2d_ecs_v1::collision_type::CollisionType;
+
+    fn mask_collision(scene: u32, level: usize, location: Vec2, size: u32) -> MaskCollision {
+        MaskCollision {
+            location,
+            mask: CollisionMask::from_rgba(
+                size,
+                size,
+                &vec![255; (size * size * 4) as usize],
+                &dcl2d_ecs_v1::color::Channel::A,
+            ),
+            collision_type: CollisionType::Solid,
+            entity: None,
+            scene: Entity::from_raw(scene),
+            level,
+            parcels: vec![],
+        }
+    }
+
+    #[test]
+    fn catalyst_server_from_the_command_line_wins() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.catalyst.server, CATALYST_SERVER);
+
+        let config: Config =
+            toml::from_str("[catalyst]\nserver = \"https://peer.decentraland.zone\"").unwrap();
+        assert_eq!(config.catalyst.server, "https://peer.decentraland.zone");
+
+        let config = config.with_catalyst(Some("http://localhost:5000".to_string()));
+        assert_eq!(config.catalyst.server().base_url, "http://localhost:5000");
+    }
+
+    #[test]
+    fn fallback_catalyst_servers_come_after_the_server() {
+        let config: Config = toml::from_str(
+            "[catalyst]\nfallback_servers = [\"https://peer-ec1.decentraland.org\"]\nretries = 1",
+        )
+        .unwrap();
+
+        let pool = config.catalyst.pool();
+        let servers: Vec<&str> = pool.servers().iter().map(|s| s.base_url.as_str()).collect();
+        assert_eq!(
+            servers,
+            vec![CATALYST_SERVER, "https://peer-ec1.decentraland.org"]
+        );
+        assert!(pool.servers().iter().all(|s| s.policy().retries == 1));
+        assert_eq!(
+            pool.servers()[0].policy().read_timeout.as_secs(),
+            CATALYST_READ_TIMEOUT_IN_SECONDS
+        );
+    }
+
+    #[test]
+    fn cache_uses_the_path_and_size_of_the_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(
+            Some(config.cache.content_cache().dir().to_path_buf()),
+            catalyst::ContentCache::default_dir()
+        );
+
+        let config: Config =
+            toml::from_str("[cache]\npath = \"/tmp/2dcl-cache\"\nmax_size = 10").unwrap();
+        let cache = config.cache.content_cache();
+        assert_eq!(cache.dir(), std::path::Path::new("/tmp/2dcl-cache"));
+        assert_eq!(cache.max_size(), 10 * 1024 * 1024);
+    }
+
+    #[test]
+    fn only_returns_the_masks_around_the_area() {
+        let mut collision_map = CollisionMap::default();
+        collision_map.insert(mask_collision(0, 0, Vec2::new(0.0, 10.0), 10));
+        collision_map.insert(mask_collision(0, 0, Vec2::new(2048.0, 10.0), 10));
+        collision_map.insert(mask_collision(0, 1, Vec2::new(0.0, 10.0), 10));
+
+        let found = collision_map.query(0, &Vec2::new(-5.0, -5.0), &Vec2::new(5.0, 5.0));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].location, Vec2::new(0.0, 10.0));
+        assert_eq!(found[0].level, 0);
+
+        let found = collision_map.query(0, &Vec2::new(1000.0, 0.0), &Vec2::new(1010.0, 5.0));
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn masks_across_cells_are_found_once_from_every_cell() {
+        let mut collision_map = CollisionMap::default();
+        collision_map.insert(mask_collision(0, 0, Vec2::new(200.0, 300.0), 600));
+
+        for point in [Vec2::new(250.0, 250.0), Vec2::new(700.0, -250.0)] {
+            assert_eq!(collision_map.query(0, &point, &point).len(), 1);
+        }
+        let found =
+            collision_map.query(0, &Vec2::new(-1000.0, -1000.0), &Vec2::new(1000.0, 1000.0));
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn removes_the_masks_of_despawned_scenes() {
+        let mut collision_map = CollisionMap::default();
+        collision_map.insert(mask_collision(0, 0, Vec2::new(0.0, 10.0), 10));
+        collision_map.insert(mask_collision(1, 0, Vec2::new(5.0, 10.0), 10));
+
+        collision_map.remove_scene(Entity::from_raw(0));
+        let found = collision_map.query(0, &Vec2::ZERO, &Vec2::new(10.0, 10.0));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].scene, Entity::from_raw(1));
+
+        collision_map.remove_scene(Entity::from_raw(1));
+        assert!(collision_map.cells.is_empty());
+        assert!(collision_map.masks.is_empty());
+    }
+    #[test]
+    fn scene_files_lookup_drops_old_parcels() {
+        let lookup = SceneFilesLookup::default();
+        let old = Instant::now() - SCENE_FILES_LOOKUP_TTL * 2;
+        lookup.0.lock().unwrap().insert(Parcel(0, 0), (old, None));
+
+        lookup.remember(&[Parcel(1, 0)], Default::default());
+
+        let entries = lookup.0.lock().unwrap();
+        assert!(!entries.contains_key(&Parcel(0, 0)));
+        assert!(entries.contains_key(&Parcel(1, 0)));
+    }
+}
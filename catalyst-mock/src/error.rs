@@ -0,0 +1,62 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum MockCatalystError {
+    Io(std::io::Error),
+    InvalidJson(serde_json::Error),
+    InvalidRequest(String),
+    InvalidHash { expected: String, found: String },
+    MissingEntityFile(String),
+    MissingContent(String),
+    NotFound,
+}
+
+impl MockCatalystError {
+    /// HTTP status code for the response.
+    pub fn status(&self) -> u16 {
+        match self {
+            MockCatalystError::Io(_) => 500,
+            MockCatalystError::NotFound => 404,
+            _ => 400,
+        }
+    }
+}
+
+impl error::Error for MockCatalystError {}
+
+impl fmt::Display for MockCatalystError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MockCatalystError::Io(error) => write!(f, "{}", error),
+            MockCatalystError::InvalidJson(error) => write!(f, "Invalid JSON: {}", error),
+            MockCatalystError::InvalidRequest(reason) => write!(f, "Invalid request: {}", reason),
+            MockCatalystError::InvalidHash { expected, found } => write!(
+                f,
+                "The hash of the file `{}` doesn't match its content, it should be `{}`.",
+                found, expected
+            ),
+            MockCatalystError::MissingEntityFile(id) => {
+                write!(f, "The entity file `{}` wasn't uploaded.", id)
+            }
+            MockCatalystError::MissingContent(hash) => write!(
+                f,
+                "The content `{}` wasn't uploaded and isn't in the server.",
+                hash
+            ),
+            MockCatalystError::NotFound => write!(f, "Not found"),
+        }
+    }
+}
+
+impl From<std::io::Error> for MockCatalystError {
+    fn from(error: std::io::Error) -> Self {
+        MockCatalystError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for MockCatalystError {
+    fn from(error: serde_json::Error) -> Self {
+        MockCatalystError::InvalidJson(error)
+    }
+}
@@ -0,0 +1,28 @@
+//! # Catalyst Mock
+//!
+//! A local stand-in for a [Catalyst](https://github.com/decentraland/catalyst) content server,
+//! backed by a directory. It implements the part of the content API that this workspace uses, so
+//! `catalyst::ContentClient`, `scene_deployer::deploy` and the 2dcl client can be tested without
+//! touching real servers:
+//!
+//! - `GET /content/status`
+//! - `GET /content/snapshot`
+//! - `GET /content/contents/{hash}` and `GET /content/contents/{hash}/active-entities`
+//! - `GET /content/available-content?cid={hash}&cid=...`
+//! - `GET /content/audit/{type}/{entity id}`
+//! - `POST /content/entities/active`
+//! - `POST /content/entities`
+//!
+//! Deployments aren't validated beyond the hashes of the uploaded files: signatures and
+//! ownership of the pointers aren't checked.
+
+mod error;
+pub use error::MockCatalystError;
+
+mod server;
+pub use server::{serve, MockServer};
+
+mod storage;
+pub use storage::{hash, Storage};
+
+pub type Result<T> = std::result::Result<T, MockCatalystError>;
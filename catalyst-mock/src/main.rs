@@ -0,0 +1,34 @@
+use std::net::SocketAddr;
+
+use catalyst_mock::{serve, Storage};
+use clap::Parser;
+
+/// Serves a local directory as a Catalyst content server.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Directory with the content, it's created if it doesn't exist
+    #[clap(default_value = "./catalyst")]
+    root: std::path::PathBuf,
+    #[clap(long, default_value_t = 5000)]
+    port: u16,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    let storage = match Storage::new(&args.root) {
+        Ok(storage) => storage,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let address = SocketAddr::from(([127, 0, 0, 1], args.port));
+    println!("serving {} in http://{}", storage.root().display(), address);
+    if let Err(e) = serve(address, storage).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::sync::oneshot;
+
+use crate::{MockCatalystError, Result, Storage};
+
+#[derive(Deserialize)]
+struct ActiveEntitiesFilter {
+    #[serde(default)]
+    pointers: Vec<String>,
+    #[serde(default)]
+    ids: Vec<String>,
+}
+
+/// A mock server running in its own thread, for tests. It stops when it's dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// let server = catalyst_mock::MockServer::start("./catalyst").unwrap();
+/// let server = catalyst::Server::new(server.url(""));
+/// ```
+pub struct MockServer {
+    address: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server in a free port of `localhost` serving the content in `root`.
+    pub fn start<T>(root: T) -> Result<MockServer>
+    where
+        T: AsRef<Path>,
+    {
+        let storage = Storage::new(root)?;
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let server = {
+            let _guard = runtime.enter();
+            hyper::Server::from_tcp(listener)
+                .map_err(std::io::Error::other)?
+                .serve(make_service_fn(move |_| {
+                    let storage = storage.clone();
+                    async move {
+                        Ok::<_, Infallible>(service_fn(move |request| {
+                            handle(storage.clone(), request)
+                        }))
+                    }
+                }))
+        };
+
+        let (shutdown, stop) = oneshot::channel::<()>();
+        let thread = thread::spawn(move || {
+            let server = server.with_graceful_shutdown(async {
+                stop.await.ok();
+            });
+            if let Err(error) = runtime.block_on(server) {
+                eprintln!("{}", error);
+            }
+        });
+
+        Ok(MockServer {
+            address,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns the URL of `path` in this server.
+    pub fn url<T>(&self, path: T) -> String
+    where
+        T: AsRef<str>,
+    {
+        format!("http://{}{}", self.address, path.as_ref())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+/// Serves `storage` in `address` until the process ends.
+pub async fn serve(address: SocketAddr, storage: Storage) -> hyper::Result<()> {
+    hyper::Server::try_bind(&address)?
+        .serve(make_service_fn(move |_| {
+            let storage = storage.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| handle(storage.clone(), request)))
+            }
+        }))
+        .await
+}
+
+async fn handle(
+    storage: Storage,
+    request: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    let response = match route(&storage, request).await {
+        Ok(response) => response,
+        Err(error) => json_response(error.status(), &json!({ "error": error.to_string() })),
+    };
+    Ok(response)
+}
+
+async fn route(storage: &Storage, request: Request<Body>) -> Result<Response<Body>> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().unwrap_or_default().to_string();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (method, segments.as_slice()) {
+        (Method::GET, ["content", "status"]) => Ok(json_response(200, &status())),
+        (Method::GET, ["content", "snapshot"]) => Ok(json_response(200, &storage.snapshot()?)),
+        (Method::GET, ["content", "pointer-changes"]) => {
+            Ok(json_response(200, &pointer_changes(storage, &query)?))
+        }
+        (Method::GET, ["content", "contents", hash]) => {
+            Ok(Response::new(Body::from(storage.content(hash)?)))
+        }
+        (Method::GET, ["content", "contents", hash, "active-entities"]) => {
+            let ids: Vec<Value> = storage
+                .entities()?
+                .into_iter()
+                .filter(|entity| {
+                    entity["content"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .any(|content| content["hash"] == json!(hash))
+                })
+                .map(|entity| entity["id"].clone())
+                .collect();
+            Ok(json_response(200, &json!(ids)))
+        }
+        (Method::GET, ["content", "available-content"]) => {
+            let available: Vec<Value> = form_urlencoded::parse(query.as_bytes())
+                .filter(|(key, _)| key == "cid")
+                .map(|(_, cid)| json!({ "cid": cid, "available": storage.has_content(&cid) }))
+                .collect();
+            Ok(json_response(200, &json!(available)))
+        }
+        (Method::GET, ["content", "audit", kind, id]) => {
+            let entity = storage.entity(kind, id)?;
+            Ok(json_response(
+                200,
+                &json!({
+                    "version": entity.get("version").cloned().unwrap_or(json!("v3")),
+                    "localTimestamp": entity["localTimestamp"],
+                    "authChain": entity["authChain"],
+                }),
+            ))
+        }
+        (Method::POST, ["content", "entities", "active"]) => {
+            let body = hyper::body::to_bytes(request.into_body())
+                .await
+                .map_err(|error| MockCatalystError::InvalidRequest(error.to_string()))?;
+            let filter: ActiveEntitiesFilter = serde_json::from_slice(&body)?;
+            let entities = storage.active_entities(&filter.pointers, &filter.ids)?;
+            Ok(json_response(200, &json!(entities)))
+        }
+        (Method::POST, ["content", "entities"]) => deploy(storage, request).await,
+        _ => Err(MockCatalystError::NotFound),
+    }
+}
+
+/// Lists the deployments of the active entities from the `from` local timestamp (included)
+/// onwards, oldest first, in a single page. Entities replaced by later deployments aren't kept, so
+/// their deployments aren't listed.
+fn pointer_changes(storage: &Storage, query: &str) -> Result<Value> {
+    let mut entity_types = Vec::new();
+    let mut from = 0;
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "entityType" => entity_types.push(json!(value)),
+            "from" => {
+                from = value.parse().map_err(|_| {
+                    MockCatalystError::InvalidRequest(format!("invalid `from`: {}", value))
+                })?
+            }
+            _ => {}
+        }
+    }
+
+    let mut entities: Vec<Value> = storage
+        .entities()?
+        .into_iter()
+        .filter(|entity| entity_types.is_empty() || entity_types.contains(&entity["type"]))
+        .filter(|entity| entity["localTimestamp"].as_u64().unwrap_or_default() >= from)
+        .collect();
+    entities.sort_by_key(|entity| entity["localTimestamp"].as_u64().unwrap_or_default());
+
+    let deltas: Vec<Value> = entities
+        .iter()
+        .map(|entity| {
+            json!({
+                "entityId": entity["id"],
+                "entityType": entity["type"],
+                "entityTimestamp": entity.get("timestamp").cloned().unwrap_or(json!(0)),
+                "localTimestamp": entity["localTimestamp"],
+                "pointers": entity["pointers"],
+                "authChain": entity["authChain"],
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "deltas": deltas,
+        "filters": { "entityTypes": entity_types, "from": from },
+        "pagination": { "offset": 0, "limit": deltas.len(), "moreData": false },
+    }))
+}
+
+/// Handles the multipart form `scene_deployer::deploy` sends: the `entityId`, the auth chain
+/// (`authChain[0][type]`, `authChain[0][payload]`, ...) and every file, named by its hash.
+async fn deploy(storage: &Storage, request: Request<Body>) -> Result<Response<Body>> {
+    let boundary = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| multer::parse_boundary(content_type).ok())
+        .ok_or_else(|| {
+            MockCatalystError::InvalidRequest("expected a multipart form".to_string())
+        })?;
+
+    let mut form = multer::Multipart::new(request.into_body(), boundary);
+    let mut entity_id = None;
+    let mut auth_chain: BTreeMap<usize, Map<String, Value>> = BTreeMap::new();
+    let mut files = Vec::new();
+    while let Some(field) = form
+        .next_field()
+        .await
+        .map_err(|error| MockCatalystError::InvalidRequest(error.to_string()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let bytes = field
+            .bytes()
+            .await
+            .map_err(|error| MockCatalystError::InvalidRequest(error.to_string()))?;
+
+        if name == "entityId" {
+            entity_id = Some(String::from_utf8_lossy(&bytes).to_string());
+        } else if let Some((index, key)) = auth_chain_field(&name) {
+            auth_chain.entry(index).or_default().insert(
+                key.to_string(),
+                json!(String::from_utf8_lossy(&bytes).to_string()),
+            );
+        } else {
+            files.push((name, bytes.to_vec()));
+        }
+    }
+
+    let entity_id = entity_id
+        .ok_or_else(|| MockCatalystError::InvalidRequest("missing `entityId`".to_string()))?;
+    let auth_chain = auth_chain.into_values().map(Value::Object).collect();
+    let local_timestamp = storage.deploy(&entity_id, &files, auth_chain)?;
+    Ok(json_response(
+        200,
+        &json!({ "creationTimestamp": local_timestamp }),
+    ))
+}
+
+/// Parses names like `authChain[0][payload]` into `(0, "payload")`.
+fn auth_chain_field(name: &str) -> Option<(usize, &str)> {
+    let (index, key) = name.strip_prefix("authChain[")?.split_once("][")?;
+    Some((index.parse().ok()?, key.strip_suffix(']')?))
+}
+
+fn status() -> Value {
+    json!({
+        "version": "v3",
+        "synchronizationStatus": {},
+        "commitHash": "",
+        "catalystVersion": env!("CARGO_PKG_VERSION"),
+        "ethNetwork": "mainnet",
+    })
+}
+
+fn json_response(status: u16, body: &Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use catalyst::entity_files::SceneFile;
+    use catalyst::{ContentClient, ContentId, Entity, EntityType, Server};
+    use dcl_common::Parcel;
+    use std::collections::HashMap;
+    use tempdir::TempDir;
+
+    async fn deploy_scene(server: &Server, parcels: Vec<Parcel>, scene: &[u8]) -> String {
+        let pointers: Vec<String> = parcels.iter().map(|p| format!("{},{}", p.0, p.1)).collect();
+        let template: SceneFile = serde_json::from_value(json!({
+            "version": "v3",
+            "type": "scene",
+            "pointers": pointers,
+            "timestamp": 0,
+            "content": [],
+            "metadata": { "scene": { "base": pointers[0], "parcels": pointers } },
+        }))
+        .unwrap();
+
+        let files = HashMap::from([("./2dcl/scene.2dcl".to_string(), scene.to_vec())]);
+        let (deploy_data, entity_id) =
+            scene_deployer::build_entity_scene(pointers, files, &template);
+        let signer =
+            dcl_crypto::Address::try_from("0xe2b6024873d218b2e83b462d3658d8d7c3f55a18").unwrap();
+        let auth_chain = dcl_crypto::AuthChain::from(vec![dcl_crypto::AuthLink::signer(signer)]);
+
+        let response =
+            scene_deployer::deploy(entity_id.clone(), deploy_data, auth_chain, server.clone())
+                .await
+                .unwrap();
+        assert_eq!(response.status(), 200, "{}", response.text().await.unwrap());
+        entity_id.0
+    }
+
+    #[tokio::test]
+    async fn scenes_can_be_deployed_and_downloaded() {
+        let tmp_dir = TempDir::new("catalyst-mock").unwrap();
+        let mock = MockServer::start(&tmp_dir).unwrap();
+        let server = Server::new(mock.url(""));
+
+        let status = ContentClient::status(&server).await.unwrap();
+        assert_eq!(status.version, "v3");
+
+        let entity_id = deploy_scene(&server, vec![Parcel(3, 4)], b"first version").await;
+        let scene_files = ContentClient::scene_files_for_parcels(&server, &vec![Parcel(3, 4)])
+            .await
+            .unwrap();
+        assert_eq!(scene_files.len(), 1);
+        assert_eq!(scene_files[0].id.as_ref().unwrap().0, entity_id);
+
+        let content = &scene_files[0].content[0];
+        let downloaded = tmp_dir.path().join("downloaded");
+        ContentClient::download(&server, content.cid.clone(), &downloaded)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(downloaded).unwrap(), b"first version");
+
+        let available = ContentClient::content_files_exists(
+            &server,
+            &vec![content.cid.clone(), ContentId::new("missing")],
+        )
+        .await
+        .unwrap();
+        assert!(available[0].available);
+        assert!(!available[1].available);
+
+        let information = ContentClient::entity_information(&server, &Entity::scene(&entity_id))
+            .await
+            .unwrap();
+        assert_eq!(information.auth_chain.len(), 1);
+
+        let new_entity_id = deploy_scene(&server, vec![Parcel(3, 4)], b"second version").await;
+        let snapshot = ContentClient::snapshot(&server).await.unwrap();
+        let scenes: Vec<catalyst::snapshot::EntitySnapshot<Parcel>> =
+            ContentClient::snapshot_entities(&server, EntityType::Scene, &snapshot)
+                .await
+                .unwrap();
+        assert_eq!(scenes.len(), 1);
+        assert_eq!(scenes[0].entity_id.0, new_entity_id);
+        assert_eq!(scenes[0].pointers, vec![Parcel(3, 4)]);
+    }
+
+    #[tokio::test]
+    async fn lists_pointer_changes_from_a_timestamp() {
+        let tmp_dir = TempDir::new("catalyst-mock").unwrap();
+        let mock = MockServer::start(&tmp_dir).unwrap();
+        let server = Server::new(mock.url(""));
+
+        let entities = tmp_dir.path().join("entities");
+        for (id, kind, pointer, local_timestamp) in [
+            ("scene_b", "scene", "1,1", 20),
+            ("scene_a", "scene", "0,0", 10),
+            ("wearable", "wearable", "urn:wearable", 15),
+        ] {
+            let entity =
+                json!({ "type": kind, "pointers": [pointer], "localTimestamp": local_timestamp });
+            std::fs::write(entities.join(format!("{}.json", id)), entity.to_string()).unwrap();
+        }
+
+        let changes: Vec<catalyst::pointer_changes::PointerChange<Parcel>> =
+            ContentClient::pointer_changes(&server, EntityType::Scene, 0)
+                .await
+                .unwrap();
+        let ids: Vec<&str> = changes
+            .iter()
+            .map(|change| change.entity_id.0.as_str())
+            .collect();
+        assert_eq!(ids, vec!["scene_a", "scene_b"]);
+        assert_eq!(changes[0].pointers, vec![Parcel(0, 0)]);
+
+        let changes: Vec<catalyst::pointer_changes::PointerChange<Parcel>> =
+            ContentClient::pointer_changes(&server, EntityType::Scene, 20)
+                .await
+                .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].entity_id.0, "scene_b");
+        assert_eq!(changes[0].local_timestamp, 20);
+    }
+
+    #[tokio::test]
+    async fn unknown_paths_are_not_found() {
+        let tmp_dir = TempDir::new("catalyst-mock").unwrap();
+        let mock = MockServer::start(&tmp_dir).unwrap();
+        let server = Server::new(mock.url(""));
+
+        let response = server.raw_get("/lambdas/profiles").await.unwrap();
+        assert_eq!(response.status(), 404);
+        let response = server
+            .raw_get("/content/contents/..%2Fentities")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[test]
+    fn parses_auth_chain_fields() {
+        assert_eq!(
+            auth_chain_field("authChain[12][signature]"),
+            Some((12, "signature"))
+        );
+        assert_eq!(auth_chain_field("authChain[x][type]"), None);
+        assert_eq!(auth_chain_field("entityId"), None);
+    }
+}
@@ -0,0 +1,363 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cid::multihash::{Code, MultihashDigest};
+use cid::Cid;
+use serde_json::{json, Map, Value};
+
+use crate::{MockCatalystError, Result};
+
+/// Entity types included in the snapshots.
+const ENTITY_TYPES: [&str; 5] = ["scene", "profile", "wearable", "store", "emote"];
+
+const SNAPSHOT_HEADER: &str = "### Decentraland json snapshot\n";
+
+/// The content of the server, saved in a local directory:
+///
+/// - `contents/<hash>`: every uploaded file, named by its hash. Entity files are here too.
+/// - `entities/<entity id>.json`: the active entities, as `/content/entities/active` returns
+///   them, with the `localTimestamp` and `authChain` of their deployment.
+///
+/// Entities can be added by hand to `entities/`, only `type` and `pointers` are required.
+#[derive(Clone)]
+pub struct Storage {
+    root: PathBuf,
+    // A deployment can replace several entities, so they're done one at a time.
+    deployments: Arc<Mutex<()>>,
+}
+
+impl Storage {
+    /// Opens the content in `root`, creating the directory if it doesn't exist.
+    pub fn new<T>(root: T) -> Result<Storage>
+    where
+        T: AsRef<Path>,
+    {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(root.join("contents"))?;
+        fs::create_dir_all(root.join("entities"))?;
+        Ok(Storage {
+            root,
+            deployments: Arc::new(Mutex::new(())),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn content(&self, hash: &str) -> Result<Vec<u8>> {
+        if !is_valid_hash(hash) {
+            return Err(MockCatalystError::NotFound);
+        }
+        fs::read(self.root.join("contents").join(hash)).map_err(|_| MockCatalystError::NotFound)
+    }
+
+    pub fn has_content(&self, hash: &str) -> bool {
+        is_valid_hash(hash) && self.root.join("contents").join(hash).is_file()
+    }
+
+    /// Saves `bytes` and returns their hash.
+    pub fn add_content(&self, bytes: &[u8]) -> Result<String> {
+        let hash = hash(bytes);
+        fs::write(self.root.join("contents").join(&hash), bytes)?;
+        Ok(hash)
+    }
+
+    /// Returns all the active entities, sorted by id.
+    pub fn entities(&self) -> Result<Vec<Value>> {
+        let mut entities = Vec::new();
+        for file in fs::read_dir(self.root.join("entities"))? {
+            let path = file?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+
+            let mut entity: Value = serde_json::from_slice(&fs::read(&path)?)?;
+            let Some(fields) = entity.as_object_mut() else {
+                return Err(MockCatalystError::InvalidRequest(format!(
+                    "{} isn't an entity",
+                    path.display()
+                )));
+            };
+
+            let id = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default();
+            fields.entry("id").or_insert_with(|| json!(id));
+            let timestamp = fields.get("timestamp").cloned().unwrap_or(json!(0));
+            fields.entry("localTimestamp").or_insert(timestamp);
+            fields.entry("authChain").or_insert_with(|| json!([]));
+            entities.push(entity);
+        }
+
+        entities.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+        Ok(entities)
+    }
+
+    /// Returns the active entities that have any of the `pointers` or `ids`.
+    pub fn active_entities(&self, pointers: &[String], ids: &[String]) -> Result<Vec<Value>> {
+        let pointers: Vec<String> = pointers.iter().map(|p| p.to_lowercase()).collect();
+        Ok(self
+            .entities()?
+            .into_iter()
+            .filter(|entity| {
+                ids.iter().any(|id| entity["id"] == json!(id))
+                    || entity_pointers(entity).any(|pointer| pointers.contains(&pointer))
+            })
+            .collect())
+    }
+
+    pub fn entity(&self, kind: &str, id: &str) -> Result<Value> {
+        self.entities()?
+            .into_iter()
+            .find(|entity| entity["id"] == json!(id) && entity["type"] == json!(kind))
+            .ok_or(MockCatalystError::NotFound)
+    }
+
+    /// Deploys the entity `entity_id`, replacing the active entities that share a pointer with it.
+    /// `files` are the uploaded files, with the hash they were uploaded with, and they must
+    /// include the entity file unless it was uploaded before. Returns the local timestamp of the
+    /// deployment.
+    pub fn deploy(
+        &self,
+        entity_id: &str,
+        files: &[(String, Vec<u8>)],
+        auth_chain: Vec<Value>,
+    ) -> Result<u64> {
+        for (name, bytes) in files {
+            let expected = hash(bytes);
+            if *name != expected {
+                return Err(MockCatalystError::InvalidHash {
+                    expected,
+                    found: name.clone(),
+                });
+            }
+        }
+
+        let entity_file = match files.iter().find(|(name, _)| name == entity_id) {
+            Some((_, bytes)) => bytes.clone(),
+            None => self
+                .content(entity_id)
+                .map_err(|_| MockCatalystError::MissingEntityFile(entity_id.to_string()))?,
+        };
+        let mut entity: Map<String, Value> = serde_json::from_slice(&entity_file)?;
+        if !entity.get("type").is_some_and(Value::is_string)
+            || !entity.get("pointers").is_some_and(Value::is_array)
+        {
+            return Err(MockCatalystError::InvalidRequest(
+                "the entity file needs a `type` and `pointers`".to_string(),
+            ));
+        }
+
+        for content in entity
+            .get("content")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let hash = content["hash"].as_str().unwrap_or_default();
+            if !self.has_content(hash) && !files.iter().any(|(name, _)| name == hash) {
+                return Err(MockCatalystError::MissingContent(hash.to_string()));
+            }
+        }
+
+        let _deployment = self
+            .deployments
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        for (_, bytes) in files {
+            self.add_content(bytes)?;
+        }
+
+        let local_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+        entity.insert("id".to_string(), json!(entity_id));
+        entity.insert("localTimestamp".to_string(), json!(local_timestamp));
+        entity.insert("authChain".to_string(), Value::Array(auth_chain));
+        let entity = Value::Object(entity);
+
+        let pointers: Vec<String> = entity_pointers(&entity).collect();
+        for replaced in self.active_entities(&pointers, &[entity_id.to_string()])? {
+            let id = replaced["id"].as_str().unwrap_or_default();
+            fs::remove_file(self.entity_path(id))?;
+        }
+
+        fs::write(
+            self.entity_path(entity_id),
+            serde_json::to_vec_pretty(&entity)?,
+        )?;
+        Ok(local_timestamp)
+    }
+
+    /// Saves a snapshot file of each entity type and returns where to find them, like
+    /// `/content/snapshot`.
+    pub fn snapshot(&self) -> Result<Value> {
+        let entities = self.entities()?;
+
+        let mut snapshots = Map::new();
+        let mut all = String::from(SNAPSHOT_HEADER);
+        let mut last_timestamp = 0;
+        for kind in ENTITY_TYPES {
+            let mut snapshot = String::from(SNAPSHOT_HEADER);
+            let mut last_type_timestamp = 0;
+            for entity in entities
+                .iter()
+                .filter(|entity| entity["type"] == json!(kind))
+            {
+                let local_timestamp = entity["localTimestamp"].as_u64().unwrap_or_default();
+                last_type_timestamp = last_type_timestamp.max(local_timestamp);
+
+                let line = json!({
+                    "entityId": entity["id"],
+                    "entityType": kind,
+                    "pointers": entity["pointers"],
+                    "localTimestamp": local_timestamp,
+                    "authChain": entity["authChain"],
+                });
+                snapshot.push_str(&format!("{}\n", line));
+            }
+
+            all.push_str(&snapshot[SNAPSHOT_HEADER.len()..]);
+            last_timestamp = last_timestamp.max(last_type_timestamp);
+            snapshots.insert(
+                kind.to_string(),
+                json!({
+                    "hash": self.add_content(snapshot.as_bytes())?,
+                    "lastIncludedDeploymentTimestamp": last_type_timestamp,
+                }),
+            );
+        }
+
+        Ok(json!({
+            "hash": self.add_content(all.as_bytes())?,
+            "lastIncludedDeploymentTimestamp": last_timestamp,
+            "entities": snapshots,
+        }))
+    }
+
+    fn entity_path(&self, id: &str) -> PathBuf {
+        self.root.join("entities").join(format!("{}.json", id))
+    }
+}
+
+/// The hash Catalyst servers use to identify files: a CIDv1 of their SHA-256.
+pub fn hash(bytes: &[u8]) -> String {
+    const RAW: u64 = 0x55;
+    Cid::new_v1(RAW, Code::Sha2_256.digest(bytes)).to_string()
+}
+
+fn is_valid_hash(hash: &str) -> bool {
+    !hash.is_empty() && hash.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn entity_pointers(entity: &Value) -> impl Iterator<Item = String> + '_ {
+    entity["pointers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn entity_file(storage: &Storage, pointers: &[&str], content: &[u8]) -> (String, Vec<u8>) {
+        let entity = json!({
+            "version": "v3",
+            "type": "scene",
+            "pointers": pointers,
+            "timestamp": 1,
+            "content": [{ "file": "scene.2dcl", "hash": storage.add_content(content).unwrap() }],
+        });
+        let bytes = serde_json::to_vec(&entity).unwrap();
+        (hash(&bytes), bytes)
+    }
+
+    #[test]
+    fn deployments_replace_the_entities_with_the_same_pointers() {
+        let tmp_dir = TempDir::new("catalyst-mock").unwrap();
+        let storage = Storage::new(&tmp_dir).unwrap();
+
+        let (first, bytes) = entity_file(&storage, &["0,0", "0,1"], b"first");
+        storage
+            .deploy(&first, &[(first.clone(), bytes)], vec![])
+            .unwrap();
+        let (other, bytes) = entity_file(&storage, &["5,5"], b"other");
+        storage
+            .deploy(&other, &[(other.clone(), bytes)], vec![])
+            .unwrap();
+        assert_eq!(storage.entities().unwrap().len(), 2);
+
+        let (second, bytes) = entity_file(&storage, &["0,1"], b"second");
+        storage
+            .deploy(&second, &[(second.clone(), bytes)], vec![])
+            .unwrap();
+
+        let active = storage.active_entities(&["0,0".to_string()], &[]).unwrap();
+        assert!(active.is_empty());
+        let active = storage.active_entities(&["0,1".to_string()], &[]).unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0]["id"], json!(second));
+        assert_eq!(storage.entities().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn deployments_check_the_uploaded_files() {
+        let tmp_dir = TempDir::new("catalyst-mock").unwrap();
+        let storage = Storage::new(&tmp_dir).unwrap();
+        let (id, bytes) = entity_file(&storage, &["0,0"], b"content");
+
+        let result = storage.deploy(&id, &[("not-the-hash".to_string(), bytes.clone())], vec![]);
+        assert!(matches!(result, Err(MockCatalystError::InvalidHash { .. })));
+
+        let result = storage.deploy(&id, &[], vec![]);
+        assert!(matches!(
+            result,
+            Err(MockCatalystError::MissingEntityFile(_))
+        ));
+
+        let entity = json!({
+            "type": "scene",
+            "pointers": ["0,0"],
+            "content": [{ "file": "missing.png", "hash": hash(b"missing") }],
+        });
+        let bytes = serde_json::to_vec(&entity).unwrap();
+        let id = hash(&bytes);
+        let result = storage.deploy(&id, &[(id.clone(), bytes)], vec![]);
+        assert!(matches!(result, Err(MockCatalystError::MissingContent(_))));
+        assert!(storage.entities().unwrap().is_empty());
+    }
+
+    #[test]
+    fn serves_entities_added_by_hand() {
+        let tmp_dir = TempDir::new("catalyst-mock").unwrap();
+        let storage = Storage::new(&tmp_dir).unwrap();
+        fs::write(
+            tmp_dir.path().join("entities").join("my-scene.json"),
+            r#"{ "type": "scene", "pointers": ["-1,-1"], "timestamp": 10, "content": [] }"#,
+        )
+        .unwrap();
+
+        let entity = storage.entity("scene", "my-scene").unwrap();
+        assert_eq!(entity["localTimestamp"], json!(10));
+        assert_eq!(entity["authChain"], json!([]));
+
+        let snapshot = storage.snapshot().unwrap();
+        assert_eq!(snapshot["lastIncludedDeploymentTimestamp"], json!(10));
+        let scenes = storage
+            .content(snapshot["entities"]["scene"]["hash"].as_str().unwrap())
+            .unwrap();
+        let scenes = String::from_utf8(scenes).unwrap();
+        assert!(scenes.starts_with(SNAPSHOT_HEADER));
+        assert!(scenes.contains(r#""entityId":"my-scene""#));
+    }
+}
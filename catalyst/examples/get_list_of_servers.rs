@@ -1,14 +1,15 @@
 extern crate catalyst;
 
+use catalyst::{LambdaClient, Server};
 use dcl_common::Result;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // let server = Server::production();
-    // let servers = LambdaClient::servers(&server).await?;
+    let server = Server::production();
+    let servers = LambdaClient::servers(&server).await?;
 
-    // for server in servers {
-    //     println!(" - {}", server.base_url);
-    // }
+    for server in servers {
+        println!(" - {}", server.base_url);
+    }
     Ok(())
 }
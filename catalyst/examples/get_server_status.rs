@@ -1,11 +1,12 @@
 extern crate catalyst;
 
+use catalyst::{LambdaClient, Server};
 use dcl_common::Result;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // let server = Server::development();
-    // let status = LambdaClient::status(&server).await?;
-    // println!("{:?}", status);
+    let server = Server::development();
+    let status = LambdaClient::status(&server).await?;
+    println!("{:?}", status);
     Ok(())
 }
@@ -0,0 +1,23 @@
+extern crate catalyst;
+
+use catalyst::snapshot::EntitySnapshot;
+use catalyst::*;
+use dcl_common::{Parcel, Result};
+use futures::StreamExt;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let server = Server::production();
+    let snapshot = ContentClient::snapshot(&server).await?;
+    let mut scenes =
+        ContentClient::snapshot_entities_stream(&server, EntityType::Scene, &snapshot).await?;
+
+    let mut parcels = 0;
+    while let Some(scene) = scenes.next().await {
+        let scene: EntitySnapshot<Parcel> = scene?;
+        parcels += scene.pointers.len();
+    }
+    println!("{} parcels have scenes", parcels);
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use reqwest::Client as ReqwestClient;
+use std::time::Duration;
+
+/// How a `Server` talks to its peer: timeouts for every request and retries for `GET`s, which
+/// are idempotent.
+///
+/// There's no limit to the time a whole request takes, so big downloads and uploads work on slow
+/// links as long as data keeps flowing.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let policy = catalyst::ClientPolicy {
+///     retries: 5,
+///     ..Default::default()
+/// };
+/// let server = catalyst::Server::with_policy("https://peer.decentraland.org", policy);
+/// assert_eq!(server.policy().read_timeout, Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientPolicy {
+    /// Time to wait for a connection to the server.
+    pub connect_timeout: Duration,
+    /// Time to wait for the server to answer, and then for each part of the response.
+    pub read_timeout: Duration,
+    /// Times a `GET` is retried after a connection error, a timeout or a `5xx` or
+    /// `429 Too Many Requests` answer.
+    pub retries: u32,
+    /// Time to wait before the first retry. It doubles on every retry, up to `max_backoff`.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl ClientPolicy {
+    /// Returns the time to wait before the retry number `retry`, starting at 0.
+    pub fn retry_delay(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+
+    pub(crate) fn http_client(&self) -> ReqwestClient {
+        ReqwestClient::builder()
+            .connect_timeout(self.connect_timeout)
+            .build()
+            .unwrap_or_else(|_| ReqwestClient::new())
+    }
+}
+
+impl Default for ClientPolicy {
+    fn default() -> Self {
+        ClientPolicy {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn retry_delay_grows_exponentially_up_to_max_backoff() {
+        let policy = ClientPolicy {
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+
+        assert_eq!(policy.retry_delay(0), Duration::from_millis(100));
+        assert_eq!(policy.retry_delay(1), Duration::from_millis(200));
+        assert_eq!(policy.retry_delay(2), Duration::from_millis(400));
+        assert_eq!(policy.retry_delay(3), Duration::from_millis(500));
+        assert_eq!(policy.retry_delay(40), Duration::from_millis(500));
+    }
+}
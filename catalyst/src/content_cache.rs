@@ -0,0 +1,473 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{CatalystError, ContentClient, ContentId, Result, Server};
+
+/// Size limit of the cache when none is given, 1GiB.
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Folder of the cache with the places each content was put in by `download`, one file per
+/// content named after its hash.
+const PLACED_DIR: &str = ".placed";
+
+/// A local cache of content files, keyed by their `ContentId`. Content never changes for a given
+/// id, so files are downloaded once and shared by every entity and tool that uses them.
+///
+/// When the files in the cache go over `max_size`, the least recently used ones are removed.
+/// The files placed somewhere else with `download` count towards `max_size` when they're copies,
+/// and they're removed with the content they came from.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use catalyst::{ContentCache, ContentId, Server};
+///
+/// let cache = ContentCache::with_default_dir().unwrap();
+/// let content_id = ContentId::new("bafkreie7l5g4bhyl5jnwvwi6cmq2rwkjm3xxwbfpheddjfwthg7gxqlrna");
+/// let path = cache.fetch(&Server::production(), &content_id).await;
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContentCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl ContentCache {
+    /// Constructs a cache that keeps its files in `dir`, with a size limit of 1GiB.
+    pub fn new<P>(dir: P) -> ContentCache
+    where
+        P: AsRef<Path>,
+    {
+        ContentCache {
+            dir: dir.as_ref().to_path_buf(),
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+
+    /// Constructs a cache in `default_dir`, or `None` if the platform doesn't have a cache
+    /// directory.
+    pub fn with_default_dir() -> Option<ContentCache> {
+        ContentCache::default_dir().map(ContentCache::new)
+    }
+
+    /// Returns the directory shared by every tool using the cache: `$XDG_CACHE_HOME/2dcl/content`
+    /// on Linux, and the equivalent folder in other platforms.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("2dcl").join("content"))
+    }
+
+    /// Sets the maximum size in bytes of the files in the cache.
+    pub fn with_max_size(mut self, max_size: u64) -> ContentCache {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    /// Returns the path of the content identified by `content_id` if it's in the cache, and marks
+    /// it as recently used.
+    pub fn get(&self, content_id: &ContentId) -> Option<PathBuf> {
+        let path = self.path(content_id).ok()?;
+        if !path.is_file() {
+            return None;
+        }
+
+        // The modification time is used to know which files were used last.
+        if let Ok(file) = File::options().write(true).open(&path) {
+            file.set_modified(SystemTime::now()).ok();
+        }
+        Some(path)
+    }
+
+    /// Returns the path of the content identified by `content_id`, downloading it from `server`
+    /// if it isn't in the cache yet.
+    pub async fn fetch(&self, server: &Server, content_id: &ContentId) -> Result<PathBuf> {
+        if let Some(path) = self.get(content_id) {
+            return Ok(path);
+        }
+
+        let path = self.path(content_id)?;
+        ContentClient::download(server, content_id.clone(), &path).await?;
+        self.evict(Some(&path))?;
+        Ok(path)
+    }
+
+    /// Places the content identified by `content_id` in the path given by `filename`, downloading
+    /// it from `server` if it isn't in the cache yet.
+    ///
+    /// `filename` is a hard link to the cached file when the file system allows it, so it should be
+    /// replaced instead of modified. It's removed when the content is evicted from the cache.
+    pub async fn download<V>(
+        &self,
+        server: &Server,
+        content_id: ContentId,
+        filename: V,
+    ) -> Result<()>
+    where
+        V: AsRef<Path>,
+    {
+        let path = self.fetch(server, &content_id).await?;
+        self.place(&path, filename.as_ref())
+    }
+
+    /// Places the content identified by `content_id` in the path given by `filename` like
+    /// `download` does, but only if it's in the cache. Returns whether it was.
+    pub fn download_cached<V>(&self, content_id: &ContentId, filename: V) -> Result<bool>
+    where
+        V: AsRef<Path>,
+    {
+        match self.get(content_id) {
+            Some(path) => self.place(&path, filename.as_ref()).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns the size in bytes of the files in the cache, and of the copies placed with
+    /// `download`.
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.entries()?.iter().map(|entry| entry.size).sum())
+    }
+
+    /// Removes every file in the cache.
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the path of `content_id` in the cache. Ids come from servers, so anything that
+    /// isn't a plain hash is rejected instead of becoming a path outside of the cache.
+    fn path(&self, content_id: &ContentId) -> Result<PathBuf> {
+        let hash = content_id.hash();
+        if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(CatalystError::InvalidContentId {
+                content_id: content_id.clone(),
+            });
+        }
+        Ok(self.dir.join(hash))
+    }
+
+    /// Places the cached `path` in `filename`, and keeps track of it so it's
+    /// counted and evicted with the content.
+    fn place(&self, path: &Path, filename: &Path) -> Result<()> {
+        if let Some(parent) = filename.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if filename.exists() {
+            fs::remove_file(filename)?;
+        }
+        let kind = match fs::hard_link(path, filename) {
+            Ok(_) => Placement::Link,
+            Err(_) => {
+                fs::copy(path, filename)?;
+                Placement::Copy
+            }
+        };
+
+        let placed_path = self.placed_path(path);
+        let filename = std::path::absolute(filename)?;
+        let placed = read_placed(&placed_path);
+        if !placed.iter().any(|placed| placed.filename == filename) {
+            if let Some(parent) = placed_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&placed_path)?;
+            writeln!(file, "{}\t{}", kind.as_str(), filename.display())?;
+        }
+
+        // Copies take space too, so they can push the cache over its size.
+        if kind == Placement::Copy {
+            self.evict(Some(path))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the file listing the places the cached `path` was put in.
+    fn placed_path(&self, path: &Path) -> PathBuf {
+        let mut placed_path = self.dir.join(PLACED_DIR);
+        if let Some(file_name) = path.file_name() {
+            placed_path.push(file_name);
+        }
+        placed_path
+    }
+
+    /// Removes the least recently used files until the cache fits in `max_size`, keeping `keep`
+    /// even if it doesn't fit by itself.
+    fn evict(&self, keep: Option<&Path>) -> Result<()> {
+        let mut entries = self.entries()?;
+        let mut size: u64 = entries.iter().map(|entry| entry.size).sum();
+        entries.sort_by_key(|entry| entry.used);
+
+        for entry in entries {
+            if size <= self.max_size {
+                break;
+            }
+            if Some(entry.path.as_path()) == keep {
+                continue;
+            }
+            // Another tool could be removing it too.
+            if fs::remove_file(&entry.path).is_ok() {
+                for placed in &entry.placed {
+                    fs::remove_file(&placed.filename).ok();
+                }
+                fs::remove_file(self.placed_path(&entry.path)).ok();
+                size -= entry.size;
+            }
+        }
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<Entry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let dir_entry = dir_entry?;
+            // Downloads in progress.
+            if dir_entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let metadata = dir_entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            // Hard links share the space of the cached file, copies take their own.
+            let placed = read_placed(&self.placed_path(&dir_entry.path()));
+            let copies_size: u64 = placed
+                .iter()
+                .filter(|placed| placed.kind == Placement::Copy)
+                .filter_map(|placed| fs::metadata(&placed.filename).ok())
+                .map(|metadata| metadata.len())
+                .sum();
+            entries.push(Entry {
+                path: dir_entry.path(),
+                size: metadata.len() + copies_size,
+                used: metadata.modified()?,
+                placed,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+struct Entry {
+    path: PathBuf,
+    size: u64,
+    used: SystemTime,
+    placed: Vec<Placed>,
+}
+
+/// A place a cached file was put in by `ContentCache::download`.
+struct Placed {
+    kind: Placement,
+    filename: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    Link,
+    Copy,
+}
+
+impl Placement {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Placement::Link => "link",
+            Placement::Copy => "copy",
+        }
+    }
+}
+
+/// Reads the places listed in `placed_path`, skipping the lines it doesn't understand.
+fn read_placed(placed_path: &Path) -> Vec<Placed> {
+    let contents = fs::read_to_string(placed_path).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (kind, filename) = line.split_once('\t')?;
+            let kind = match kind {
+                "link" => Placement::Link,
+                "copy" => Placement::Copy,
+                _ => return None,
+            };
+            Some(Placed {
+                kind,
+                filename: PathBuf::from(filename),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use std::time::Duration;
+    use tempdir::TempDir;
+
+    fn mock_content<'a>(server: &'a MockServer, content: &str) -> (ContentId, httpmock::Mock<'a>) {
+        let content_id = ContentId::from_content(content.as_bytes());
+        let mock = server.mock(|when, then| {
+            when.path(format!("/content/contents/{}", content_id));
+            then.status(200).body(content);
+        });
+        (content_id, mock)
+    }
+
+    #[test]
+    fn it_downloads_content_once() {
+        let server = MockServer::start();
+        let (content_id, m) = mock_content(&server, "File Content");
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-cache-test").unwrap();
+        let cache = ContentCache::new(tmp_dir.path().join("cache"));
+
+        for entity in ["first-entity", "second-entity"] {
+            let filename = tmp_dir.path().join(entity).join("file.txt");
+            tokio_test::block_on(cache.download(&server, content_id.clone(), &filename)).unwrap();
+            assert_eq!(fs::read_to_string(&filename).unwrap(), "File Content");
+        }
+
+        m.assert_hits(1);
+        assert_eq!(cache.size().unwrap(), "File Content".len() as u64);
+    }
+
+    #[test]
+    fn it_only_places_cached_content() {
+        let server = MockServer::start();
+        let (content_id, _) = mock_content(&server, "File Content");
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-cache-test").unwrap();
+        let cache = ContentCache::new(tmp_dir.path().join("cache"));
+        let filename = tmp_dir.path().join("file.txt");
+
+        assert!(!cache.download_cached(&content_id, &filename).unwrap());
+        assert!(!filename.exists());
+
+        tokio_test::block_on(cache.fetch(&server, &content_id)).unwrap();
+        assert!(cache.download_cached(&content_id, &filename).unwrap());
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "File Content");
+    }
+
+    #[test]
+    fn it_evicts_the_least_recently_used_content() {
+        let server = MockServer::start();
+        let (first, _) = mock_content(&server, "First");
+        let (second, _) = mock_content(&server, "Second");
+        let (third, _) = mock_content(&server, "Third");
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-cache-test").unwrap();
+        let cache = ContentCache::new(tmp_dir.path()).with_max_size(12);
+
+        let first_path = tokio_test::block_on(cache.fetch(&server, &first)).unwrap();
+        let second_path = tokio_test::block_on(cache.fetch(&server, &second)).unwrap();
+
+        // Makes `first` the most recently used, without relying on the clock resolution.
+        let old = SystemTime::now() - Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(&second_path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        assert_eq!(cache.get(&first), Some(first_path));
+
+        tokio_test::block_on(cache.fetch(&server, &third)).unwrap();
+
+        assert!(cache.get(&first).is_some());
+        assert!(cache.get(&second).is_none());
+        assert!(cache.get(&third).is_some());
+        assert_eq!(cache.size().unwrap(), 10);
+    }
+
+    #[test]
+    fn it_removes_placed_files_with_evicted_content() {
+        let server = MockServer::start();
+        let (first, _) = mock_content(&server, "First");
+        let (second, _) = mock_content(&server, "Second");
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-cache-test").unwrap();
+        let cache = ContentCache::new(tmp_dir.path().join("cache")).with_max_size(6);
+        let first_filename = tmp_dir.path().join("scene").join("first.txt");
+        let second_filename = tmp_dir.path().join("scene").join("second.txt");
+
+        tokio_test::block_on(cache.download(&server, first.clone(), &first_filename)).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(cache.get(&first).unwrap())
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        tokio_test::block_on(cache.download(&server, second, &second_filename)).unwrap();
+
+        assert!(cache.get(&first).is_none());
+        assert!(!first_filename.exists());
+        assert_eq!(fs::read_to_string(&second_filename).unwrap(), "Second");
+    }
+
+    #[test]
+    fn it_rejects_ids_that_are_not_hashes() {
+        let server = MockServer::start();
+        let m = server.mock(|_, then| {
+            then.status(200).body("Not in the cache");
+        });
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-cache-test").unwrap();
+        let cache = ContentCache::new(tmp_dir.path().join("cache"));
+        let outside = tmp_dir.path().join("outside");
+
+        for id in [
+            "../outside".to_string(),
+            outside.display().to_string(),
+            String::new(),
+        ] {
+            let content_id = ContentId::new(id);
+            assert!(cache.get(&content_id).is_none());
+            let result = tokio_test::block_on(cache.fetch(&server, &content_id));
+            assert!(matches!(
+                result,
+                Err(CatalystError::InvalidContentId { .. })
+            ));
+        }
+
+        m.assert_hits(0);
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn it_keeps_content_bigger_than_the_cache() {
+        let server = MockServer::start();
+        let (content_id, _) = mock_content(&server, "File Content");
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-cache-test").unwrap();
+        let cache = ContentCache::new(tmp_dir.path()).with_max_size(1);
+
+        let path = tokio_test::block_on(cache.fetch(&server, &content_id)).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "File Content");
+    }
+}
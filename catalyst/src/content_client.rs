@@ -1,14 +1,27 @@
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use crate::entity_files::SceneFile;
 use crate::entity_information::EntityInformation;
-use crate::snapshot::{EntitySnapshot, Snapshot};
+use crate::pointer_changes::{PointerChange, PointerChangesPage};
+use crate::server::error_for_status;
+use crate::snapshot::{self, EntitySnapshot, Snapshot};
 use crate::status::ContentServerStatus;
 use crate::*;
-use dcl_common::{Parcel, Result};
+use dcl_common::Parcel;
+
+/// Parcels asked for in each request of `scene_files_by_parcel`.
+const POINTERS_PER_REQUEST: usize = 100;
+
+/// Requests of `scene_files_by_parcel` running at the same time.
+const CONCURRENT_REQUESTS: usize = 4;
 
 /// Implements all the request to interact with [Catalyst Content Servers](https://decentraland.github.io/catalyst-api-specs/#tag/Content-Server).
 ///
@@ -60,24 +73,36 @@ impl ContentClient {
     }
 
     /// Download the file referenced by `content_id` in the path given by `filename`.
+    /// The content is checked against `content_id` (see `ContentId::is_hash_of`) and `filename`
+    /// is only written if it matches, so it's never left with partial content.
     /// [See on Catalyst API Docs](https://decentraland.github.io/catalyst-api-specs/#operation/getContentFile)
     pub async fn download<V>(server: &Server, content_id: ContentId, filename: V) -> Result<()>
     where
         V: AsRef<Path>,
     {
-        let response = server
-            .raw_get(format!("/content/contents/{}", content_id))
-            .await?;
+        let path = format!("/content/contents/{}", content_id);
+        let response = error_for_status(&path, server.raw_get(&path).await?).await?;
+        let content = server.read_body(response).await?;
+
+        if content_id.is_hash_of(&content) == Some(false) {
+            return Err(CatalystError::ContentMismatch { content_id });
+        }
 
-        if let Some(parent) = filename.as_ref().parent() {
+        let filename = filename.as_ref();
+        if let Some(parent) = filename.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let mut dest = File::create(filename)?;
-        let content = response.bytes().await?;
-        dest.write_all(&content)?;
+        // Written somewhere else first and moved to `filename` so it's replaced at once.
+        let temp_filename = temp_filename(filename);
+        let result = File::create(&temp_filename)
+            .and_then(|mut dest| dest.write_all(&content))
+            .and_then(|_| fs::rename(&temp_filename, filename));
+        if result.is_err() {
+            fs::remove_file(&temp_filename).ok();
+        }
 
-        Ok(())
+        Ok(result?)
     }
 
     /// Get information about the given `entity`.
@@ -100,38 +125,121 @@ impl ContentClient {
         Ok(result)
     }
 
+    /// Returns the deployments of entities of type `entity_type` made from the `from` timestamp
+    /// (included) onwards, oldest first, going through every page of them.
+    /// [See on Catalyst API Docs](https://decentraland.github.io/catalyst-api-specs/#operation/getPointerChanges)
+    pub async fn pointer_changes<T>(
+        server: &Server,
+        entity_type: EntityType,
+        from: u64,
+    ) -> Result<Vec<PointerChange<T>>>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        let mut path = format!(
+            "/content/pointer-changes?entityType={}&from={}&sortingField=local_timestamp&sortingOrder=ASC",
+            entity_type, from
+        );
+
+        let mut changes = Vec::new();
+        loop {
+            let page: PointerChangesPage<T> = server.get(&path).await?;
+            changes.extend(page.deltas);
+            match page.pagination.next {
+                Some(next) if page.pagination.more_data => {
+                    path = format!("/content/pointer-changes{}", next);
+                }
+                _ => break,
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Returns the scene content files for all the scenes that own the given `parcels`, indexed by
+    /// every parcel each scene owns. Parcels without a scene aren't in the result, and the parcels
+    /// of the same scene share it.
+    ///
+    /// Unlike `scene_files_for_parcels`, the parcels are asked for in batches with a few requests
+    /// at a time, so there's no limit in how many of them can be given.
+    pub async fn scene_files_by_parcel(
+        server: &Server,
+        parcels: &[Parcel],
+    ) -> Result<HashMap<Parcel, Arc<SceneFile>>> {
+        let mut unique_parcels = HashSet::new();
+        let parcels: Vec<Parcel> = parcels
+            .iter()
+            .filter(|parcel| unique_parcels.insert(*parcel))
+            .cloned()
+            .collect();
+
+        let mut batches = stream::iter(parcels.chunks(POINTERS_PER_REQUEST))
+            .map(|batch| async move {
+                let batch = batch.to_vec();
+                Self::scene_files_for_parcels(server, &batch).await
+            })
+            .buffer_unordered(CONCURRENT_REQUESTS);
+
+        // Scenes with several parcels can be in the response of more than one batch.
+        let mut entities = HashSet::new();
+        let mut scene_files = HashMap::new();
+        while let Some(batch) = batches.try_next().await? {
+            for scene_file in batch {
+                if let Some(id) = &scene_file.id {
+                    if !entities.insert(id.clone()) {
+                        continue;
+                    }
+                }
+                let scene_file = Arc::new(scene_file);
+                for pointer in &scene_file.pointers {
+                    if let Ok(parcel) = Parcel::from_str(pointer) {
+                        scene_files.insert(parcel, scene_file.clone());
+                    }
+                }
+            }
+        }
+        Ok(scene_files)
+    }
+
     /// Returns a list of entities (in the form of `EntitySnapshot`) for the given `entity_type` and `snapshot`.
+    /// Snapshots can be huge, see `snapshot_entities_stream` to go through them without loading
+    /// them whole in memory.
     pub async fn snapshot_entities<T>(
         server: &Server,
         entity_type: EntityType,
         snapshot: &Snapshot,
     ) -> Result<Vec<EntitySnapshot<T>>>
     where
-        T: for<'a> Deserialize<'a>,
+        T: for<'a> Deserialize<'a> + Send + 'static,
     {
-        let hash: &ContentId = match entity_type {
-            EntityType::Scene => &snapshot.entities.scene.hash,
-            EntityType::Profile => &snapshot.entities.profile.hash,
-            EntityType::Wearable => &snapshot.entities.wearable.hash,
-            EntityType::Emote => &snapshot.entities.emote.hash,
-        };
-
-        let response = server
-            .raw_get(format!("/content/contents/{}", hash))
-            .await?;
-
-        let text = response.text().await?;
-
-        let mut result: Vec<EntitySnapshot<T>> = vec![];
+        Self::snapshot_entities_stream(server, entity_type, snapshot)
+            .await?
+            .try_collect()
+            .await
+    }
 
-        for line in text.lines() {
-            if line.find('{') == Some(0) {
-                let snapshot: EntitySnapshot<T> = serde_json::from_str(line)?;
-                result.push(snapshot);
+    /// Returns the entities (in the form of `EntitySnapshot`) for the given `entity_type` and
+    /// `snapshot`, parsed as the snapshot file is downloaded.
+    pub async fn snapshot_entities_stream<T>(
+        server: &Server,
+        entity_type: EntityType,
+        snapshot: &Snapshot,
+    ) -> Result<BoxStream<'static, Result<EntitySnapshot<T>>>>
+    where
+        T: for<'a> Deserialize<'a> + Send + 'static,
+    {
+        let path = format!("/content/contents/{}", snapshot.entities_hash(entity_type));
+        let response = error_for_status(&path, server.raw_get(&path).await?).await?;
+
+        let chunks = stream::unfold(Some((server.clone(), response)), |state| async move {
+            let (server, mut response) = state?;
+            match server.read_chunk(&mut response).await {
+                Ok(Some(chunk)) => Some((Ok(chunk), Some((server, response)))),
+                Ok(None) => None,
+                Err(error) => Some((Err(error), None)),
             }
-        }
+        });
 
-        Ok(result)
+        Ok(snapshot::entities_from_chunks(path, chunks))
     }
 
     /// Returns a snapshot that includes the content ids for the entities available in the snapshot.
@@ -149,11 +257,28 @@ impl ContentClient {
     }
 }
 
+/// Returns a path next to `filename` to download it, different for each download.
+fn temp_filename(filename: &Path) -> PathBuf {
+    static DOWNLOADS: AtomicUsize = AtomicUsize::new(0);
+
+    let name = filename
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    filename.with_file_name(format!(
+        ".{}.{}-{}.download",
+        name,
+        std::process::id(),
+        DOWNLOADS.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use dcl_common::Parcel;
     use httpmock::prelude::*;
+    use serde_json::json;
     use std::fs;
     use tempdir::TempDir;
 
@@ -182,6 +307,102 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn it_gets_scene_files_by_parcel_in_batches() {
+        let response = include_str!("../fixtures/scenes_from_parcels.json");
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.method(POST).path("/content/entities/active");
+            then.status(200).body(response);
+        });
+
+        let server = Server::new(server.url(""));
+
+        let mut parcels: Vec<Parcel> = (0..150).map(|x| Parcel(x, 0)).collect();
+        parcels.push(Parcel(0, 0));
+        let result =
+            tokio_test::block_on(ContentClient::scene_files_by_parcel(&server, &parcels)).unwrap();
+
+        m.assert_hits(2);
+
+        let expected: Vec<SceneFile> = serde_json::from_str(response).unwrap();
+        assert_eq!(result.len(), expected[0].pointers.len());
+        assert_eq!(*result[&Parcel(0, 0)], expected[0]);
+        assert!(Arc::ptr_eq(
+            &result[&Parcel(0, 0)],
+            &result[&Parcel(-9, -9)]
+        ));
+    }
+
+    #[test]
+    fn it_gets_pointer_changes() {
+        let response = include_str!("../fixtures/pointer_changes.json");
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.method(GET)
+                .path("/content/pointer-changes")
+                .query_param("entityType", "scene")
+                .query_param("from", "1689000000000");
+            then.status(200).body(response);
+        });
+
+        let server = Server::new(server.url(""));
+
+        let changes: Vec<PointerChange<Parcel>> = tokio_test::block_on(
+            ContentClient::pointer_changes(&server, EntityType::Scene, 1689000000000),
+        )
+        .unwrap();
+
+        m.assert();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].pointers, vec![Parcel(-3, 7)]);
+    }
+
+    #[test]
+    fn it_goes_through_every_page_of_pointer_changes() {
+        let change = |entity_id: &str, local_timestamp: u64| {
+            json!({
+                "entityType": "scene",
+                "entityId": entity_id,
+                "localTimestamp": local_timestamp,
+                "pointers": ["0,0"]
+            })
+        };
+        let server = MockServer::start();
+
+        let first = server.mock(|when, then| {
+            when.path("/content/pointer-changes")
+                .query_param("from", "100");
+            then.status(200).json_body(json!({
+                "deltas": [change("first", 100)],
+                "pagination": { "moreData": true, "next": "?entityType=scene&from=150&lastId=first" }
+            }));
+        });
+        let second = server.mock(|when, then| {
+            when.path("/content/pointer-changes")
+                .query_param("from", "150")
+                .query_param("lastId", "first");
+            then.status(200).json_body(json!({
+                "deltas": [change("second", 200)],
+                "pagination": { "moreData": false }
+            }));
+        });
+
+        let server = Server::new(server.url(""));
+
+        let changes: Vec<PointerChange<Parcel>> = tokio_test::block_on(
+            ContentClient::pointer_changes(&server, EntityType::Scene, 100),
+        )
+        .unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].local_timestamp, 200);
+    }
+
     #[test]
     fn it_implements_content_files_exist() {
         let response = include_str!("../fixtures/available_content.json");
@@ -375,4 +596,89 @@ mod tests {
 
         assert_eq!(fs::read_to_string(filename).unwrap(), "File Content");
     }
+
+    #[test]
+    fn it_does_not_download_missing_files() {
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.path("/content/contents/a-hash");
+            then.status(404).body("{\"error\": \"Not found\"}");
+        });
+
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-client-test").unwrap();
+        let filename = tmp_dir.path().join("test.txt");
+
+        let result = tokio_test::block_on(ContentClient::download(
+            &server,
+            ContentId::new("a-hash"),
+            filename.clone(),
+        ));
+
+        m.assert();
+
+        assert!(matches!(result, Err(CatalystError::NotFound { .. })));
+        assert!(!filename.exists());
+    }
+
+    #[test]
+    fn it_checks_the_downloaded_content() {
+        let server = MockServer::start();
+        let content_id = ContentId::from_content(b"File Content");
+
+        let m = server.mock(|when, then| {
+            when.path(format!("/content/contents/{}", content_id));
+            then.status(200).body("File Cont");
+        });
+
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-client-test").unwrap();
+        let filename = tmp_dir.path().join("test.txt");
+        fs::write(&filename, "Old Content").unwrap();
+
+        let result = tokio_test::block_on(ContentClient::download(
+            &server,
+            content_id.clone(),
+            filename.clone(),
+        ));
+
+        m.assert();
+
+        assert!(
+            matches!(result, Err(CatalystError::ContentMismatch { content_id: id }) if id == content_id)
+        );
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "Old Content");
+        assert_eq!(fs::read_dir(tmp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn it_downloads_content_that_matches_its_hash() {
+        let server = MockServer::start();
+        let content_id = ContentId::new("QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o");
+
+        let m = server.mock(|when, then| {
+            when.path(format!("/content/contents/{}", content_id));
+            then.status(200).body("hello world\n");
+        });
+
+        let server = Server::new(server.url(""));
+
+        let tmp_dir = TempDir::new("content-client-test").unwrap();
+        let filename = tmp_dir.path().join("scenes").join("test.txt");
+
+        tokio_test::block_on(ContentClient::download(
+            &server,
+            content_id,
+            filename.clone(),
+        ))
+        .unwrap();
+
+        m.assert();
+
+        assert_eq!(fs::read_to_string(&filename).unwrap(), "hello world\n");
+        assert_eq!(fs::read_dir(filename.parent().unwrap()).unwrap().count(), 1);
+    }
 }
@@ -0,0 +1,202 @@
+//! Hashes of content files, as Catalyst servers compute them.
+//!
+//! New content is identified by a CIDv1 of the SHA-256 of the file (`bafkrei...`). Older content
+//! uses the hash IPFS gives to files: the root of a DAG of UnixFS nodes with the file split in
+//! chunks of 256KiB (`Qm...` for CIDv0 and `bafybei...` for CIDv1).
+
+use cid::multihash::{Code, MultihashDigest};
+use cid::{Cid, Version};
+
+const RAW: u64 = 0x55;
+const DAG_PB: u64 = 0x70;
+
+/// Default chunk size and links per node of the IPFS importers.
+const CHUNK_SIZE: usize = 262_144;
+const MAX_LINKS: usize = 174;
+
+/// Returns the CIDv1 of the SHA-256 of `bytes`, used for new content.
+pub(crate) fn raw_cid(bytes: &[u8]) -> Cid {
+    Cid::new_v1(RAW, Code::Sha2_256.digest(bytes))
+}
+
+/// Returns whether `hash` identifies `bytes`, or `None` if `hash` isn't a kind of hash that can
+/// be checked.
+pub(crate) fn is_hash_of(hash: &str, bytes: &[u8]) -> Option<bool> {
+    let cid = Cid::try_from(hash).ok()?;
+    let code = Code::try_from(cid.hash().code()).ok()?;
+
+    match cid.codec() {
+        RAW => Some(code.digest(bytes) == *cid.hash()),
+        DAG_PB => {
+            // CIDv1 files are usually imported with raw leaves, but not always.
+            let leaves: &[Leaves] = match cid.version() {
+                Version::V0 => &[Leaves::File],
+                Version::V1 => &[Leaves::Raw, Leaves::File],
+            };
+            Some(leaves.iter().any(|leaves| {
+                unixfs_root(bytes, code, cid.version(), *leaves).is_some_and(|root| root.cid == cid)
+            }))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Leaves {
+    /// Chunks are saved as they are.
+    Raw,
+    /// Chunks are saved in UnixFS file nodes.
+    File,
+}
+
+struct Node {
+    cid: Cid,
+    file_size: u64,
+    /// Size of the node and all the nodes it links to.
+    tree_size: u64,
+}
+
+fn unixfs_root(bytes: &[u8], code: Code, version: Version, leaves: Leaves) -> Option<Node> {
+    let mut level = if bytes.is_empty() {
+        vec![leaf(&[], code, version, leaves)?]
+    } else {
+        bytes
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| leaf(chunk, code, version, leaves))
+            .collect::<Option<Vec<Node>>>()?
+    };
+
+    while level.len() > 1 {
+        level = level
+            .chunks(MAX_LINKS)
+            .map(|children| parent(children, code, version))
+            .collect::<Option<Vec<Node>>>()?;
+    }
+
+    level.pop()
+}
+
+fn leaf(chunk: &[u8], code: Code, version: Version, leaves: Leaves) -> Option<Node> {
+    match leaves {
+        Leaves::Raw => Some(Node {
+            cid: Cid::new(Version::V1, RAW, code.digest(chunk)).ok()?,
+            file_size: chunk.len() as u64,
+            tree_size: chunk.len() as u64,
+        }),
+        Leaves::File => {
+            let data = unixfs_file(chunk, chunk.len() as u64, &[]);
+            let block = dag_pb_node(&[], &data);
+            Some(Node {
+                cid: Cid::new(version, DAG_PB, code.digest(&block)).ok()?,
+                file_size: chunk.len() as u64,
+                tree_size: block.len() as u64,
+            })
+        }
+    }
+}
+
+fn parent(children: &[Node], code: Code, version: Version) -> Option<Node> {
+    let file_size = children.iter().map(|child| child.file_size).sum();
+    let block_sizes: Vec<u64> = children.iter().map(|child| child.file_size).collect();
+    let data = unixfs_file(&[], file_size, &block_sizes);
+    let block = dag_pb_node(children, &data);
+
+    Some(Node {
+        cid: Cid::new(version, DAG_PB, code.digest(&block)).ok()?,
+        file_size,
+        tree_size: block.len() as u64 + children.iter().map(|c| c.tree_size).sum::<u64>(),
+    })
+}
+
+/// Encodes a UnixFS `Data` message of type `File`.
+fn unixfs_file(data: &[u8], file_size: u64, block_sizes: &[u64]) -> Vec<u8> {
+    const FILE: u64 = 2;
+
+    let mut message = Vec::new();
+    varint_field(&mut message, 1, FILE);
+    if !data.is_empty() {
+        bytes_field(&mut message, 2, data);
+    }
+    varint_field(&mut message, 3, file_size);
+    for block_size in block_sizes {
+        varint_field(&mut message, 4, *block_size);
+    }
+    message
+}
+
+/// Encodes a `PBNode` message, with its links before its data like IPFS does.
+fn dag_pb_node(links: &[Node], data: &[u8]) -> Vec<u8> {
+    let mut message = Vec::new();
+    for link in links {
+        let mut encoded_link = Vec::new();
+        bytes_field(&mut encoded_link, 1, &link.cid.to_bytes());
+        bytes_field(&mut encoded_link, 2, &[]);
+        varint_field(&mut encoded_link, 3, link.tree_size);
+        bytes_field(&mut message, 2, &encoded_link);
+    }
+    bytes_field(&mut message, 1, data);
+    message
+}
+
+fn varint_field(message: &mut Vec<u8>, field: u64, value: u64) {
+    varint(message, field << 3);
+    varint(message, value);
+}
+
+fn bytes_field(message: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    varint(message, field << 3 | 2);
+    varint(message, bytes.len() as u64);
+    message.extend_from_slice(bytes);
+}
+
+fn varint(message: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        message.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    message.push(value as u8);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_checks_raw_hashes() {
+        let hash = raw_cid(b"hello world\n").to_string();
+        assert!(hash.starts_with("bafkrei"));
+        assert_eq!(is_hash_of(&hash, b"hello world\n"), Some(true));
+        assert_eq!(is_hash_of(&hash, b"hello world"), Some(false));
+    }
+
+    #[test]
+    fn it_checks_ipfs_file_hashes() {
+        // Hashes given by `ipfs add`.
+        let hash = "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o";
+        assert_eq!(is_hash_of(hash, b"hello world\n"), Some(true));
+        assert_eq!(is_hash_of(hash, b"hello world"), Some(false));
+
+        let hash = "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH";
+        assert_eq!(is_hash_of(hash, b""), Some(true));
+    }
+
+    #[test]
+    fn it_checks_files_split_in_chunks() {
+        let bytes: Vec<u8> = (0..CHUNK_SIZE * 3 + 10).map(|i| i as u8).collect();
+        for (version, leaves) in [
+            (Version::V0, Leaves::File),
+            (Version::V1, Leaves::File),
+            (Version::V1, Leaves::Raw),
+        ] {
+            let root = unixfs_root(&bytes, Code::Sha2_256, version, leaves).unwrap();
+            assert_eq!(root.file_size, bytes.len() as u64);
+            assert_eq!(is_hash_of(&root.cid.to_string(), &bytes), Some(true));
+            assert_eq!(is_hash_of(&root.cid.to_string(), &bytes[1..]), Some(false));
+        }
+    }
+
+    #[test]
+    fn it_does_not_check_unknown_hashes() {
+        assert_eq!(is_hash_of("a-hash", b"content"), None);
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use crate::HashId;
+use crate::{content_hash, HashId};
 
 /// Used to represent a hash that is used in the context of a content file id.
 ///
@@ -42,6 +42,34 @@ impl ContentId {
     pub fn hash(&self) -> &HashId {
         &self.0
     }
+
+    /// Returns the `ContentId` new content gets in Catalyst servers: a CIDv1 of its SHA-256.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use catalyst::ContentId;
+    /// let cid = ContentId::from_content(b"hello world");
+    /// assert_eq!(cid.is_hash_of(b"hello world"), Some(true));
+    /// ```
+    pub fn from_content(bytes: &[u8]) -> ContentId {
+        ContentId(content_hash::raw_cid(bytes).to_string())
+    }
+
+    /// Returns whether this is the hash of `bytes`, either a CIDv1 of their SHA-256 or an IPFS
+    /// hash like `Qm...`, or `None` if it isn't a hash that can be checked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use catalyst::ContentId;
+    /// let cid = ContentId::new("QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o");
+    /// assert_eq!(cid.is_hash_of(b"hello world\n"), Some(true));
+    /// assert_eq!(ContentId::new("a-hash").is_hash_of(b"hello world\n"), None);
+    /// ```
+    pub fn is_hash_of(&self, bytes: &[u8]) -> Option<bool> {
+        content_hash::is_hash_of(&self.0, bytes)
+    }
 }
 
 impl fmt::Display for ContentId {
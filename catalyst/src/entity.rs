@@ -127,7 +127,7 @@ impl fmt::Display for EntityType {
 /// let message = format!("entity missing: {}", entityId);
 /// assert_eq!(message, "entity missing: a-missing-entity");
 /// ```
-#[derive(Debug, PartialEq, Eq, Deserialize, Serialize, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Deserialize, Serialize, Clone)]
 pub struct EntityId(pub HashId);
 
 impl EntityId {
@@ -0,0 +1,150 @@
+use crate::server::{is_retryable_error, is_retryable_status};
+use crate::ContentId;
+use reqwest::StatusCode;
+use std::error;
+use std::fmt;
+
+/// Length of the part of a response body kept in `CatalystError::Decode`.
+const BODY_SNIPPET_LENGTH: usize = 200;
+
+#[derive(Debug)]
+pub enum CatalystError {
+    /// The server answered with an error status.
+    Http {
+        status: u16,
+        body: String,
+    },
+    /// The request couldn't be sent or its response couldn't be read.
+    Transport(reqwest::Error),
+    /// The server took longer than the `read_timeout` of its `ClientPolicy` to answer, or to send
+    /// the next part of the response.
+    Timeout,
+    /// The response to `path` doesn't have the expected structure.
+    Decode {
+        path: String,
+        body_snippet: String,
+        error: serde_json::Error,
+    },
+    /// The server answered `404 Not Found` to `path`.
+    NotFound {
+        path: String,
+    },
+    /// The content downloaded doesn't match the hash it was requested with.
+    ContentMismatch {
+        content_id: ContentId,
+    },
+    /// The id isn't a hash, so it can't be used to name the content in a `ContentCache`.
+    InvalidContentId {
+        content_id: ContentId,
+    },
+    Io(std::io::Error),
+}
+
+impl CatalystError {
+    /// Whether the request could work if it's sent again, or sent to another peer.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CatalystError::Http { status, .. } => StatusCode::from_u16(*status)
+                .map(is_retryable_status)
+                .unwrap_or_default(),
+            CatalystError::Transport(error) => is_retryable_error(error),
+            CatalystError::Timeout => true,
+            // The download could have been cut short, or another peer could have the content.
+            CatalystError::ContentMismatch { .. } => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn decode<T>(path: T, body: &str, error: serde_json::Error) -> CatalystError
+    where
+        T: AsRef<str>,
+    {
+        let body_snippet = match body.char_indices().nth(BODY_SNIPPET_LENGTH) {
+            Some((end, _)) => format!("{}...", &body[..end]),
+            None => body.to_string(),
+        };
+
+        CatalystError::Decode {
+            path: path.as_ref().to_string(),
+            body_snippet,
+            error,
+        }
+    }
+}
+
+impl error::Error for CatalystError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CatalystError::Transport(error) => Some(error),
+            CatalystError::Decode { error, .. } => Some(error),
+            CatalystError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CatalystError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalystError::Http { status, body } => {
+                write!(f, "The server answered with status {}: {}", status, body)
+            }
+            CatalystError::Transport(error) => write!(f, "Request failed: {}", error),
+            CatalystError::Timeout => write!(f, "The server took too long to answer."),
+            CatalystError::Decode {
+                path,
+                body_snippet,
+                error,
+            } => write!(
+                f,
+                "Unexpected response from `{}`: {}\n{}",
+                path, error, body_snippet
+            ),
+            CatalystError::NotFound { path } => write!(f, "`{}` wasn't found.", path),
+            CatalystError::ContentMismatch { content_id } => write!(
+                f,
+                "The content downloaded doesn't match its hash `{}`.",
+                content_id
+            ),
+            CatalystError::InvalidContentId { content_id } => {
+                write!(f, "`{}` isn't a valid content hash.", content_id)
+            }
+            CatalystError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<reqwest::Error> for CatalystError {
+    fn from(error: reqwest::Error) -> Self {
+        CatalystError::Transport(error)
+    }
+}
+
+impl From<std::io::Error> for CatalystError {
+    fn from(error: std::io::Error) -> Self {
+        CatalystError::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_errors_keep_the_start_of_the_body() {
+        let error = serde_json::from_str::<u32>("x").unwrap_err();
+        let body = "é".repeat(BODY_SNIPPET_LENGTH * 2);
+        match CatalystError::decode("/content/status", &body, error) {
+            CatalystError::Decode {
+                path, body_snippet, ..
+            } => {
+                assert_eq!(path, "/content/status");
+                assert_eq!(
+                    body_snippet,
+                    format!("{}...", "é".repeat(BODY_SNIPPET_LENGTH))
+                );
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+}
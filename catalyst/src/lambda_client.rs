@@ -1,181 +1,241 @@
-// use crate::*;
-// use dcl_common::{ ContentId, Parcel, SceneId, Result};
-// use serde::Deserialize;
-
-// /// `LambdaClient` implements all the request to interact with [Catalyst Lambda](https://decentraland.github.io/catalyst-api-specs/#tag/Lambdas).
-// ///
-// #[derive(Default)]
-// pub struct LambdaClient {}
-// impl LambdaClient {
-//     /// Implements [`/lambda/contracts/servers`](https://decentraland.github.io/catalyst-api-specs/#operation/getServers)
-//     pub async fn servers(server: &Server) -> Result<Vec<Server>> {
-//         let servers: Vec<Server> = server.get("/lambdas/contracts/servers").await?;
-//         Ok(servers)
-//     }
-
-//     /// Implements [`/lambda/status`](https://decentraland.github.io/catalyst-api-specs/#operation/getLambdaStatus)
-//     pub async fn status(server: &Server) -> Result<server::Status> {
-//         let status: server::Status = server.get("/lambdas/status").await?;
-//         Ok(status)
-//     }
-
-//     /// Implements [`/lambda/contentv1/scenes`](https://decentraland.github.io/catalyst-api-specs/#operation/getScenes)
-//     pub async fn scenes(server: &Server, start: &Parcel, end: &Parcel) -> Result<Vec<Scene>> {
-//         let scenes: ScenesResult = server
-//             .get(format!(
-//                 "/lambdas/contentv2/scenes?x1={}&y1={}&x2={}&y2={}",
-//                 start.0, start.1, end.0, end.1
-//             ))
-//             .await?;
-//         Ok(scenes.data)
-//     }
-
-//     pub async fn scene_descriptor<T>(server: &Server, scene: Entity<T>) -> Result<SceneDescriptor>
-//     where T : AsRef<str> 
-//     {
-//         Ok(SceneDescriptor {})
-//     }
-// }
-
-// // #[derive(Debug, Deserialize)]
-// // pub struct SceneDescriptor {
-
-// // }
-
-// // #[derive(Debug, Deserialize)]
-// // pub struct Scene {
-// //     pub parcel_id: Parcel,
-// //     pub root_cid: ContentId,
-// //     pub scene_cid: ContentId,
-// // }
-
-// // #[derive(Debug, Deserialize)]
-// // struct ScenesResult {
-// //     data: Vec<Scene>,
-// // }
-
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use httpmock::prelude::*;
-
-//     #[test]
-//     fn it_can_be_created() {
-//         LambdaClient::default();
-//         assert!(true)
-//     }
-
-//     #[test]
-//     fn it_implements_server_list() {
-//         let response = "[
-//             {\"baseUrl\": \"https://server.com\",\"owner\": \"owner\",\"id\": \"id\"}
-//         ]";
-
-//         let server = MockServer::start();
-
-//         let m = server.mock(|when, then| {
-//             when.path("/lambdas/contracts/servers");
-//             then.status(200).body(response);
-//         });
-
-//         let server = Server::new(server.url(""));
-
-//         let servers = tokio_test::block_on(LambdaClient::servers(&server)).unwrap();
-
-//         m.assert();
-//         assert_eq!(servers[0].base_url, "https://server.com");
-//         assert_eq!(servers[0].owner, "owner");
-//         assert_eq!(servers[0].id, "id")
-//     }
-
-//     #[test]
-//     fn it_implements_server_status() {
-//         let response = "{\"version\": \"1.0\",\"currentTime\": 1628875330839,\"contentServerUrl\": \"https://content-server.com\",\"commitHash\": \"7890de4598f88a382863ea5f399b9cc17b80b42e\",\"catalystVersion\": \"1.3.3\"}";
-
-//         let server = MockServer::start();
-
-//         let m = server.mock(|when, then| {
-//             when.path("/lambdas/status");
-//             then.status(200).body(response);
-//         });
-
-//         let server = Server::new(server.url(""));
-
-//         let status = tokio_test::block_on(LambdaClient::status(&server)).unwrap();
-
-//         m.assert();
-
-//         assert_eq!(status.version, "1.0");
-//         assert_eq!(status.current_time, 1628875330839);
-//         assert_eq!(status.content_server_url, "https://content-server.com");
-//         assert_eq!(
-//             status.commit_hash,
-//             "7890de4598f88a382863ea5f399b9cc17b80b42e"
-//         );
-//         assert_eq!(status.catalyst_version, "1.3.3");
-//     }
-
-//     #[test]
-//     fn it_implements_scenes() {
-//         let response = "{ 
-//           \"data\": [{
-//             \"parcel_id\": \"-9,-9\",
-//             \"root_cid\": \"QmaGgvj8EyWXFuyMs9GM7nrxzNSVFgByvu5PBniUfPYm6Q\",
-//             \"scene_cid\": \"QmQ2bvXj4DVsBM1m25YyM3quJLdApA1uaoDTe7LBJi9k2d\"
-//           }]
-//         }";
-
-//         let server = MockServer::start();
-
-//         let m = server.mock(|when, then| {
-//             when.path("/lambdas/contentv2/scenes");
-//             then.status(200).body(response);
-//         });
-
-//         let server = Server::new(server.url(""));
-
-//         let scenes = tokio_test::block_on(LambdaClient::scenes(
-//             &server,
-//             &Parcel(-1, -1),
-//             &Parcel(1, 1),
-//         ))
-//         .unwrap();
-
-//         m.assert();
-
-//         assert_eq!(scenes[0].parcel_id, Parcel(-9, -9));
-//         assert_eq!(
-//             scenes[0].root_cid,
-//             ContentId("QmaGgvj8EyWXFuyMs9GM7nrxzNSVFgByvu5PBniUfPYm6Q".to_string())
-//         );
-//         assert_eq!(
-//             scenes[0].scene_cid,
-//             ContentId("QmQ2bvXj4DVsBM1m25YyM3quJLdApA1uaoDTe7LBJi9k2d".to_string())
-//         );
-//     }
-
-//     #[test]
-//     fn it_implements_scene_descriptor() {
-//         let response = include_str!("../fixtures/genesis_plaza_scene.json");
-
-//         let server = MockServer::start();
-
-//         let m = server.mock(|when, then| {
-//             when.path("/lambdas/contentv2/contents/hash");
-//             then.status(200).body(response);
-//         });
-
-//         let server = Server::new(server.url(""));
-
-//         let response = tokio_test::block_on(LambdaClient::scene_descriptor(
-//             &server,
-//             Entity::scene("hash")
-//         )).unwrap();
-
-//         m.assert();
-
-//         let expected : SceneDescriptor = serde_json::from_str(response).unwrap();
-//         assert_eq!(content_file, expected);
-
-//     }
-// }
+use serde::Serialize;
+
+use crate::profile::Profile;
+use crate::status::LambdaStatus;
+use crate::wearable::{Wearable, WearablesPage};
+use crate::*;
+
+/// Wearables asked for in each request of `LambdaClient::wearables`.
+const WEARABLES_PAGE_SIZE: usize = 100;
+
+/// `LambdaClient` implements all the request to interact with [Catalyst Lambda](https://decentraland.github.io/catalyst-api-specs/#tag/Lambdas).
+///
+#[derive(Default)]
+pub struct LambdaClient {}
+
+#[derive(Serialize)]
+struct ProfilesRequest<'a, T> {
+    ids: &'a [T],
+}
+
+impl LambdaClient {
+    /// Returns the Catalyst servers in the DAO.
+    /// [See on Catalyst API Docs](https://decentraland.github.io/catalyst-api-specs/#operation/getServers)
+    pub async fn servers(server: &Server) -> Result<Vec<Server>> {
+        let servers: Vec<Server> = server.get("/lambdas/contracts/servers").await?;
+        Ok(servers)
+    }
+
+    /// Returns information about the status of the lambdas of the server.
+    /// [See on Catalyst API Docs](https://decentraland.github.io/catalyst-api-specs/#operation/getLambdaStatus)
+    pub async fn status(server: &Server) -> Result<LambdaStatus> {
+        let status: LambdaStatus = server.get("/lambdas/status").await?;
+        Ok(status)
+    }
+
+    /// Returns the profiles of the users with the given Ethereum `addresses`.
+    /// [See on Catalyst API Docs](https://decentraland.github.io/catalyst-api-specs/#operation/getAvatarsDetailsByPost)
+    pub async fn profiles<T>(server: &Server, addresses: &[T]) -> Result<Vec<Profile>>
+    where
+        T: Serialize,
+    {
+        let request = ProfilesRequest { ids: addresses };
+        let profiles: Vec<Profile> = server.post("/lambdas/profiles", &request).await?;
+        Ok(profiles)
+    }
+
+    /// Returns the wearables owned by the user with the given Ethereum `address`, going through
+    /// every page of them.
+    /// [See on Catalyst API Docs](https://decentraland.github.io/catalyst-api-specs/#operation/getWearables)
+    pub async fn wearables<T>(server: &Server, address: T) -> Result<Vec<Wearable>>
+    where
+        T: AsRef<str>,
+    {
+        let mut wearables = Vec::new();
+        for page_num in 1.. {
+            let page: WearablesPage = server
+                .get(format!(
+                    "/lambdas/users/{}/wearables?pageNum={}&pageSize={}",
+                    address.as_ref(),
+                    page_num,
+                    WEARABLES_PAGE_SIZE
+                ))
+                .await?;
+
+            let last_page = page.elements.len() < WEARABLES_PAGE_SIZE;
+            wearables.extend(page.elements);
+            if last_page || wearables.len() >= page.total_amount {
+                break;
+            }
+        }
+        Ok(wearables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_implements_server_list() {
+        let response = "[
+            {\"baseUrl\": \"https://server.com\",\"owner\": \"owner\",\"id\": \"id\"}
+        ]";
+
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.path("/lambdas/contracts/servers");
+            then.status(200).body(response);
+        });
+
+        let server = Server::new(server.url(""));
+
+        let servers = tokio_test::block_on(LambdaClient::servers(&server)).unwrap();
+
+        m.assert();
+        assert_eq!(servers[0].base_url, "https://server.com");
+        assert_eq!(servers[0].owner, "owner");
+        assert_eq!(servers[0].id, "id")
+    }
+
+    #[test]
+    fn it_implements_server_status() {
+        let response = "{\"version\": \"1.0\",\"currentTime\": 1628875330839,\"contentServerUrl\": \"https://content-server.com\",\"commitHash\": \"7890de4598f88a382863ea5f399b9cc17b80b42e\",\"catalystVersion\": \"1.3.3\"}";
+
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.path("/lambdas/status");
+            then.status(200).body(response);
+        });
+
+        let server = Server::new(server.url(""));
+
+        let status = tokio_test::block_on(LambdaClient::status(&server)).unwrap();
+
+        m.assert();
+
+        assert_eq!(status.version, "1.0");
+        assert_eq!(status.current_time, 1628875330839);
+        assert_eq!(status.content_server_url, "https://content-server.com");
+        assert_eq!(
+            status.commit_hash,
+            "7890de4598f88a382863ea5f399b9cc17b80b42e"
+        );
+        assert_eq!(status.catalyst_version, "1.3.3");
+    }
+
+    #[test]
+    fn it_gets_profiles() {
+        let response = include_str!("../fixtures/profiles.json");
+        let address = "0x270722b5222968603e4650c3b70a7dfb971ed5b6";
+
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/lambdas/profiles")
+                .json_body(json!({ "ids": [address] }));
+            then.status(200).body(response);
+        });
+
+        let server = Server::new(server.url(""));
+
+        let profiles = tokio_test::block_on(LambdaClient::profiles(&server, &[address])).unwrap();
+
+        m.assert();
+
+        let expected: Vec<Profile> = serde_json::from_str(response).unwrap();
+        assert_eq!(profiles, expected);
+        assert_eq!(profiles[0].avatars[0].eth_address, address);
+    }
+
+    #[test]
+    fn it_gets_wearables() {
+        let response = include_str!("../fixtures/wearables.json");
+
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.method(GET)
+                .path("/lambdas/users/0xaddress/wearables")
+                .query_param("pageNum", "1");
+            then.status(200).body(response);
+        });
+
+        let server = Server::new(server.url(""));
+
+        let wearables =
+            tokio_test::block_on(LambdaClient::wearables(&server, "0xaddress")).unwrap();
+
+        m.assert();
+
+        assert_eq!(wearables.len(), 1);
+        assert_eq!(wearables[0].name, "2dcl Hoodie");
+        assert_eq!(wearables[0].category, "upper_body");
+        assert_eq!(wearables[0].amount, 2);
+    }
+
+    #[test]
+    fn it_goes_through_every_page_of_wearables() {
+        let wearable = |i: usize| json!({ "urn": format!("urn:wearable:{}", i), "amount": 1 });
+        let first_page: Vec<_> = (0..WEARABLES_PAGE_SIZE).map(wearable).collect();
+
+        let server = MockServer::start();
+
+        let first = server.mock(|when, then| {
+            when.path("/lambdas/users/0xaddress/wearables")
+                .query_param("pageNum", "1");
+            then.status(200).json_body(json!({
+                "elements": first_page,
+                "pageNum": 1,
+                "pageSize": WEARABLES_PAGE_SIZE,
+                "totalAmount": WEARABLES_PAGE_SIZE + 1
+            }));
+        });
+        let second = server.mock(|when, then| {
+            when.path("/lambdas/users/0xaddress/wearables")
+                .query_param("pageNum", "2");
+            then.status(200).json_body(json!({
+                "elements": [wearable(WEARABLES_PAGE_SIZE)],
+                "pageNum": 2,
+                "pageSize": WEARABLES_PAGE_SIZE,
+                "totalAmount": WEARABLES_PAGE_SIZE + 1
+            }));
+        });
+
+        let server = Server::new(server.url(""));
+
+        let wearables =
+            tokio_test::block_on(LambdaClient::wearables(&server, "0xaddress")).unwrap();
+
+        first.assert();
+        second.assert();
+
+        assert_eq!(wearables.len(), WEARABLES_PAGE_SIZE + 1);
+        assert_eq!(
+            wearables.last().unwrap().urn,
+            format!("urn:wearable:{}", WEARABLES_PAGE_SIZE)
+        );
+    }
+
+    #[test]
+    fn it_fails_for_unknown_users() {
+        let server = MockServer::start();
+
+        let m = server.mock(|when, then| {
+            when.path("/lambdas/users/0xaddress/wearables");
+            then.status(404);
+        });
+
+        let server = Server::new(server.url(""));
+
+        let result = tokio_test::block_on(LambdaClient::wearables(&server, "0xaddress"));
+
+        m.assert();
+        assert!(matches!(result, Err(CatalystError::NotFound { .. })));
+    }
+}
@@ -7,8 +7,8 @@
 mod content_client;
 pub use content_client::ContentClient;
 
-// mod lambda_client;
-// pub use lambda_client::LambdaClient;
+mod lambda_client;
+pub use lambda_client::LambdaClient;
 
 mod entity;
 pub use entity::Entity;
@@ -18,15 +18,34 @@ pub use entity::EntityType;
 mod content_id;
 pub use content_id::ContentId;
 
+mod content_hash;
+
+mod content_cache;
+pub use content_cache::ContentCache;
+
 pub mod entity_files;
 
 mod server;
 pub use server::Server;
 
+mod client_policy;
+pub use client_policy::ClientPolicy;
+
+mod server_pool;
+pub use server_pool::{PeerHealth, ServerPool};
+
+mod error;
+pub use error::CatalystError;
+
+pub type Result<T> = std::result::Result<T, CatalystError>;
+
 pub mod status;
 
 pub mod entity_information;
+pub mod pointer_changes;
+pub mod profile;
 pub mod snapshot;
+pub mod wearable;
 
 // Represents an id in the form of a hash, used for content files and entities.
 pub type HashId = String;
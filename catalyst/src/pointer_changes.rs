@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+use crate::entity_information::AuthChain;
+use crate::{EntityId, EntityType};
+
+/// A deployment that changed the entity that owns `pointers`, as returned by
+/// `ContentClient::pointer_changes`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PointerChange<T> {
+    pub entity_id: EntityId,
+    pub entity_type: EntityType,
+    pub pointers: Vec<T>,
+    pub local_timestamp: u64,
+    #[serde(default)]
+    pub auth_chain: Vec<AuthChain>,
+}
+
+/// A page of the pointer changes feed.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PointerChangesPage<T> {
+    pub deltas: Vec<PointerChange<T>>,
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Pagination {
+    #[serde(default)]
+    pub more_data: bool,
+    /// Query string of the next page.
+    pub next: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dcl_common::Parcel;
+
+    #[test]
+    fn it_deserializes_pointer_changes_from_json() {
+        let json = include_str!("../fixtures/pointer_changes.json");
+        let page: PointerChangesPage<Parcel> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(page.deltas.len(), 2);
+        assert_eq!(page.deltas[0].entity_type, EntityType::Scene);
+        assert_eq!(
+            page.deltas[0].pointers,
+            vec![Parcel(10, 10), Parcel(10, 11)]
+        );
+        assert_eq!(page.deltas[1].local_timestamp, 1689000060000);
+        assert!(!page.pagination.more_data);
+    }
+}
@@ -0,0 +1,81 @@
+use serde::Deserialize;
+
+use crate::Urn;
+
+/// A user profile, as returned by `LambdaClient::profiles`.
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Profile {
+    pub avatars: Vec<ProfileAvatar>,
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileAvatar {
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub eth_address: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub has_claimed_name: bool,
+    pub avatar: Avatar,
+}
+
+/// What an avatar looks like.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Avatar {
+    pub body_shape: Urn,
+    pub eyes: ColoredPart,
+    pub hair: ColoredPart,
+    pub skin: ColoredPart,
+    pub wearables: Vec<Urn>,
+    #[serde(default)]
+    pub snapshots: Option<Snapshots>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct ColoredPart {
+    pub color: Color,
+}
+
+/// A color with channels between `0.0` and `1.0`.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    #[serde(default = "opaque")]
+    pub a: f32,
+}
+
+fn opaque() -> f32 {
+    1.0
+}
+
+/// Pictures of the avatar.
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Snapshots {
+    pub face256: String,
+    pub body: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_deserializes_profiles_from_json() {
+        let json = include_str!("../fixtures/profiles.json");
+        let result: Vec<Profile> = serde_json::from_str(json).unwrap();
+        let avatar = &result[0].avatars[0];
+        assert_eq!(avatar.name, "hiddenpeople");
+        assert!(avatar.avatar.body_shape.ends_with("BaseMale"));
+        assert_eq!(avatar.avatar.hair.color.a, 1.0);
+    }
+}
@@ -1,5 +1,5 @@
-use dcl_common::Result;
-use reqwest::Client as ReqwestClient;
+use crate::{CatalystError, ClientPolicy, Result};
+use reqwest::{Client as ReqwestClient, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 
 /// A *single* catalyst server.
@@ -12,16 +12,8 @@ pub struct Server {
     pub id: String,
     #[serde(skip)]
     http_client: ReqwestClient,
-}
-
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct Status {
-    pub version: String,
-    pub current_time: u64,
-    pub content_server_url: String,
-    pub commit_hash: String,
-    pub catalyst_version: String,
+    #[serde(skip)]
+    policy: ClientPolicy,
 }
 
 #[derive(Deserialize, Debug)]
@@ -42,6 +34,26 @@ impl Server {
     /// assert_eq!(server.base_url, "https://my-awesome-server.org")
     /// ```
     pub fn new<T>(base_url: T) -> Server
+    where
+        T: AsRef<str>,
+    {
+        Server::with_policy(base_url, ClientPolicy::default())
+    }
+
+    /// Constructs a new `Server` using a custom `base_url` and the timeouts and retries in
+    /// `policy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let policy = catalyst::ClientPolicy {
+    ///     retries: 0,
+    ///     ..Default::default()
+    /// };
+    /// let server = catalyst::Server::with_policy("https://my-awesome-server.org", policy);
+    /// assert_eq!(server.policy().retries, 0)
+    /// ```
+    pub fn with_policy<T>(base_url: T, policy: ClientPolicy) -> Server
     where
         T: AsRef<str>,
     {
@@ -49,10 +61,15 @@ impl Server {
             base_url: base_url.as_ref().to_string(),
             owner: "".to_string(),
             id: "".to_string(),
-            http_client: ReqwestClient::new(),
+            http_client: policy.http_client(),
+            policy,
         }
     }
 
+    pub fn policy(&self) -> &ClientPolicy {
+        &self.policy
+    }
+
     /// Constructs a new `Server` using a development `base_url`
     /// (`https://server.decentraland.zone`).
     ///
@@ -93,7 +110,8 @@ impl Server {
     }
 
     /// Executes a `GET` request to `path`.
-    /// The response is parsed as JSON and deserialized in the result.
+    /// The response is parsed as JSON and deserialized in the result, unless the server answers
+    /// with an error status.
     /// If you need to deal with the result by hand, use `get_raw`.
     ///
     pub async fn get<U, R>(&self, path: U) -> Result<R>
@@ -101,28 +119,42 @@ impl Server {
         U: AsRef<str> + std::fmt::Display,
         R: for<'a> Deserialize<'a>,
     {
-        let response = self.raw_get(path).await?;
-        let text = response.text().await?;
-        let status: R = serde_json::from_str(text.as_str())?;
-        Ok(status)
+        let response = self.raw_get(&path).await?;
+        self.decode(path, response).await
     }
 
     /// Executes a `GET` request to `path`.
     /// The response is returned as is using `reqwest::Response`.
     /// For automatic deserialization of JSON response see `get`.
+    ///
+    /// The request is retried with exponential backoff as the `ClientPolicy` of the server
+    /// says, and the last response or error is returned. Read the body with `read_chunk` or
+    /// `read_body` so its parts are subject to the `read_timeout` of the policy too.
     pub async fn raw_get<U>(&self, path: U) -> Result<reqwest::Response>
     where
         U: AsRef<str> + std::fmt::Display,
     {
-        Ok(self
-            .http_client
-            .get(format!("{}{}", self.base_url, path))
-            .send()
-            .await?)
+        let url = format!("{}{}", self.base_url, path);
+        let mut retry = 0;
+        loop {
+            let result = self.send(self.http_client.get(&url)).await;
+            let should_retry = match &result {
+                Ok(response) => is_retryable_status(response.status()),
+                Err(error) => error.is_retryable(),
+            };
+
+            if !should_retry || retry >= self.policy.retries {
+                return result;
+            }
+
+            tokio::time::sleep(self.policy.retry_delay(retry)).await;
+            retry += 1;
+        }
     }
 
     /// Executes a `POST` request to `path` with body `body`.
-    /// The response is parsed as JSON and deserialized in the result.
+    /// The response is parsed as JSON and deserialized in the result, unless the server answers
+    /// with an error status.
     /// If you need to deal with the result by hand, use `get_raw`.
     ///
     pub async fn post<U, B, R>(&self, path: U, body: &B) -> Result<R>
@@ -131,10 +163,8 @@ impl Server {
         B: for<'a> Serialize,
         R: for<'a> Deserialize<'a>,
     {
-        let response = self.raw_post(path, body).await?;
-        let text = response.text().await?;
-        let status: R = serde_json::from_str(text.as_str())?;
-        Ok(status)
+        let response = self.raw_post(&path, body).await?;
+        self.decode(path, response).await
     }
 
     /// Executes a `POST` request to `path` with body `body`.
@@ -145,14 +175,17 @@ impl Server {
         U: AsRef<str> + std::fmt::Display,
         B: for<'a> Serialize,
     {
-        Ok(self
-            .http_client
-            .post(format!("{}{}", self.base_url, path))
-            .json(&body)
-            .send()
-            .await?)
+        self.send(
+            self.http_client
+                .post(format!("{}{}", self.base_url, path))
+                .json(&body),
+        )
+        .await
     }
 
+    /// Executes a multipart `POST` request to `path` with `form`.
+    /// Uploads can take long on slow links, so only the `connect_timeout` of the `ClientPolicy`
+    /// applies to them.
     pub async fn raw_post_form<U>(
         &self,
         path: U,
@@ -168,13 +201,88 @@ impl Server {
             .send()
             .await?)
     }
+
+    /// Reads the next part of the body of `response`, or `None` if it was all read.
+    /// Fails with `CatalystError::Timeout` if the server takes longer than the `read_timeout` of
+    /// the `ClientPolicy` to send it.
+    pub async fn read_chunk(&self, response: &mut Response) -> Result<Option<Vec<u8>>> {
+        match tokio::time::timeout(self.policy.read_timeout, response.chunk()).await {
+            Ok(chunk) => Ok(chunk?.map(|chunk| chunk.to_vec())),
+            Err(_) => Err(CatalystError::Timeout),
+        }
+    }
+
+    /// Reads the whole body of `response`, waiting at most the `read_timeout` of the
+    /// `ClientPolicy` for each part of it.
+    pub async fn read_body(&self, mut response: Response) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.read_chunk(&mut response).await? {
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    /// Sends `request`, waiting at most the `read_timeout` of the `ClientPolicy` for the answer.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        match tokio::time::timeout(self.policy.read_timeout, request.send()).await {
+            Ok(response) => Ok(response?),
+            Err(_) => Err(CatalystError::Timeout),
+        }
+    }
+
+    async fn decode<U, R>(&self, path: U, response: Response) -> Result<R>
+    where
+        U: AsRef<str>,
+        R: for<'a> Deserialize<'a>,
+    {
+        let response = error_for_status(&path, response).await?;
+        let body = self.read_body(response).await?;
+        serde_json::from_slice(&body)
+            .map_err(|error| CatalystError::decode(path, &String::from_utf8_lossy(&body), error))
+    }
+}
+
+/// Server errors and rate limits are worth another try, other errors would just happen again.
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+pub(crate) fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+}
+
+/// Returns the `response` to `path` if the server answered with a successful status.
+pub(crate) async fn error_for_status<U>(
+    path: U,
+    response: reqwest::Response,
+) -> Result<reqwest::Response>
+where
+    U: AsRef<str>,
+{
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND {
+        return Err(CatalystError::NotFound {
+            path: path.as_ref().to_string(),
+        });
+    }
+
+    if !status.is_success() {
+        return Err(CatalystError::Http {
+            status: status.as_u16(),
+            body: response.text().await.unwrap_or_default(),
+        });
+    }
+
+    Ok(response)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::status::LambdaStatus;
     use dcl_common::Parcel;
     use httpmock::prelude::*;
+    use std::time::Duration;
 
     #[test]
     fn it_builds_with_base_url() {
@@ -221,7 +329,7 @@ mod tests {
 
         let server = Server::new(server.url(""));
 
-        let status: Status = tokio_test::block_on(server.get("/lambdas/status")).unwrap();
+        let status: LambdaStatus = tokio_test::block_on(server.get("/lambdas/status")).unwrap();
 
         m.assert();
         assert_eq!(status.version, "1.0");
@@ -247,9 +355,11 @@ mod tests {
 
         let server = Server::new(server.url(""));
 
-        let response: reqwest::Response =
-            tokio_test::block_on(server.raw_get("/lambdas/status")).unwrap();
-        let body = tokio_test::block_on(response.text()).unwrap();
+        // The body has to be read in the same runtime, its timeout is a timer of that runtime.
+        let body = tokio_test::block_on(async {
+            let response: reqwest::Response = server.raw_get("/lambdas/status").await.unwrap();
+            response.text().await.unwrap()
+        });
 
         m.assert();
         assert_eq!(body, "this_is_not_json");
@@ -290,11 +400,120 @@ mod tests {
         let server = Server::new(server.url(""));
 
         let parcels = vec![Parcel(0, 0)];
-        let response: reqwest::Response =
-            tokio_test::block_on(server.raw_post("/some/path", &parcels)).unwrap();
-        let body = tokio_test::block_on(response.text()).unwrap();
+        let body = tokio_test::block_on(async {
+            let response: reqwest::Response =
+                server.raw_post("/some/path", &parcels).await.unwrap();
+            response.text().await.unwrap()
+        });
 
         m.assert();
         assert_eq!(body, "this_is_not_json");
     }
+
+    #[test]
+    fn it_retries_gets_that_fail_with_server_errors() {
+        let server = MockServer::start();
+        let m = server.mock(|when, then| {
+            when.path("/content/status");
+            then.status(503);
+        });
+        let policy = ClientPolicy {
+            retries: 2,
+            backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let server = Server::with_policy(server.url(""), policy);
+
+        let response = tokio_test::block_on(server.raw_get("/content/status")).unwrap();
+
+        m.assert_hits(3);
+        assert_eq!(response.status(), 503);
+    }
+
+    #[test]
+    fn it_does_not_retry_client_errors_or_posts() {
+        let server = MockServer::start();
+        let get = server.mock(|when, then| {
+            when.method(GET).path("/missing");
+            then.status(404);
+        });
+        let post = server.mock(|when, then| {
+            when.method(POST).path("/broken");
+            then.status(503);
+        });
+        let policy = ClientPolicy {
+            retries: 2,
+            backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let server = Server::with_policy(server.url(""), policy);
+
+        tokio_test::block_on(server.raw_get("/missing")).unwrap();
+        tokio_test::block_on(server.raw_post("/broken", &Parcel(0, 0))).unwrap();
+
+        get.assert_hits(1);
+        post.assert_hits(1);
+    }
+
+    #[test]
+    fn it_times_out() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.path("/slow");
+            then.status(200).delay(Duration::from_millis(500));
+        });
+        let policy = ClientPolicy {
+            read_timeout: Duration::from_millis(50),
+            retries: 0,
+            ..Default::default()
+        };
+        let server = Server::with_policy(server.url(""), policy);
+
+        let result = tokio_test::block_on(server.raw_get("/slow"));
+        assert!(matches!(result, Err(CatalystError::Timeout)));
+    }
+
+    #[test]
+    fn it_checks_the_status_before_decoding() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.path("/missing");
+            then.status(404).body("{}");
+        });
+        server.mock(|when, then| {
+            when.path("/broken");
+            then.status(400).body("invalid request");
+        });
+        let server = Server::new(server.url(""));
+
+        let result: Result<LambdaStatus> = tokio_test::block_on(server.get("/missing"));
+        assert!(matches!(result, Err(CatalystError::NotFound { path }) if path == "/missing"));
+
+        let result: Result<LambdaStatus> = tokio_test::block_on(server.get("/broken"));
+        assert!(matches!(
+            result,
+            Err(CatalystError::Http { status: 400, body }) if body == "invalid request"
+        ));
+    }
+
+    #[test]
+    fn it_reports_responses_that_cant_be_decoded() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/echo");
+            then.status(200).body("{\"not\": \"a parcel\"}");
+        });
+        let server = Server::new(server.url(""));
+
+        let result: Result<Parcel> = tokio_test::block_on(server.post("/echo", &Parcel(0, 0)));
+        match result {
+            Err(CatalystError::Decode {
+                path, body_snippet, ..
+            }) => {
+                assert_eq!(path, "/echo");
+                assert_eq!(body_snippet, "{\"not\": \"a parcel\"}");
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
 }
@@ -0,0 +1,301 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{CatalystError, ClientPolicy, ContentClient, Result, Server};
+
+/// What a `ServerPool` knows about one of its peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerHealth {
+    /// The peer hasn't been used or checked yet.
+    Unknown,
+    /// The last request to the peer got an answer.
+    Healthy,
+    /// The last request to the peer failed with a connection error, a timeout or a server
+    /// error.
+    Unhealthy,
+}
+
+impl PeerHealth {
+    fn preference(&self) -> u8 {
+        match self {
+            PeerHealth::Healthy => 0,
+            PeerHealth::Unknown => 1,
+            PeerHealth::Unhealthy => 2,
+        }
+    }
+}
+
+/// A list of Catalyst peers serving the same content. Requests go to the healthiest peer and
+/// fail over to the next one when it's down.
+///
+/// Clones of a pool share the health of its peers.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use catalyst::{ContentClient, Server, ServerPool};
+///
+/// let pool = ServerPool::new(vec![Server::production(), Server::new("https://peer-ec1.decentraland.org")]);
+/// let status = pool.run(|server| async move { ContentClient::status(&server).await }).await;
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServerPool {
+    servers: Vec<Server>,
+    health: Arc<Mutex<Vec<PeerHealth>>>,
+}
+
+impl ServerPool {
+    /// Constructs a pool with `servers`, in order of preference.
+    ///
+    /// # Panics
+    ///
+    /// If `servers` is empty.
+    pub fn new(servers: Vec<Server>) -> ServerPool {
+        assert!(
+            !servers.is_empty(),
+            "a server pool needs at least one server"
+        );
+
+        ServerPool {
+            health: Arc::new(Mutex::new(vec![PeerHealth::Unknown; servers.len()])),
+            servers,
+        }
+    }
+
+    pub fn servers(&self) -> &[Server] {
+        &self.servers
+    }
+
+    /// Returns the health of each server, in the same order as `servers`.
+    pub fn health(&self) -> Vec<PeerHealth> {
+        self.lock_health().clone()
+    }
+
+    /// Returns whether every server failed the last request or health check sent to it.
+    pub fn is_unreachable(&self) -> bool {
+        self.lock_health()
+            .iter()
+            .all(|health| *health == PeerHealth::Unhealthy)
+    }
+
+    /// Returns the server requests should go to now.
+    pub fn server(&self) -> Server {
+        self.servers[self.by_preference()[0]].clone()
+    }
+
+    /// Asks every server for its status to know which ones are healthy.
+    pub async fn check_health(&self) {
+        for (index, server) in self.servers.iter().enumerate() {
+            // A failed check is enough, the pool fails over to the next peer anyway.
+            let policy = ClientPolicy {
+                retries: 0,
+                ..server.policy().clone()
+            };
+            let server = Server::with_policy(&server.base_url, policy);
+            let health = match ContentClient::status(&server).await {
+                Ok(_) => PeerHealth::Healthy,
+                Err(_) => PeerHealth::Unhealthy,
+            };
+            self.lock_health()[index] = health;
+        }
+    }
+
+    /// Runs `request` against the healthiest server. If it fails with an error worth retrying
+    /// (see `CatalystError::is_retryable`), the server is marked as unhealthy and `request` is
+    /// run against the next one. Returns the result of the last server tried.
+    pub async fn run<F, Fut, T>(&self, request: F) -> Result<T>
+    where
+        F: FnMut(Server) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_with_fail_over(request, false).await
+    }
+
+    /// Like `run`, but `request` is also run against the next server when it fails with
+    /// `CatalystError::NotFound`, as content deployed recently may not be in every peer yet.
+    /// Servers answering `404 Not Found` aren't marked as unhealthy.
+    pub async fn run_until_found<F, Fut, T>(&self, request: F) -> Result<T>
+    where
+        F: FnMut(Server) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_with_fail_over(request, true).await
+    }
+
+    async fn run_with_fail_over<F, Fut, T>(&self, mut request: F, on_not_found: bool) -> Result<T>
+    where
+        F: FnMut(Server) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut result = None;
+        for index in self.by_preference() {
+            let response = request(self.servers[index].clone()).await;
+            let failed = matches!(&response, Err(error) if error.is_retryable());
+            let not_found = matches!(&response, Err(CatalystError::NotFound { .. }));
+            self.lock_health()[index] = if failed {
+                PeerHealth::Unhealthy
+            } else {
+                PeerHealth::Healthy
+            };
+
+            if !failed && !(on_not_found && not_found) {
+                return response;
+            }
+            result = Some(response);
+        }
+
+        result.expect("a server pool has at least one server")
+    }
+
+    /// Indexes of the servers, healthy ones first and keeping the order they were given.
+    fn by_preference(&self) -> Vec<usize> {
+        let health = self.lock_health();
+        let mut order: Vec<usize> = (0..self.servers.len()).collect();
+        order.sort_by_key(|index| health[*index].preference());
+        order
+    }
+
+    fn lock_health(&self) -> MutexGuard<'_, Vec<PeerHealth>> {
+        self.health
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use httpmock::prelude::*;
+    use std::time::Duration;
+
+    fn server(mock: &MockServer) -> Server {
+        let policy = ClientPolicy {
+            retries: 0,
+            backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        Server::with_policy(mock.url(""), policy)
+    }
+
+    fn status_mock(mock: &MockServer, status: u16) -> httpmock::Mock<'_> {
+        let response = include_str!("../fixtures/content_server_status.json");
+        mock.mock(|when, then| {
+            when.path("/content/status");
+            then.status(status).body(response);
+        })
+    }
+
+    #[test]
+    fn it_fails_over_to_the_next_server() {
+        let down = MockServer::start();
+        let up = MockServer::start();
+        let down_status = status_mock(&down, 503);
+        let up_status = status_mock(&up, 200);
+        let pool = ServerPool::new(vec![server(&down), server(&up)]);
+
+        let status = tokio_test::block_on(
+            pool.run(|server| async move { ContentClient::status(&server).await }),
+        )
+        .unwrap();
+
+        assert_eq!(status.version, "v3");
+        assert_eq!(
+            pool.health(),
+            vec![PeerHealth::Unhealthy, PeerHealth::Healthy]
+        );
+        assert_eq!(pool.server().base_url, up.url(""));
+
+        tokio_test::block_on(
+            pool.run(|server| async move { ContentClient::status(&server).await }),
+        )
+        .unwrap();
+        down_status.assert_hits(1);
+        up_status.assert_hits(2);
+    }
+
+    #[test]
+    fn it_does_not_fail_over_on_client_errors() {
+        let first = MockServer::start();
+        let second = MockServer::start();
+        first.mock(|when, then| {
+            when.path("/content/status");
+            then.status(404);
+        });
+        let second_status = status_mock(&second, 200);
+        let pool = ServerPool::new(vec![server(&first), server(&second)]);
+
+        let result = tokio_test::block_on(
+            pool.run(|server| async move { ContentClient::status(&server).await }),
+        );
+
+        assert!(matches!(result, Err(CatalystError::NotFound { .. })));
+        assert_eq!(
+            pool.health(),
+            vec![PeerHealth::Healthy, PeerHealth::Unknown]
+        );
+        second_status.assert_hits(0);
+    }
+
+    #[test]
+    fn it_looks_for_missing_content_in_the_next_server() {
+        let first = MockServer::start();
+        let second = MockServer::start();
+        let first_status = first.mock(|when, then| {
+            when.path("/content/status");
+            then.status(404);
+        });
+        let second_status = status_mock(&second, 200);
+        let pool = ServerPool::new(vec![server(&first), server(&second)]);
+
+        let status = tokio_test::block_on(
+            pool.run_until_found(|server| async move { ContentClient::status(&server).await }),
+        )
+        .unwrap();
+
+        assert_eq!(status.version, "v3");
+        assert_eq!(pool.health(), vec![PeerHealth::Healthy; 2]);
+        first_status.assert_hits(1);
+        second_status.assert_hits(1);
+    }
+
+    #[test]
+    fn it_returns_the_last_error_when_every_server_is_down() {
+        let first = MockServer::start();
+        let second = MockServer::start();
+        status_mock(&first, 503);
+        status_mock(&second, 500);
+        let pool = ServerPool::new(vec![server(&first), server(&second)]);
+
+        let result = tokio_test::block_on(
+            pool.run(|server| async move { ContentClient::status(&server).await }),
+        );
+
+        assert!(matches!(
+            result,
+            Err(CatalystError::Http { status: 500, .. })
+        ));
+        assert_eq!(pool.health(), vec![PeerHealth::Unhealthy; 2]);
+        assert!(pool.is_unreachable());
+    }
+
+    #[test]
+    fn it_checks_the_health_of_every_server() {
+        let down = MockServer::start();
+        let up = MockServer::start();
+        status_mock(&down, 500);
+        status_mock(&up, 200);
+        let pool = ServerPool::new(vec![server(&down), server(&up)]);
+        let clone = pool.clone();
+
+        tokio_test::block_on(pool.check_health());
+
+        assert_eq!(
+            clone.health(),
+            vec![PeerHealth::Unhealthy, PeerHealth::Healthy]
+        );
+        assert_eq!(clone.server().base_url, up.url(""));
+        assert!(!clone.is_unreachable());
+    }
+}
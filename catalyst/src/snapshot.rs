@@ -1,6 +1,13 @@
 use crate::entity_information::AuthChain;
-use crate::{ContentId, EntityId, EntityType};
+use crate::{CatalystError, ContentId, EntityId, EntityType, Result};
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
 use serde::Deserialize;
+use std::collections::VecDeque;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// Size of the chunks read from snapshot files.
+const CHUNK_SIZE: usize = 64 * 1024;
 
 #[derive(Debug, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -36,10 +43,158 @@ pub struct EntitySnapshot<T> {
     pub auth_chain: Vec<AuthChain>,
 }
 
+impl Snapshot {
+    /// Returns the hash of the snapshot file with the entities of type `entity_type`.
+    pub fn entities_hash(&self, entity_type: EntityType) -> &ContentId {
+        match entity_type {
+            EntityType::Scene => &self.entities.scene.hash,
+            EntityType::Profile => &self.entities.profile.hash,
+            EntityType::Wearable => &self.entities.wearable.hash,
+            EntityType::Emote => &self.entities.emote.hash,
+        }
+    }
+}
+
+/// Reads the entities of a snapshot file saved in `path`, as they're read from disk.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use catalyst::snapshot::{self, EntitySnapshot};
+/// use dcl_common::Parcel;
+/// use futures::StreamExt;
+///
+/// let mut scenes = snapshot::entities_from_file::<Parcel, _>("scenes.json").await.unwrap();
+/// while let Some(scene) = scenes.next().await {
+///     let scene: EntitySnapshot<Parcel> = scene.unwrap();
+/// }
+/// # });
+/// ```
+pub async fn entities_from_file<T, P>(
+    path: P,
+) -> Result<BoxStream<'static, Result<EntitySnapshot<T>>>>
+where
+    T: for<'a> Deserialize<'a> + Send + 'static,
+    P: AsRef<Path>,
+{
+    let name = path.as_ref().display().to_string();
+    let file = tokio::fs::File::open(path).await?;
+
+    let chunks = stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok(chunk), Some(file)))
+            }
+            Err(error) => Some((Err(error.into()), None)),
+        }
+    });
+
+    Ok(entities_from_chunks(name, chunks))
+}
+
+/// Parses the lines of a snapshot file as its `chunks` arrive. `path` is where the file comes
+/// from, for errors.
+pub(crate) fn entities_from_chunks<T, C>(
+    path: String,
+    chunks: C,
+) -> BoxStream<'static, Result<EntitySnapshot<T>>>
+where
+    T: for<'a> Deserialize<'a> + Send + 'static,
+    C: Stream<Item = Result<Vec<u8>>> + Send + 'static,
+{
+    let state = Lines {
+        path,
+        chunks: chunks.boxed(),
+        lines: VecDeque::new(),
+        partial: Vec::new(),
+        done: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            while let Some(line) = state.lines.pop_front() {
+                if let Some(entity) = parse_line(&state.path, &line) {
+                    return Some((entity, state));
+                }
+            }
+
+            if state.done {
+                let line = std::mem::take(&mut state.partial);
+                return parse_line(&state.path, &line).map(|entity| (entity, state));
+            }
+
+            match state.chunks.next().await {
+                Some(Ok(chunk)) => state.push(&chunk),
+                Some(Err(error)) => {
+                    state.done = true;
+                    state.partial.clear();
+                    return Some((Err(error), state));
+                }
+                None => state.done = true,
+            }
+        }
+    })
+    .boxed()
+}
+
+struct Lines {
+    path: String,
+    chunks: BoxStream<'static, Result<Vec<u8>>>,
+    lines: VecDeque<Vec<u8>>,
+    partial: Vec<u8>,
+    done: bool,
+}
+
+impl Lines {
+    fn push(&mut self, chunk: &[u8]) {
+        let mut pieces = chunk.split(|byte| *byte == b'\n');
+        if let Some(first) = pieces.next() {
+            self.partial.extend_from_slice(first);
+        }
+        for piece in pieces {
+            self.lines
+                .push_back(std::mem::replace(&mut self.partial, piece.to_vec()));
+        }
+    }
+}
+
+/// Lines that aren't JSON objects, like the header of the file, are skipped.
+fn parse_line<T>(path: &str, line: &[u8]) -> Option<Result<EntitySnapshot<T>>>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    if !line.starts_with(b"{") {
+        return None;
+    }
+
+    Some(
+        serde_json::from_slice(line)
+            .map_err(|error| CatalystError::decode(path, &String::from_utf8_lossy(line), error)),
+    )
+}
+
 #[cfg(test)]
 mod test {
+    use super::*;
     use crate::snapshot::Snapshot;
     use crate::ContentId;
+    use dcl_common::Parcel;
+    use futures::TryStreamExt;
+    use tempdir::TempDir;
+
+    const ENTITIES: &str = "### Decentraland json snapshot\n\
+        {\"entityId\":\"a\",\"entityType\":\"scene\",\"pointers\":[\"0,0\"],\"localTimestamp\":1,\"authChain\":[]}\r\n\
+        {\"entityId\":\"b\",\"entityType\":\"scene\",\"pointers\":[\"1,0\",\"1,1\"],\"localTimestamp\":2,\"authChain\":[]}";
+
+    fn ids(entities: Vec<EntitySnapshot<Parcel>>) -> Vec<String> {
+        entities.into_iter().map(|e| e.entity_id.0).collect()
+    }
 
     #[test]
     fn it_deserializes_from_json() {
@@ -50,4 +205,52 @@ mod test {
             ContentId::new("bafybeifkmnczrywizlqhfirodivjenxyzu33k7wk4azxi7upklzfc5h3uy")
         );
     }
+
+    #[test]
+    fn it_parses_entities_split_across_chunks() {
+        for chunk_size in [1, 7, 100, ENTITIES.len()] {
+            let chunks: Vec<Result<Vec<u8>>> = ENTITIES
+                .as_bytes()
+                .chunks(chunk_size)
+                .map(|chunk| Ok(chunk.to_vec()))
+                .collect();
+            let entities: Vec<EntitySnapshot<Parcel>> = tokio_test::block_on(
+                entities_from_chunks("snapshot".to_string(), stream::iter(chunks)).try_collect(),
+            )
+            .unwrap();
+
+            assert_eq!(ids(entities), vec!["a", "b"]);
+        }
+    }
+
+    #[test]
+    fn it_yields_error_and_continues_on_invalid_lines() {
+        let lines = format!("{{\"entityId\": 1}}\n{}", ENTITIES);
+        let chunks: Vec<Result<Vec<u8>>> = vec![Ok(lines.into_bytes())];
+        let mut entities: Vec<Result<EntitySnapshot<Parcel>>> = tokio_test::block_on(
+            entities_from_chunks("snapshot".to_string(), stream::iter(chunks)).collect(),
+        );
+
+        assert!(matches!(
+            &entities[0],
+            Err(CatalystError::Decode { path, body_snippet, .. })
+                if path == "snapshot" && body_snippet == "{\"entityId\": 1}"
+        ));
+        let rest: Vec<EntitySnapshot<Parcel>> = entities.drain(1..).collect::<Result<_>>().unwrap();
+        assert_eq!(ids(rest), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn it_reads_entities_from_files() {
+        let tmp_dir = TempDir::new("snapshot-test").unwrap();
+        let path = tmp_dir.path().join("scenes.json");
+        std::fs::write(&path, ENTITIES).unwrap();
+
+        let entities: Vec<EntitySnapshot<Parcel>> = tokio_test::block_on(async {
+            entities_from_file(&path).await.unwrap().try_collect().await
+        })
+        .unwrap();
+
+        assert_eq!(ids(entities), vec!["a", "b"]);
+    }
 }
@@ -22,6 +22,16 @@ pub struct ContentServerStatus {
 #[derive(Debug, Deserialize, Eq, PartialEq)]
 pub struct SynchronizationStatus {}
 
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LambdaStatus {
+    pub version: String,
+    pub current_time: u64,
+    pub content_server_url: String,
+    pub commit_hash: String,
+    pub catalyst_version: String,
+}
+
 #[cfg(test)]
 mod test {
     use crate::status::ContentServerStatus;
@@ -0,0 +1,27 @@
+use serde::Deserialize;
+
+use crate::Urn;
+
+/// A wearable owned by a user, as returned by `LambdaClient::wearables`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Wearable {
+    pub urn: Urn,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub rarity: Option<String>,
+    /// How many of this wearable the user owns.
+    #[serde(default)]
+    pub amount: u64,
+}
+
+/// A page of the wearables owned by a user.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WearablesPage {
+    pub elements: Vec<Wearable>,
+    pub total_amount: usize,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub enum Activation {
+    #[default]
+    Interact,
+    Enter,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn can_be_serialized_from_json() {
+        can_go_from_json_to_mp::<Activation, _>("activation/interact");
+        can_go_from_json_to_mp::<Activation, _>("activation/enter");
+    }
+}
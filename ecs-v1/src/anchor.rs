@@ -13,7 +13,11 @@ pub enum Anchor {
     TopLeft,
     TopCenter,
     TopRight,
+    /// Pixels from the bottom left corner of the sprite, clamped to its bounds.
     Custom(Vec2<i32>),
+    /// Like `Custom`, but it can be outside of the sprite. The compiler uses it to place the tiles
+    /// of sliced images.
+    Unbounded(Vec2<i32>),
 }
 
 #[cfg(test)]
@@ -38,4 +42,9 @@ mod test {
     fn can_serialize_custom_value_from_json() {
         can_go_from_json_to_mp::<Anchor, _>("anchor/custom");
     }
+
+    #[test]
+    fn can_serialize_unbounded_value_from_json() {
+        can_go_from_json_to_mp::<Anchor, _>("anchor/unbounded");
+    }
 }
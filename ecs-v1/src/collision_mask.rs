@@ -0,0 +1,175 @@
+//! Collision masks of `MaskCollider`s, precomputed by the compiler so clients don't have to decode
+//! the sprites.
+//!
+//! A mask file has a 12 bytes header followed by one bit per pixel, row by row from the top left
+//! corner. Every row starts on a new byte, and the first pixel is the most significant bit.
+//!
+//! | bytes    | content                                 |
+//! |----------|-----------------------------------------|
+//! | `0..4`   | `MAGIC`                                 |
+//! | `4..8`   | width, `u32` little endian              |
+//! | `8..12`  | height, `u32` little endian             |
+//! | `12..`   | bits                                    |
+
+use crate::color::Channel;
+use crate::error::CollisionMaskError;
+use std::io::Write;
+
+pub const MAGIC: &[u8; 4] = b"2DCM";
+
+const HEADER_SIZE: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CollisionMask {
+    width: u32,
+    height: u32,
+    bits: Vec<u8>,
+}
+
+impl CollisionMask {
+    /// Creates a mask from RGBA pixels, a pixel collides if its `channel` isn't 0.
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8], channel: &Channel) -> Self {
+        let mut mask = CollisionMask {
+            width,
+            height,
+            bits: vec![0; row_size(width) * height as usize],
+        };
+
+        let channel = channel.clone() as usize;
+        for (index, pixel) in pixels
+            .chunks_exact(4)
+            .take(width as usize * height as usize)
+            .enumerate()
+        {
+            if pixel[channel] > 0 {
+                let index = index as u32;
+                mask.set(index % width, index / width);
+            }
+        }
+        mask
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns `true` if the pixel collides. Pixels outside the mask don't.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let (byte, bit) = self.position(x, y);
+        self.bits[byte] & bit != 0
+    }
+
+    fn set(&mut self, x: u32, y: u32) {
+        let (byte, bit) = self.position(x, y);
+        self.bits[byte] |= bit;
+    }
+
+    fn position(&self, x: u32, y: u32) -> (usize, u8) {
+        let byte = y as usize * row_size(self.width) + x as usize / 8;
+        (byte, 0x80 >> (x % 8))
+    }
+
+    pub fn read(data: &[u8]) -> Result<Self, CollisionMaskError> {
+        if data.len() < HEADER_SIZE || !data.starts_with(MAGIC) {
+            return Err(CollisionMaskError::InvalidHeader);
+        }
+
+        let mut width = [0; 4];
+        width.copy_from_slice(&data[4..8]);
+        let mut height = [0; 4];
+        height.copy_from_slice(&data[8..HEADER_SIZE]);
+        let width = u32::from_le_bytes(width);
+        let height = u32::from_le_bytes(height);
+
+        let bits = &data[HEADER_SIZE..];
+        let expected = row_size(width) * height as usize;
+        if bits.len() != expected {
+            return Err(CollisionMaskError::InvalidSize {
+                expected,
+                found: bits.len(),
+            });
+        }
+
+        Ok(CollisionMask {
+            width,
+            height,
+            bits: bits.to_vec(),
+        })
+    }
+
+    pub fn write<W>(&self, mut writer: W) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&self.bits)
+    }
+}
+
+fn row_size(width: u32) -> usize {
+    (width as usize).div_ceil(8)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 3x2 image where only the top right and bottom left pixels are opaque, and the bottom right
+    // one is red but transparent.
+    const PIXELS: [u8; 24] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 9, 9, 9, 255, //
+        9, 9, 9, 255, 0, 0, 0, 0, 255, 0, 0, 0,
+    ];
+
+    #[test]
+    fn uses_the_channel_of_the_pixels() {
+        let mask = CollisionMask::from_rgba(3, 2, &PIXELS, &Channel::A);
+        assert!(!mask.get(0, 0));
+        assert!(mask.get(2, 0));
+        assert!(mask.get(0, 1));
+        assert!(!mask.get(2, 1));
+        assert!(!mask.get(3, 0));
+
+        let mask = CollisionMask::from_rgba(3, 2, &PIXELS, &Channel::R);
+        assert!(mask.get(2, 1));
+    }
+
+    #[test]
+    fn can_read_what_it_writes() {
+        let pixels: Vec<u8> = (0..20 * 3)
+            .flat_map(|i| [0, 0, 0, if i % 3 == 0 { 255 } else { 0 }])
+            .collect();
+        let mask = CollisionMask::from_rgba(20, 3, &pixels, &Channel::A);
+
+        let mut data = Vec::new();
+        mask.write(&mut data).unwrap();
+        assert!(data.starts_with(MAGIC));
+        assert_eq!(data.len(), HEADER_SIZE + 3 * 3);
+        assert_eq!(CollisionMask::read(&data).unwrap(), mask);
+    }
+
+    #[test]
+    fn fails_on_truncated_files() {
+        let mask = CollisionMask::from_rgba(3, 2, &PIXELS, &Channel::A);
+        let mut data = Vec::new();
+        mask.write(&mut data).unwrap();
+
+        assert!(matches!(
+            CollisionMask::read(&data[..data.len() - 1]),
+            Err(CollisionMaskError::InvalidSize { .. })
+        ));
+        assert!(matches!(
+            CollisionMask::read(b"2DC"),
+            Err(CollisionMaskError::InvalidHeader)
+        ));
+    }
+}
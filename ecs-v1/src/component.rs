@@ -1,14 +1,19 @@
+use crate::Diagnostic;
 use std::any::Any;
 use std::{fmt::Debug, path::Path};
 
 #[typetag::serde(tag = "type")]
 pub trait Component: Debug {
+    /// Writes the assets the component needs at runtime into the compiled scene. `source_path` is
+    /// the `scene.json` being compiled, and `destination_path` the folder of the compiled scene.
     fn compile(&self, _source_path: &Path, _destination_path: &Path) -> Result<(), String> {
         Ok(())
     }
 
-    fn check(&self, _level_id: usize, _source_path: &Path) -> Result<(), String> {
-        Ok(())
+    /// Validates the component before it gets compiled. Diagnostics are located relative to the
+    /// component, so pointers should start at its attributes (e.g. `/sprite`).
+    fn check(&self, _level_id: usize, _source_path: &Path) -> Vec<Diagnostic> {
+        Vec::new()
     }
 
     fn as_any(&self) -> &dyn Any;
@@ -1,8 +1,12 @@
+use crate::collision_mask::CollisionMask;
 use crate::collision_type::CollisionType;
 use crate::color::Channel;
-use crate::{Anchor, Component};
+use crate::{Anchor, Component, Diagnostic};
 use core::any::Any;
+use imagesize::size;
 use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::Path;
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 pub struct MaskCollider {
@@ -15,13 +19,64 @@ pub struct MaskCollider {
     pub anchor: Anchor,
 }
 
+impl MaskCollider {
+    /// Path of the collision mask in the assets of the compiled scene, next to the sprite.
+    pub fn mask_path(&self) -> String {
+        let channel = match self.channel {
+            Channel::R => "r",
+            Channel::G => "g",
+            Channel::B => "b",
+            Channel::A => "a",
+        };
+        format!("{}.{}.mask", self.sprite, channel)
+    }
+}
+
 #[typetag::serde]
 impl Component for MaskCollider {
+    fn compile(&self, source_path: &Path, destination_path: &Path) -> Result<(), String> {
+        let sprite_path = assets_path(source_path).join(&self.sprite);
+        let image = image::open(&sprite_path)
+            .map_err(|e| format!("{}: {}", self.sprite, e))?
+            .to_rgba8();
+        let mask = CollisionMask::from_rgba(image.width(), image.height(), &image, &self.channel);
+
+        let mask_path = destination_path.join("assets").join(self.mask_path());
+        if let Some(parent) = mask_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let file = File::create(&mask_path).map_err(|e| e.to_string())?;
+        mask.write(file).map_err(|e| e.to_string())
+    }
+
+    fn check(&self, _level_id: usize, source_path: &Path) -> Vec<Diagnostic> {
+        let sprite_path = assets_path(source_path).join(&self.sprite);
+        let diagnostic = match size(&sprite_path) {
+            Ok(_) => return Vec::new(),
+            Err(_) if !sprite_path.exists() => Diagnostic::error(
+                "asset-not-found",
+                format!("{} won't collide. File not found", self.sprite),
+            ),
+            Err(e) => Diagnostic::error(
+                "invalid-image",
+                format!("{} won't collide. {}", self.sprite, e),
+            ),
+        };
+        vec![diagnostic.at("/sprite")]
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
 }
 
+fn assets_path(source_path: &Path) -> std::path::PathBuf {
+    let mut assets_path = source_path.to_path_buf();
+    assets_path.pop();
+    assets_path.push("assets");
+    assets_path
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
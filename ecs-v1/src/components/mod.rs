@@ -2,6 +2,7 @@ mod box_collider;
 mod circle_collider;
 mod mask_collider;
 mod sprite_renderer;
+mod sprite_sheet_renderer;
 mod transform;
 mod trigger;
 
@@ -10,6 +11,7 @@ pub mod triggers;
 pub use box_collider::BoxCollider;
 pub use circle_collider::CircleCollider;
 pub use mask_collider::MaskCollider;
-pub use sprite_renderer::SpriteRenderer;
+pub use sprite_renderer::{SpriteRenderer, MAX_SIZE_X, MAX_SIZE_Y};
+pub use sprite_sheet_renderer::{SpriteSheetAnimation, SpriteSheetRenderer};
 pub use transform::Transform;
 pub use trigger::Trigger;
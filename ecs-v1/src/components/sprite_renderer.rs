@@ -1,7 +1,7 @@
 use crate::Vec2;
-use crate::{color::RGBA, Anchor, Component};
+use crate::{blend_mode::BlendMode, color::RGBA, Anchor, Component, Diagnostic};
 use core::any::Any;
-use imagesize::size;
+use imagesize::{size, ImageError};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -19,11 +19,15 @@ pub struct SpriteRenderer {
     pub flip: Vec2<bool>,
     #[serde(default)]
     pub anchor: Anchor,
+    /// Left out when it's `None`, so sprite renderers without a blend mode are written as they
+    /// were before it existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blend_mode: Option<BlendMode>,
 }
 
 #[typetag::serde]
 impl Component for SpriteRenderer {
-    fn check(&self, level_id: usize, source_path: &Path) -> Result<(), String> {
+    fn check(&self, level_id: usize, source_path: &Path) -> Vec<Diagnostic> {
         let mut source_path = source_path.to_path_buf();
         source_path.pop();
         source_path.push("assets");
@@ -35,17 +39,21 @@ impl Component for SpriteRenderer {
                 y: v.height,
             },
             Err(e) => {
-                return Err(format!("{} won't be renderer. {}", self.sprite, e));
+                return vec![image_error(&self.sprite, &source_path, e).at("/sprite")];
             }
         };
 
         if level_id == 0 && (image_size.x > MAX_SIZE_X || image_size.y > MAX_SIZE_Y) {
-            Err(format!(
-                "{} won't be rendered. Images in the overworld can't be bigger than {}x{}",
-                self.sprite, MAX_SIZE_X, MAX_SIZE_Y
-            ))
+            vec![Diagnostic::error(
+                "image-too-big",
+                format!(
+                    "{} won't be rendered. Images in the overworld can't be bigger than {}x{}",
+                    self.sprite, MAX_SIZE_X, MAX_SIZE_Y
+                ),
+            )
+            .at("/sprite")]
         } else {
-            Ok(())
+            Vec::new()
         }
     }
 
@@ -54,6 +62,21 @@ impl Component for SpriteRenderer {
     }
 }
 
+/// Diagnostic for an image that can't be read, telling apart missing files from broken ones.
+pub(crate) fn image_error(name: &str, path: &Path, error: ImageError) -> Diagnostic {
+    if path.exists() {
+        Diagnostic::error(
+            "invalid-image",
+            format!("{} won't be rendered. {}", name, error),
+        )
+    } else {
+        Diagnostic::error(
+            "asset-not-found",
+            format!("{} won't be rendered. File not found", name),
+        )
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -64,6 +87,20 @@ mod test {
         can_go_from_json_to_mp::<SpriteRenderer, _>("components/sprite_renderer");
     }
 
+    #[test]
+    fn can_be_serialized_with_blend_mode_from_json() {
+        can_go_from_json_to_mp::<SpriteRenderer, _>("components/sprite_renderer_blend_mode");
+    }
+
+    #[test]
+    fn can_be_deserialized_from_data_written_without_blend_mode() {
+        let mp = load_mp_fixture("components/sprite_renderer").unwrap();
+        let result: SpriteRenderer = rmp_serde::from_slice(&mp).unwrap();
+        assert_eq!(result.sprite, "a_pixel.png");
+        assert_eq!(result.flip, Vec2 { x: true, y: false });
+        assert_eq!(result.blend_mode, None);
+    }
+
     #[test]
     fn supports_optional_values_with_defaults() {
         let json = load_json_fixture("components/sprite_renderer_optional").unwrap();
@@ -80,7 +117,8 @@ mod test {
                 },
                 layer: 0,
                 flip: Vec2 { x: false, y: false },
-                anchor: Anchor::Center
+                anchor: Anchor::Center,
+                blend_mode: None
             }
         )
     }
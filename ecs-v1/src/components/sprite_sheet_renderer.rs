@@ -0,0 +1,207 @@
+use crate::components::sprite_renderer::{image_error, MAX_SIZE_X, MAX_SIZE_Y};
+use crate::Vec2;
+use crate::{color::RGBA, Anchor, Component, Diagnostic, Size};
+use core::any::Any;
+use imagesize::size;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
+pub struct SpriteSheetRenderer {
+    pub spritesheet: String,
+    pub tile_size: Size,
+    pub rows: usize,
+    pub columns: usize,
+    pub animations: BTreeMap<String, SpriteSheetAnimation>,
+    pub default_state: String,
+    #[serde(default)]
+    pub color: RGBA,
+    #[serde(default)]
+    pub layer: i32,
+    #[serde(default)]
+    pub flip: Vec2<bool>,
+    #[serde(default)]
+    pub anchor: Anchor,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SpriteSheetAnimation {
+    pub first: usize,
+    pub last: usize,
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: f32,
+}
+
+#[typetag::serde]
+impl Component for SpriteSheetRenderer {
+    fn check(&self, level_id: usize, source_path: &Path) -> Vec<Diagnostic> {
+        let mut source_path = source_path.to_path_buf();
+        source_path.pop();
+        source_path.push("assets");
+        source_path.push(&self.spritesheet);
+
+        let image_size = match size(&source_path) {
+            Ok(v) => Vec2 {
+                x: v.width,
+                y: v.height,
+            },
+            Err(e) => {
+                return vec![image_error(&self.spritesheet, &source_path, e).at("/spritesheet")];
+            }
+        };
+
+        if self.rows == 0 || self.columns == 0 {
+            return vec![Diagnostic::error(
+                "empty-spritesheet",
+                format!(
+                    "{} won't be rendered. A spritesheet needs at least one row and one column",
+                    self.spritesheet
+                ),
+            )];
+        }
+
+        let mut diagnostics = Vec::new();
+
+        let sheet_size = Vec2 {
+            x: self.tile_size.width as usize * self.columns,
+            y: self.tile_size.height as usize * self.rows,
+        };
+
+        if sheet_size.x > image_size.x || sheet_size.y > image_size.y {
+            diagnostics.push(
+                Diagnostic::error(
+                    "spritesheet-out-of-bounds",
+                    format!(
+                        "{} won't be rendered. {} columns and {} rows of {}x{} don't fit in a {}x{} image",
+                        self.spritesheet,
+                        self.columns,
+                        self.rows,
+                        self.tile_size.width,
+                        self.tile_size.height,
+                        image_size.x,
+                        image_size.y
+                    ),
+                )
+                .at("/tile_size"),
+            );
+        }
+
+        if level_id == 0
+            && (self.tile_size.width as usize > MAX_SIZE_X
+                || self.tile_size.height as usize > MAX_SIZE_Y)
+        {
+            diagnostics.push(
+                Diagnostic::error(
+                    "image-too-big",
+                    format!(
+                        "{} won't be rendered. Tiles in the overworld can't be bigger than {}x{}",
+                        self.spritesheet, MAX_SIZE_X, MAX_SIZE_Y
+                    ),
+                )
+                .at("/tile_size"),
+            );
+        }
+
+        let frames = self.rows * self.columns;
+        for (name, animation) in &self.animations {
+            let pointer = format!("/animations/{}", name.replace('~', "~0").replace('/', "~1"));
+
+            if animation.first > animation.last || animation.last >= frames {
+                diagnostics.push(
+                    Diagnostic::error(
+                        "invalid-frame-range",
+                        format!(
+                            "Animation `{}` in {} has an invalid frame range {}..={}. The spritesheet has {} frames",
+                            name, self.spritesheet, animation.first, animation.last, frames
+                        ),
+                    )
+                    .at(&pointer),
+                );
+            }
+
+            if animation.frame_rate <= 0.0 {
+                diagnostics.push(
+                    Diagnostic::error(
+                        "invalid-frame-rate",
+                        format!(
+                            "Animation `{}` in {} needs a frame rate greater than 0",
+                            name, self.spritesheet
+                        ),
+                    )
+                    .at(format!("{}/frame_rate", pointer)),
+                );
+            }
+        }
+
+        if !self.animations.contains_key(&self.default_state) {
+            diagnostics.push(
+                Diagnostic::error(
+                    "unknown-animation",
+                    format!(
+                        "The default state `{}` of {} is not one of its animations",
+                        self.default_state, self.spritesheet
+                    ),
+                )
+                .at("/default_state"),
+            );
+        }
+
+        diagnostics
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn default_frame_rate() -> f32 {
+    10.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn can_be_serialized_from_json() {
+        can_go_from_json_to_mp::<SpriteSheetRenderer, _>("components/sprite_sheet_renderer");
+    }
+
+    #[test]
+    fn supports_optional_values_with_defaults() {
+        let json = load_json_fixture("components/sprite_sheet_renderer_optional").unwrap();
+        let result: SpriteSheetRenderer = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            result,
+            SpriteSheetRenderer {
+                spritesheet: "water.png".to_string(),
+                tile_size: Size {
+                    width: 16,
+                    height: 16
+                },
+                rows: 1,
+                columns: 4,
+                animations: BTreeMap::from([(
+                    "idle".to_string(),
+                    SpriteSheetAnimation {
+                        first: 0,
+                        last: 3,
+                        frame_rate: 10.0
+                    }
+                )]),
+                default_state: "idle".to_string(),
+                color: RGBA {
+                    r: 1.0,
+                    g: 1.0,
+                    b: 1.0,
+                    a: 1.0
+                },
+                layer: 0,
+                flip: Vec2 { x: false, y: false },
+                anchor: Anchor::Center
+            }
+        )
+    }
+}
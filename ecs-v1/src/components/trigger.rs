@@ -1,7 +1,8 @@
+use crate::activation::Activation;
 use crate::Component;
-use std::io::Error;
 
+/// An action fired when the player activates a `Trigger` collider of the same entity.
 #[typetag::serde(tag = "type")]
 pub trait Trigger: Component {
-    fn on_trigger(&self) -> Result<(), Error>;
+    fn activation(&self) -> &Activation;
 }
@@ -1,14 +1,16 @@
+use crate::activation::Activation;
 use crate::components::Trigger;
 use crate::{Component, Vec2};
 use core::any::Any;
 use serde::{Deserialize, Serialize};
-use std::io::Error;
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct LevelChange {
     pub level: String,
     #[serde(default)]
     pub spawn_point: Vec2<i32>,
+    #[serde(default)]
+    pub activation: Activation,
 }
 
 #[typetag::serde]
@@ -20,8 +22,8 @@ impl Component for LevelChange {
 
 #[typetag::serde]
 impl Trigger for LevelChange {
-    fn on_trigger(&self) -> Result<(), Error> {
-        Ok(())
+    fn activation(&self) -> &Activation {
+        &self.activation
     }
 }
 
@@ -1,3 +1,13 @@
 mod level_change;
+mod open_url;
+mod play_sound;
+mod set_visibility;
+mod show_message;
+mod teleport;
 
 pub use level_change::LevelChange;
+pub use open_url::OpenUrl;
+pub use play_sound::PlaySound;
+pub use set_visibility::SetVisibility;
+pub use show_message::ShowMessage;
+pub use teleport::Teleport;
@@ -0,0 +1,68 @@
+use crate::activation::Activation;
+use crate::components::Trigger;
+use crate::{Component, Diagnostic};
+use core::any::Any;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct OpenUrl {
+    pub url: String,
+    #[serde(default)]
+    pub activation: Activation,
+}
+
+#[typetag::serde]
+impl Component for OpenUrl {
+    fn check(&self, _level_id: usize, _source_path: &Path) -> Vec<Diagnostic> {
+        if self.url.starts_with("https://") || self.url.starts_with("http://") {
+            Vec::new()
+        } else {
+            vec![Diagnostic::error(
+                "unsupported-url",
+                format!(
+                    "{} won't be opened. Only http and https urls are supported",
+                    self.url
+                ),
+            )
+            .at("/url")]
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for OpenUrl {
+    fn activation(&self) -> &Activation {
+        &self.activation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn can_be_serialized_from_json() {
+        can_go_from_json_to_mp::<OpenUrl, _>("components/open_url");
+    }
+
+    #[test]
+    fn only_accepts_http_urls() {
+        let url = OpenUrl {
+            url: "file:///etc/passwd".to_string(),
+            activation: Activation::Interact,
+        };
+        assert!(!url.check(0, Path::new("scene.json")).is_empty());
+
+        let url = OpenUrl {
+            url: "https://decentraland.org".to_string(),
+            activation: Activation::Interact,
+        };
+        assert!(url.check(0, Path::new("scene.json")).is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::activation::Activation;
+use crate::components::Trigger;
+use crate::{Component, Diagnostic};
+use core::any::Any;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PlaySound {
+    pub sound: String,
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+    #[serde(default)]
+    pub activation: Activation,
+}
+
+#[typetag::serde]
+impl Component for PlaySound {
+    fn check(&self, _level_id: usize, source_path: &Path) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        let mut source_path = source_path.to_path_buf();
+        source_path.pop();
+        source_path.push("assets");
+        source_path.push(&self.sound);
+
+        if !source_path.exists() {
+            diagnostics.push(
+                Diagnostic::error(
+                    "asset-not-found",
+                    format!("{} won't be played. File not found", self.sound),
+                )
+                .at("/sound"),
+            );
+        }
+
+        if self.volume < 0.0 {
+            diagnostics.push(
+                Diagnostic::error(
+                    "negative-volume",
+                    format!(
+                        "{} won't be played. The volume can't be negative",
+                        self.sound
+                    ),
+                )
+                .at("/volume"),
+            );
+        }
+
+        diagnostics
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for PlaySound {
+    fn activation(&self) -> &Activation {
+        &self.activation
+    }
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn can_be_serialized_from_json() {
+        can_go_from_json_to_mp::<PlaySound, _>("components/play_sound");
+    }
+
+    #[test]
+    fn supports_optional_values_with_defaults() {
+        let json = load_json_fixture("components/play_sound_optional").unwrap();
+        let result: PlaySound = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            result,
+            PlaySound {
+                sound: "door.ogg".to_string(),
+                volume: 1.0,
+                activation: Activation::Interact
+            }
+        )
+    }
+}
@@ -0,0 +1,51 @@
+use crate::activation::Activation;
+use crate::components::Trigger;
+use crate::{Component, Diagnostic};
+use core::any::Any;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SetVisibility {
+    pub entity: String,
+    pub visible: bool,
+    #[serde(default)]
+    pub activation: Activation,
+}
+
+#[typetag::serde]
+impl Component for SetVisibility {
+    fn check(&self, _level_id: usize, _source_path: &Path) -> Vec<Diagnostic> {
+        if self.entity.is_empty() {
+            vec![Diagnostic::error(
+                "missing-entity-name",
+                "SetVisibility needs the name of the entity to show or hide",
+            )
+            .at("/entity")]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for SetVisibility {
+    fn activation(&self) -> &Activation {
+        &self.activation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn can_be_serialized_from_json() {
+        can_go_from_json_to_mp::<SetVisibility, _>("components/set_visibility");
+    }
+}
@@ -0,0 +1,57 @@
+use crate::activation::Activation;
+use crate::components::Trigger;
+use crate::Component;
+use core::any::Any;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ShowMessage {
+    pub message: String,
+    #[serde(default = "default_duration")]
+    pub duration: f32,
+    #[serde(default)]
+    pub activation: Activation,
+}
+
+#[typetag::serde]
+impl Component for ShowMessage {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for ShowMessage {
+    fn activation(&self) -> &Activation {
+        &self.activation
+    }
+}
+
+fn default_duration() -> f32 {
+    3.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn can_be_serialized_from_json() {
+        can_go_from_json_to_mp::<ShowMessage, _>("components/show_message");
+    }
+
+    #[test]
+    fn supports_optional_values_with_defaults() {
+        let json = load_json_fixture("components/show_message_optional").unwrap();
+        let result: ShowMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            result,
+            ShowMessage {
+                message: "Hello!".to_string(),
+                duration: 3.0,
+                activation: Activation::Interact
+            }
+        )
+    }
+}
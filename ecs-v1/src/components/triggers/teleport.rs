@@ -0,0 +1,37 @@
+use crate::activation::Activation;
+use crate::components::Trigger;
+use crate::{Component, Vec2};
+use core::any::Any;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Teleport {
+    pub parcel: Vec2<i16>,
+    #[serde(default)]
+    pub activation: Activation,
+}
+
+#[typetag::serde]
+impl Component for Teleport {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Teleport {
+    fn activation(&self) -> &Activation {
+        &self.activation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn can_be_serialized_from_json() {
+        can_go_from_json_to_mp::<Teleport, _>("components/teleport");
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Where a diagnostic was found in `scene.json`.
+///
+/// `pointer` is a JSON pointer (RFC 6901). Components report it relative to themselves
+/// (e.g. `/sprite`), and the compiler turns it into an absolute pointer.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Location {
+    pub level: Option<usize>,
+    pub entity: Vec<String>,
+    pub component: Option<usize>,
+    pub pointer: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: String,
+    pub message: String,
+    pub location: Location,
+}
+
+impl Diagnostic {
+    pub fn error<C, M>(code: C, message: M) -> Self
+    where
+        C: Into<String>,
+        M: Into<String>,
+    {
+        Diagnostic::new(Severity::Error, code, message)
+    }
+
+    pub fn warning<C, M>(code: C, message: M) -> Self
+    where
+        C: Into<String>,
+        M: Into<String>,
+    {
+        Diagnostic::new(Severity::Warning, code, message)
+    }
+
+    fn new<C, M>(severity: Severity, code: C, message: M) -> Self
+    where
+        C: Into<String>,
+        M: Into<String>,
+    {
+        Diagnostic {
+            severity,
+            code: code.into(),
+            message: message.into(),
+            location: Location::default(),
+        }
+    }
+
+    /// Points the diagnostic to an attribute, relative to the current location.
+    pub fn at<P>(mut self, pointer: P) -> Self
+    where
+        P: AsRef<str>,
+    {
+        self.location.pointer.push_str(pointer.as_ref());
+        self
+    }
+
+    /// Places a diagnostic reported by a component in the scene.
+    /// `pointer` is the JSON pointer of the component in `scene.json`.
+    pub fn in_component(
+        mut self,
+        level: usize,
+        entity: &[String],
+        component: usize,
+        pointer: &str,
+    ) -> Self {
+        self.location.level = Some(level);
+        self.location.entity = entity.to_vec();
+        self.location.component = Some(component);
+        self.location.pointer = format!("{}{}", pointer, self.location.pointer);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+
+        if let Some(level) = self.location.level {
+            write!(f, "\n  --> level {}", level)?;
+            if !self.location.entity.is_empty() {
+                write!(f, ", entity `{}`", self.location.entity.join(" > "))?;
+            }
+            if let Some(component) = self.location.component {
+                write!(f, ", component {}", component)?;
+            }
+        }
+
+        if !self.location.pointer.is_empty() {
+            write!(f, "\n  --> {}", self.location.pointer)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn component_pointers_are_made_absolute() {
+        let diagnostic = Diagnostic::error("asset-not-found", "tree.png not found")
+            .at("/sprite")
+            .in_component(
+                0,
+                &["Forest".to_string(), "Tree".to_string()],
+                1,
+                "/levels/0/entities/3/children/0/components/1",
+            );
+
+        assert_eq!(
+            diagnostic.location,
+            Location {
+                level: Some(0),
+                entity: vec!["Forest".to_string(), "Tree".to_string()],
+                component: Some(1),
+                pointer: "/levels/0/entities/3/children/0/components/1/sprite".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn serializes_to_json() {
+        let diagnostic = Diagnostic::warning("some-code", "some message").at("/url");
+        let json = serde_json::to_string(&diagnostic).unwrap();
+        assert_eq!(
+            json,
+            r#"{"severity":"warning","code":"some-code","message":"some message","location":{"level":null,"entity":[],"component":null,"pointer":"/url"}}"#
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    UnsupportedVersion(u16),
+    SchemaMismatch {
+        version: u16,
+        schema_hash: u64,
+        error: rmp_serde::decode::Error,
+    },
+    Decode {
+        version: u16,
+        error: rmp_serde::decode::Error,
+    },
+}
+
+#[derive(Debug)]
+pub enum CollisionMaskError {
+    InvalidHeader,
+    InvalidSize { expected: usize, found: usize },
+}
+
+impl error::Error for SceneFileError {}
+
+impl error::Error for CollisionMaskError {}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneFileError::UnsupportedVersion(version) => write!(
+                f,
+                "The scene was compiled with format version {}, but this client only supports up to version {}. Please update 2dcl.",
+                version,
+                crate::scene_file::VERSION
+            ),
+            SceneFileError::SchemaMismatch {
+                version,
+                schema_hash,
+                error,
+            } => write!(
+                f,
+                "The scene was compiled with a different schema ({:016x}) of format version {}: {}",
+                schema_hash, version, error
+            ),
+            SceneFileError::Decode { version, error } => write!(
+                f,
+                "The scene could not be decoded as format version {}: {}",
+                version, error
+            ),
+        }
+    }
+}
+
+impl fmt::Display for CollisionMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CollisionMaskError::InvalidHeader => write!(f, "The file is not a collision mask."),
+            CollisionMaskError::InvalidSize { expected, found } => write!(
+                f,
+                "The collision mask should have {} bytes of pixels, but it has {}.",
+                expected, found
+            ),
+        }
+    }
+}
@@ -1,20 +1,27 @@
 mod anchor;
 mod component;
+mod diagnostic;
 mod entity;
+mod error;
 mod level;
 mod scene;
 mod size;
 mod vec;
 
+pub mod activation;
 pub mod blend_mode;
+pub mod collision_mask;
 pub mod collision_type;
 pub mod color;
 pub mod components;
+pub mod scene_file;
 pub mod test_utils;
 
 pub use anchor::Anchor;
 pub use component::Component;
+pub use diagnostic::{Diagnostic, Location, Severity};
 pub use entity::Entity;
+pub use error::{CollisionMaskError, SceneFileError};
 pub use level::Level;
 pub use scene::Scene;
 pub use size::Size;
@@ -1,6 +1,5 @@
 use crate::Level;
 use dcl_common::{Parcel, Result};
-use rmp_serde::Deserializer;
 use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 
@@ -30,10 +29,9 @@ impl Default for Scene {
 }
 
 impl Scene {
-    pub fn from_mp(data: &Vec<u8>) -> Result<Scene> {
-        let mut de = Deserializer::from_read_ref(data);
-        let deserialized_scene: Scene = Deserialize::deserialize(&mut de)?;
-        Ok(deserialized_scene)
+    /// Reads a scene file, with or without a header. See `scene_file::read`.
+    pub fn from_mp(data: &[u8]) -> Result<Scene> {
+        Ok(crate::scene_file::read(data)?)
     }
 
     pub fn from_json(data: String) -> Result<Scene> {
@@ -66,4 +64,16 @@ mod test {
 
         assert_eq!(scene_from_mp.name, scene_from_json.name)
     }
+
+    #[test]
+    fn deserialize_from_mp_with_header() {
+        let scene_json = load_json_fixture("scene").unwrap();
+        let scene = Scene::from_json(scene_json).unwrap();
+        let mut data = Vec::new();
+        crate::scene_file::write(&scene, &mut data).unwrap();
+
+        let scene_from_mp = Scene::from_mp(&data).unwrap();
+
+        assert_eq!(scene_from_mp.name, scene.name)
+    }
 }
@@ -0,0 +1,592 @@
+//! Container format of `scene.2dcl` files.
+//!
+//! A scene file starts with a header followed by the MessagePack serialization of the `Scene`:
+//!
+//! | bytes   | content                                   |
+//! |---------|-------------------------------------------|
+//! | `0..4`  | `MAGIC`                                   |
+//! | `4..6`  | format version, `u16` little endian       |
+//! | `6..14` | schema hash, `u64` little endian          |
+//! | `14..`  | MessagePack payload                       |
+//!
+//! Files compiled before the header existed are raw MessagePack, and are read as version `0`.
+
+use crate::error::SceneFileError;
+use crate::Scene;
+use rmp_serde::{Deserializer, Serializer};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+pub const MAGIC: &[u8; 4] = b"2DCL";
+
+/// Current format version. Bump it every time `SCHEMA` changes in a way older clients can't read,
+/// and add a migration for the previous version in `migrate`.
+pub const VERSION: u16 = 2;
+
+/// Serialized shape of the scene. Only used to compute `SCHEMA_HASH`, so clients can tell apart
+/// a scene that is broken from a scene that was compiled with a different schema.
+pub const SCHEMA: &str = "\
+Scene{timestamp:SystemTime,name:String,parcels:[Parcel(i16,i16)],base:Parcel(i16,i16),levels:[Level]}
+Level{name:String,dimensions:Vec2<u16>,player_layer:i16,entities:[Entity],spawn_point:Vec2<i16>}
+Entity{name:String,children:[Entity],components:[Component]}
+Transform{location:Vec2<i32>,rotation:Vec3<f32>,scale:Vec2<f32>}
+SpriteRenderer{sprite:String,color:RGBA,layer:i32,flip:Vec2<bool>,anchor:Anchor,blend_mode:Option<BlendMode>}
+SpriteSheetRenderer{spritesheet:String,tile_size:Size,rows:usize,columns:usize,animations:{String:SpriteSheetAnimation},default_state:String,color:RGBA,layer:i32,flip:Vec2<bool>,anchor:Anchor}
+SpriteSheetAnimation{first:usize,last:usize,frame_rate:f32}
+BoxCollider{collision_type:CollisionType,center:Vec2<i32>,size:Size}
+CircleCollider{collision_type:CollisionType,center:Vec2<i32>,radius:u32}
+MaskCollider{sprite:String,collision_type:CollisionType,channel:Channel,anchor:Anchor}
+LevelChange{level:String,spawn_point:Vec2<i32>,activation:Activation}
+Teleport{parcel:Vec2<i16>,activation:Activation}
+SetVisibility{entity:String,visible:bool,activation:Activation}
+PlaySound{sound:String,volume:f32,activation:Activation}
+ShowMessage{message:String,duration:f32,activation:Activation}
+OpenUrl{url:String,activation:Activation}
+Vec2<T>{x:T,y:T}
+Vec3<T>{x:T,y:T,z:T}
+Size{width:u16,height:u16}
+RGBA{r:f32,g:f32,b:f32,a:f32}
+Anchor(Center|BottomLeft|BottomCenter|BottomRight|CenterLeft|CenterRight|TopLeft|TopCenter|TopRight|Custom(Vec2<i32>)|Unbounded(Vec2<i32>))
+BlendMode(Add|AlphaBlend|Multiply|Custom{color:BlendOptions,alpha:BlendOptions})
+BlendOptions{src:BlendFactor,dst:BlendFactor}
+BlendFactor(Zero|One|Src|OneMinusSrc|SrcAlpha|OneMinusSrcAlpha|Dst|OneMinusDst|DstAlpha|OneMinusDstAlpha|SrcAlphaSaturated|Constant|OneMinusConstant)
+CollisionType(Solid|Trigger)
+Channel(R|G|B|A)
+Activation(Interact|Enter)
+";
+
+pub const SCHEMA_HASH: u64 = fnv1a(SCHEMA.as_bytes());
+
+const HEADER_SIZE: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub version: u16,
+    pub schema_hash: u64,
+}
+
+impl Header {
+    pub fn current() -> Self {
+        Header {
+            version: VERSION,
+            schema_hash: SCHEMA_HASH,
+        }
+    }
+}
+
+/// Splits a scene file in its header and payload. Files without a header are version `0`.
+pub fn read_header(data: &[u8]) -> (Header, &[u8]) {
+    if data.len() >= HEADER_SIZE && data.starts_with(MAGIC) {
+        let mut version = [0; 2];
+        version.copy_from_slice(&data[4..6]);
+        let mut schema_hash = [0; 8];
+        schema_hash.copy_from_slice(&data[6..HEADER_SIZE]);
+
+        let header = Header {
+            version: u16::from_le_bytes(version),
+            schema_hash: u64::from_le_bytes(schema_hash),
+        };
+        return (header, &data[HEADER_SIZE..]);
+    }
+
+    let header = Header {
+        version: 0,
+        schema_hash: 0,
+    };
+    (header, data)
+}
+
+/// Reads a scene file of any supported version into the current `Scene` model.
+pub fn read(data: &[u8]) -> Result<Scene, SceneFileError> {
+    let (header, payload) = read_header(data);
+    migrate(&header, payload)
+}
+
+/// Writes `scene` with the current header.
+pub fn write<W>(scene: &Scene, mut writer: W) -> dcl_common::Result<()>
+where
+    W: Write,
+{
+    let header = Header::current();
+    writer.write_all(MAGIC)?;
+    writer.write_all(&header.version.to_le_bytes())?;
+    writer.write_all(&header.schema_hash.to_le_bytes())?;
+    scene.serialize(&mut Serializer::new(&mut writer))?;
+    Ok(())
+}
+
+// When the format changes, move the previous models to their own module, decode them here and
+// convert them into the current `Scene`.
+fn migrate(header: &Header, payload: &[u8]) -> Result<Scene, SceneFileError> {
+    match header.version {
+        // Headerless files use the same model as version 1, which is version 2 without
+        // `Anchor::Unbounded`.
+        0 | 1 => decode(header, payload),
+        VERSION => decode(header, payload),
+        version => Err(SceneFileError::UnsupportedVersion(version)),
+    }
+}
+
+fn decode(header: &Header, payload: &[u8]) -> Result<Scene, SceneFileError> {
+    let mut de = Deserializer::from_read_ref(payload);
+    Scene::deserialize(&mut de).map_err(|error| {
+        if header.version != 0 && header.schema_hash != SCHEMA_HASH {
+            SceneFileError::SchemaMismatch {
+                version: header.version,
+                schema_hash: header.schema_hash,
+                error,
+            }
+        } else {
+            SceneFileError::Decode {
+                version: header.version,
+                error,
+            }
+        }
+    })
+}
+
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::activation::Activation;
+    use crate::blend_mode::{BlendFactor, BlendMode, BlendOptions};
+    use crate::collision_type::CollisionType;
+    use crate::color::{Channel, RGBA};
+    use crate::components::triggers::*;
+    use crate::components::*;
+    use crate::test_utils::*;
+    use crate::{Anchor, Component, Entity, Level, Size, Vec2, Vec3};
+    use dcl_common::Parcel;
+    use serde_json::Value;
+    use std::collections::{BTreeMap, HashMap, HashSet};
+    use std::time::SystemTime;
+
+    fn scene_file() -> Vec<u8> {
+        let json = load_json_fixture("scene").unwrap();
+        let scene = Scene::from_json(json).unwrap();
+        let mut data = Vec::new();
+        write(&scene, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn writes_the_header_before_the_payload() {
+        let data = scene_file();
+        let (header, payload) = read_header(&data);
+
+        assert!(data.starts_with(MAGIC));
+        assert_eq!(header, Header::current());
+        assert_eq!(payload, load_mp_fixture("scene").unwrap());
+    }
+
+    #[test]
+    fn can_read_what_it_writes() {
+        let data = scene_file();
+        let scene = read(&data).unwrap();
+        assert_eq!(scene.name, "main_plaza");
+    }
+
+    #[test]
+    fn reads_headerless_files_as_version_0() {
+        let data = load_mp_fixture("scene").unwrap();
+        let (header, payload) = read_header(&data);
+        assert_eq!(header.version, 0);
+        assert_eq!(payload, data);
+
+        let scene = read(&data).unwrap();
+        assert_eq!(scene.name, "main_plaza");
+    }
+
+    #[test]
+    fn reads_version_1_files() {
+        let mut data = scene_file();
+        data[4..6].copy_from_slice(&1u16.to_le_bytes());
+        data[6..HEADER_SIZE].copy_from_slice(&0xbf4257c6c0669e31u64.to_le_bytes());
+
+        let scene = read(&data).unwrap();
+        assert_eq!(scene.name, "main_plaza");
+    }
+
+    #[test]
+    fn fails_on_newer_versions() {
+        let mut data = scene_file();
+        data[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            read(&data),
+            Err(SceneFileError::UnsupportedVersion(v)) if v == VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn reports_schema_mismatches() {
+        let mut data = scene_file();
+        data[6..HEADER_SIZE].copy_from_slice(&0u64.to_le_bytes());
+        data.truncate(HEADER_SIZE + 4);
+        assert!(matches!(
+            read(&data),
+            Err(SceneFileError::SchemaMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn schema_hash_is_stable() {
+        // If this fails you changed `SCHEMA`: bump `VERSION` and add a migration if needed.
+        assert_eq!(SCHEMA_HASH, 0x1ec78682777ff4ae);
+    }
+
+    #[test]
+    fn schema_describes_the_scene_types() {
+        // If this fails you changed a type of the scene without updating `SCHEMA`. New components
+        // have to be added to `populated_scene` too.
+        let value = serde_json::to_value(populated_scene()).unwrap();
+        let mut checker = SchemaChecker::new();
+        checker.check("Scene", &value, "scene");
+
+        let mut missing: Vec<&String> = checker
+            .definitions
+            .keys()
+            .filter(|name| !checker.visited.contains(*name))
+            .collect();
+        missing.sort();
+        assert!(missing.is_empty(), "not in the test scene: {:?}", missing);
+    }
+
+    /// A scene using every type of `SCHEMA`, with all the optional values set.
+    fn populated_scene() -> Scene {
+        let blend_options = BlendOptions {
+            src: BlendFactor::SrcAlpha,
+            dst: BlendFactor::OneMinusSrcAlpha,
+        };
+        let components: Vec<Box<dyn Component>> = vec![
+            Box::new(Transform {
+                location: Vec2 { x: 1, y: 2 },
+                rotation: Vec3 {
+                    x: 0.,
+                    y: 0.,
+                    z: 1.,
+                },
+                scale: Vec2 { x: 1., y: 1. },
+            }),
+            Box::new(SpriteRenderer {
+                sprite: "sprite.png".to_string(),
+                color: RGBA::default(),
+                layer: 1,
+                flip: Vec2 { x: true, y: false },
+                anchor: Anchor::Custom(Vec2 { x: 1, y: 1 }),
+                blend_mode: Some(BlendMode::Custom {
+                    color: blend_options.clone(),
+                    alpha: blend_options,
+                }),
+            }),
+            Box::new(SpriteSheetRenderer {
+                spritesheet: "spritesheet.png".to_string(),
+                tile_size: Size {
+                    width: 16,
+                    height: 16,
+                },
+                rows: 1,
+                columns: 2,
+                animations: BTreeMap::from([(
+                    "idle".to_string(),
+                    SpriteSheetAnimation {
+                        first: 0,
+                        last: 1,
+                        frame_rate: 4.,
+                    },
+                )]),
+                default_state: "idle".to_string(),
+                color: RGBA::default(),
+                layer: 0,
+                flip: Vec2::default(),
+                anchor: Anchor::Center,
+            }),
+            Box::new(BoxCollider {
+                collision_type: CollisionType::Solid,
+                center: Vec2::default(),
+                size: Size::default(),
+            }),
+            Box::new(CircleCollider {
+                collision_type: CollisionType::Trigger,
+                center: Vec2::default(),
+                radius: 8,
+            }),
+            Box::new(MaskCollider {
+                sprite: "mask.png".to_string(),
+                collision_type: CollisionType::Solid,
+                channel: Channel::A,
+                anchor: Anchor::Unbounded(Vec2 { x: -1, y: 0 }),
+            }),
+            Box::new(LevelChange {
+                level: "interior".to_string(),
+                spawn_point: Vec2::default(),
+                activation: Activation::Enter,
+            }),
+            Box::new(Teleport {
+                parcel: Vec2 { x: 1, y: 1 },
+                activation: Activation::Interact,
+            }),
+            Box::new(SetVisibility {
+                entity: "door".to_string(),
+                visible: true,
+                activation: Activation::Interact,
+            }),
+            Box::new(PlaySound {
+                sound: "bell.mp3".to_string(),
+                volume: 1.,
+                activation: Activation::Interact,
+            }),
+            Box::new(ShowMessage {
+                message: "hello".to_string(),
+                duration: 3.,
+                activation: Activation::Interact,
+            }),
+            Box::new(OpenUrl {
+                url: "https://decentraland.org".to_string(),
+                activation: Activation::Interact,
+            }),
+        ];
+
+        Scene {
+            id: 0,
+            timestamp: SystemTime::UNIX_EPOCH,
+            name: "scene".to_string(),
+            parcels: vec![Parcel(0, 0)],
+            base: Parcel(0, 0),
+            levels: vec![Level {
+                id: 0,
+                name: "main".to_string(),
+                dimensions: Vec2 { x: 1, y: 1 },
+                player_layer: 0,
+                entities: vec![Entity {
+                    id: 0,
+                    name: "entity".to_string(),
+                    children: vec![Entity::new("child".to_string())],
+                    components,
+                }],
+                spawn_point: Vec2::default(),
+            }],
+        }
+    }
+
+    /// A type as described in a line of `SCHEMA`.
+    enum Definition {
+        Struct {
+            params: Vec<String>,
+            fields: Vec<(String, String)>,
+        },
+        /// The variants, with their `(T)` or `{field:T}` payload if they have one.
+        Enum(Vec<(String, Option<String>)>),
+    }
+
+    /// Checks the JSON serialization of a scene against `SCHEMA`, remembering the types it went
+    /// through.
+    struct SchemaChecker {
+        definitions: HashMap<String, Definition>,
+        visited: HashSet<String>,
+    }
+
+    impl SchemaChecker {
+        fn new() -> Self {
+            let definitions = SCHEMA.lines().map(parse_definition).collect();
+            SchemaChecker {
+                definitions,
+                visited: HashSet::new(),
+            }
+        }
+
+        fn check(&mut self, ty: &str, value: &Value, path: &str) {
+            match ty {
+                "String" => assert!(value.is_string(), "{path} isn't a String"),
+                "bool" => assert!(value.is_boolean(), "{path} isn't a bool"),
+                "f32" => assert!(value.is_number(), "{path} isn't a f32"),
+                "i16" | "i32" | "u16" | "u32" | "usize" => check_integer(ty, value, path),
+                "SystemTime" => {
+                    let object = value.as_object().expect(path);
+                    assert!(object.contains_key("secs_since_epoch"), "{path}");
+                    assert!(object.contains_key("nanos_since_epoch"), "{path}");
+                }
+                "Parcel(i16,i16)" => {
+                    let parcel = value.as_str().expect(path);
+                    let (x, y) = parcel.split_once(',').expect(path);
+                    assert!(
+                        x.parse::<i16>().is_ok() && y.parse::<i16>().is_ok(),
+                        "{path}"
+                    );
+                }
+                "Component" => {
+                    // Components are tagged with their type, next to their fields.
+                    let mut component = value.as_object().expect(path).clone();
+                    let name = match component.remove("type") {
+                        Some(Value::String(name)) => name,
+                        _ => panic!("{path} isn't a component"),
+                    };
+                    let path = format!("{path}.{name}");
+                    self.check(&name, &Value::Object(component), &path);
+                }
+                _ if ty.starts_with('[') => {
+                    let item_ty = &ty[1..ty.len() - 1];
+                    let items = value.as_array().expect(path);
+                    for (i, item) in items.iter().enumerate() {
+                        self.check(item_ty, item, &format!("{path}[{i}]"));
+                    }
+                }
+                _ if ty.starts_with('{') => {
+                    let (key_ty, value_ty) = ty[1..ty.len() - 1].split_once(':').unwrap();
+                    assert_eq!(key_ty, "String", "{path} keys aren't strings");
+                    let object = value.as_object().expect(path);
+                    for (key, item) in object {
+                        self.check(value_ty, item, &format!("{path}.{key}"));
+                    }
+                }
+                _ if ty.starts_with("Option<") => {
+                    assert!(!value.is_null(), "{path} is None in the test scene");
+                    self.check(&ty[7..ty.len() - 1], value, path);
+                }
+                _ => self.check_definition(ty, value, path),
+            }
+        }
+
+        fn check_definition(&mut self, ty: &str, value: &Value, path: &str) {
+            let (name, args) = match ty.split_once('<') {
+                Some((name, args)) => (name, split_top_level(&args[..args.len() - 1], ',')),
+                None => (ty, Vec::new()),
+            };
+            self.visited.insert(name.to_string());
+
+            let fields: Vec<(String, String)> = match self.definitions.get(name) {
+                Some(Definition::Struct { params, fields }) => fields
+                    .iter()
+                    .map(|(field, field_ty)| {
+                        let field_ty = match params.iter().position(|param| param == field_ty) {
+                            Some(i) => args[i].clone(),
+                            None => field_ty.clone(),
+                        };
+                        (field.clone(), field_ty)
+                    })
+                    .collect(),
+                Some(Definition::Enum(variants)) => {
+                    let (variant, payload) = match value {
+                        Value::String(variant) => (variant.as_str(), None),
+                        Value::Object(object) if object.len() == 1 => {
+                            let (variant, payload) = object.iter().next().unwrap();
+                            (variant.as_str(), Some(payload))
+                        }
+                        _ => panic!("{path} isn't a {name}"),
+                    };
+                    let payload_ty = match variants.iter().find(|(v, _)| v == variant) {
+                        Some((_, payload_ty)) => payload_ty.clone(),
+                        None => panic!("{path}: {name} has no variant {variant} in SCHEMA"),
+                    };
+                    let path = format!("{path}.{variant}");
+                    match (payload_ty, payload) {
+                        (None, None) => {}
+                        (Some(payload_ty), Some(payload)) if payload_ty.starts_with('(') => {
+                            self.check(&payload_ty[1..payload_ty.len() - 1], payload, &path);
+                        }
+                        (Some(payload_ty), Some(payload)) => {
+                            let fields = parse_fields(&payload_ty[1..payload_ty.len() - 1]);
+                            self.check_fields(&fields, payload, &path);
+                        }
+                        _ => panic!("{path} doesn't match its variant in SCHEMA"),
+                    }
+                    return;
+                }
+                None => panic!("{path}: {name} isn't in SCHEMA"),
+            };
+            self.check_fields(&fields, value, path);
+        }
+
+        fn check_fields(&mut self, fields: &[(String, String)], value: &Value, path: &str) {
+            let object = value.as_object().expect(path);
+            let mut expected: Vec<&str> = fields.iter().map(|(field, _)| field.as_str()).collect();
+            let mut actual: Vec<&str> = object.keys().map(|key| key.as_str()).collect();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "fields of {path} don't match SCHEMA");
+
+            for (field, field_ty) in fields {
+                self.check(field_ty, &object[field], &format!("{path}.{field}"));
+            }
+        }
+    }
+
+    fn check_integer(ty: &str, value: &Value, path: &str) {
+        let (min, max) = match ty {
+            "i16" => (i16::MIN as i64, i16::MAX as i64),
+            "i32" => (i32::MIN as i64, i32::MAX as i64),
+            "u16" => (0, u16::MAX as i64),
+            "u32" => (0, u32::MAX as i64),
+            _ => (0, i64::MAX),
+        };
+        let number = value.as_i64().expect(path);
+        assert!(min <= number && number <= max, "{path} isn't a {ty}");
+    }
+
+    fn parse_definition(line: &str) -> (String, Definition) {
+        let start = line.find(['{', '(']).unwrap();
+        let (name, params) = match line[..start].split_once('<') {
+            Some((name, params)) => (name, split_top_level(&params[..params.len() - 1], ',')),
+            None => (&line[..start], Vec::new()),
+        };
+        let body = &line[start + 1..line.len() - 1];
+
+        let definition = if line[start..].starts_with('{') {
+            Definition::Struct {
+                params,
+                fields: parse_fields(body),
+            }
+        } else {
+            let variants = split_top_level(body, '|')
+                .into_iter()
+                .map(|variant| match variant.find(['{', '(']) {
+                    Some(i) => (variant[..i].to_string(), Some(variant[i..].to_string())),
+                    None => (variant, None),
+                })
+                .collect();
+            Definition::Enum(variants)
+        };
+        (name.to_string(), definition)
+    }
+
+    fn parse_fields(body: &str) -> Vec<(String, String)> {
+        split_top_level(body, ',')
+            .into_iter()
+            .map(|field| {
+                let (name, ty) = field.split_once(':').unwrap();
+                (name.to_string(), ty.to_string())
+            })
+            .collect()
+    }
+
+    /// Splits `s` by `separator`, skipping the ones inside brackets.
+    fn split_top_level(s: &str, separator: char) -> Vec<String> {
+        let mut parts = Vec::new();
+        let mut depth = 0;
+        let mut part = String::new();
+        for c in s.chars() {
+            match c {
+                '<' | '{' | '[' | '(' => depth += 1,
+                '>' | '}' | ']' | ')' => depth -= 1,
+                _ if c == separator && depth == 0 => {
+                    parts.push(std::mem::take(&mut part));
+                    continue;
+                }
+                _ => {}
+            }
+            part.push(c);
+        }
+        parts.push(part);
+        parts
+    }
+}
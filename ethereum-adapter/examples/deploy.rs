@@ -5,7 +5,7 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
-use dcl2d_ecs_v1::Scene;
+use dcl2d_ecs_v1::scene_file;
 use dcl_common::Result;
 use dcl_crypto::{
     account::{EphemeralPayload, PersonalSignature},
@@ -38,7 +38,7 @@ async fn main() -> Result<()> {
     scene_file.push("scene.2dcl");
 
     let scene = std::fs::read(scene_file)?;
-    let scene = Scene::from_mp(&scene)?;
+    let scene = scene_file::read(&scene)?;
 
     let parcels = scene.parcels;
 
@@ -1,11 +1,27 @@
+use dcl2d_ecs_v1::Diagnostic;
 use std::error;
 use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum SceneCompileError {
     //  NoParcels,
     SourceNotDirectory,
     DestinationNotDirectory,
+    InvalidPsdPath(PathBuf),
+    InvalidScene,
+    InvalidTiledMap {
+        path: PathBuf,
+        reason: String,
+    },
+    CheckFailed {
+        diagnostics: Vec<Diagnostic>,
+        deny_warnings: bool,
+    },
+    ComponentCompileFailed {
+        entity: String,
+        error: String,
+    },
 }
 
 impl error::Error for SceneCompileError {}
@@ -18,6 +34,37 @@ impl fmt::Display for SceneCompileError {
             } */
             SceneCompileError::SourceNotDirectory => write!(f, "Source is not a folder."),
             SceneCompileError::DestinationNotDirectory => write!(f, "Destination is not a folder."),
+            SceneCompileError::InvalidPsdPath(path) => {
+                write!(f, "Invalid PSD path: {}", path.display())
+            }
+            SceneCompileError::InvalidScene => {
+                write!(f, "scene.json doesn't have the structure of a scene.")
+            }
+            SceneCompileError::InvalidTiledMap { path, reason } => {
+                write!(f, "Invalid Tiled map {}: {}", path.display(), reason)
+            }
+            SceneCompileError::ComponentCompileFailed { entity, error } => {
+                write!(f, "Could not compile entity `{}`: {}", entity, error)
+            }
+            SceneCompileError::CheckFailed {
+                diagnostics,
+                deny_warnings,
+            } => {
+                for diagnostic in diagnostics {
+                    writeln!(f, "{}", diagnostic)?;
+                }
+                let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+                let warnings = diagnostics.len() - errors;
+                if *deny_warnings {
+                    write!(
+                        f,
+                        "Could not compile the scene: {} errors and {} warnings (warnings are denied).",
+                        errors, warnings
+                    )
+                } else {
+                    write!(f, "Could not compile the scene: {} errors.", errors)
+                }
+            }
         }
     }
 }
@@ -0,0 +1,441 @@
+//! Slices images that are too big for the overworld.
+//!
+//! Every `SpriteRenderer` of the first level with an image bigger than `MAX_SIZE_X` x `MAX_SIZE_Y`
+//! is replaced by child entities, one for each tile of the image that isn't empty. The tiles are
+//! saved in `assets/tiles/<image path>/` of the compiled scene, and each child has an unbounded
+//! anchor that puts the tile where it was in the image, so rotations and z-ordering with the
+//! player don't change. The sliced images are left out of the compiled scene unless something else uses them.
+//!
+//! `MaskCollider`s that use one of those images get sliced the same way, so collisions line up.
+
+use dcl2d_ecs_v1::components::{MaskCollider, SpriteRenderer, Transform, MAX_SIZE_X, MAX_SIZE_Y};
+use dcl2d_ecs_v1::{Anchor, Component, Entity, Scene, Vec2, Vec3};
+use dcl_common::Result;
+use image::{imageops, RgbaImage};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TILES_FOLDER: &str = "tiles";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tile {
+    column: u32,
+    row: u32,
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    sprite: String,
+}
+
+#[derive(Debug)]
+struct SlicedImage {
+    width: u32,
+    height: u32,
+    tiles: Vec<Tile>,
+}
+
+/// Slices the images of the first level of `scene` that are too big, reading them from the assets
+/// of the compiled scene in `destination_path` and saving the tiles next to them. Returns the
+/// sliced images.
+pub fn slice_large_images(scene: &mut Scene, destination_path: &Path) -> Result<Vec<String>> {
+    let level = match scene.levels.first_mut() {
+        Some(v) => v,
+        None => return Ok(Vec::new()),
+    };
+
+    let assets_path = destination_path.join("assets");
+    let mut images = HashMap::new();
+    for entity in &level.entities {
+        find_large_images(entity, &assets_path, &mut images)?;
+    }
+    if images.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    for entity in &mut level.entities {
+        slice_entity(entity, &images);
+    }
+    Ok(images.into_keys().collect())
+}
+
+/// Removes the `sliced` images that `scene` doesn't use anymore from the assets of the compiled
+/// scene in `destination_path`, so they aren't deployed.
+pub fn remove_unused_images(
+    scene: &Scene,
+    destination_path: &Path,
+    sliced: &[String],
+) -> Result<()> {
+    let scene = serde_json::to_value(scene)?;
+    for sprite in sliced {
+        let sprite_path = destination_path.join("assets").join(sprite);
+        if !uses(&scene, sprite) && sprite_path.exists() {
+            fs::remove_file(sprite_path)?;
+        }
+    }
+    Ok(())
+}
+
+fn uses(value: &Value, sprite: &str) -> bool {
+    match value {
+        Value::String(v) => v == sprite,
+        Value::Array(v) => v.iter().any(|value| uses(value, sprite)),
+        Value::Object(v) => v.values().any(|value| uses(value, sprite)),
+        _ => false,
+    }
+}
+
+fn find_large_images(
+    entity: &Entity,
+    assets_path: &Path,
+    images: &mut HashMap<String, SlicedImage>,
+) -> Result<()> {
+    for component in &entity.components {
+        let sprite = match component.as_any().downcast_ref::<SpriteRenderer>() {
+            Some(v) => &v.sprite,
+            None => continue,
+        };
+        if images.contains_key(sprite) {
+            continue;
+        }
+
+        // Missing or broken images are reported by `SpriteRenderer::check`.
+        let (width, height) = match image::image_dimensions(assets_path.join(sprite)) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if width as usize > MAX_SIZE_X || height as usize > MAX_SIZE_Y {
+            images.insert(sprite.clone(), slice_image(sprite, assets_path)?);
+        }
+    }
+
+    for child in &entity.children {
+        find_large_images(child, assets_path, images)?;
+    }
+    Ok(())
+}
+
+fn slice_image(sprite: &str, assets_path: &Path) -> Result<SlicedImage> {
+    let image: RgbaImage = image::open(assets_path.join(sprite))?.to_rgba8();
+    let tiles_path = PathBuf::from(TILES_FOLDER).join(Path::new(sprite).with_extension(""));
+    fs::create_dir_all(assets_path.join(&tiles_path))?;
+
+    let mut tiles = Vec::new();
+    for (row, top) in (0..image.height()).step_by(MAX_SIZE_Y).enumerate() {
+        for (column, left) in (0..image.width()).step_by(MAX_SIZE_X).enumerate() {
+            let width = (MAX_SIZE_X as u32).min(image.width() - left);
+            let height = (MAX_SIZE_Y as u32).min(image.height() - top);
+            let tile = imageops::crop_imm(&image, left, top, width, height).to_image();
+            if tile.pixels().all(|pixel| pixel.0 == [0, 0, 0, 0]) {
+                continue;
+            }
+
+            let file_name = tiles_path.join(format!("{}_{}.png", column, row));
+            tile.save(assets_path.join(&file_name))?;
+            tiles.push(Tile {
+                column: column as u32,
+                row: row as u32,
+                left,
+                top,
+                width,
+                height,
+                sprite: file_name.to_string_lossy().replace('\\', "/"),
+            });
+        }
+    }
+
+    Ok(SlicedImage {
+        width: image.width(),
+        height: image.height(),
+        tiles,
+    })
+}
+
+fn slice_entity(entity: &mut Entity, images: &HashMap<String, SlicedImage>) {
+    let mut tiles: BTreeMap<(u32, u32), Entity> = BTreeMap::new();
+    let mut components: Vec<Box<dyn Component>> = Vec::new();
+    for component in entity.components.drain(..) {
+        if let Some(sprite_renderer) = component.as_any().downcast_ref::<SpriteRenderer>() {
+            if let Some(image) = images.get(&sprite_renderer.sprite) {
+                let anchor = anchor_location(&sprite_renderer.anchor, image);
+                for tile in &image.tiles {
+                    let tile_sprite = SpriteRenderer {
+                        sprite: tile.sprite.clone(),
+                        anchor: tile_anchor(&anchor, image, tile, &sprite_renderer.flip),
+                        ..sprite_renderer.clone()
+                    };
+                    tile_entity(&mut tiles, &entity.name, tile)
+                        .components
+                        .push(Box::new(tile_sprite));
+                }
+                continue;
+            }
+        }
+
+        if let Some(mask_collider) = component.as_any().downcast_ref::<MaskCollider>() {
+            if let Some(image) = images.get(&mask_collider.sprite) {
+                let anchor = anchor_location(&mask_collider.anchor, image);
+                for tile in &image.tiles {
+                    let tile_collider = MaskCollider {
+                        sprite: tile.sprite.clone(),
+                        collision_type: mask_collider.collision_type.clone(),
+                        channel: mask_collider.channel.clone(),
+                        anchor: tile_anchor(&anchor, image, tile, &Vec2::default()),
+                    };
+                    tile_entity(&mut tiles, &entity.name, tile)
+                        .components
+                        .push(Box::new(tile_collider));
+                }
+                continue;
+            }
+        }
+
+        components.push(component);
+    }
+    entity.components = components;
+
+    for child in &mut entity.children {
+        slice_entity(child, images);
+    }
+    entity.children.extend(tiles.into_values());
+}
+
+fn tile_entity<'a>(
+    tiles: &'a mut BTreeMap<(u32, u32), Entity>,
+    name: &str,
+    tile: &Tile,
+) -> &'a mut Entity {
+    tiles.entry((tile.row, tile.column)).or_insert_with(|| {
+        let mut child = Entity::new(format!("{} ({},{})", name, tile.column, tile.row));
+        child.components.push(Box::new(Transform {
+            location: Vec2::default(),
+            rotation: Vec3::default(),
+            scale: Vec2 { x: 1.0, y: 1.0 },
+        }));
+        child
+    })
+}
+
+/// Location of `anchor` in pixels, from the bottom left corner of the image.
+fn anchor_location(anchor: &Anchor, image: &SlicedImage) -> Vec2<i32> {
+    let width = image.width as i32;
+    let height = image.height as i32;
+    let (x, y) = match anchor {
+        Anchor::Center => (width / 2, height / 2),
+        Anchor::BottomLeft => (0, 0),
+        Anchor::BottomCenter => (width / 2, 0),
+        Anchor::BottomRight => (width, 0),
+        Anchor::CenterLeft => (0, height / 2),
+        Anchor::CenterRight => (width, height / 2),
+        Anchor::TopLeft => (0, height),
+        Anchor::TopCenter => (width / 2, height),
+        Anchor::TopRight => (width, height),
+        Anchor::Custom(v) | Anchor::Unbounded(v) => (v.x, v.y),
+    };
+    Vec2 { x, y }
+}
+
+/// Anchor of `tile` that puts it in the same place it has in the image.
+fn tile_anchor(anchor: &Vec2<i32>, image: &SlicedImage, tile: &Tile, flip: &Vec2<bool>) -> Anchor {
+    // Flipped sprites mirror the whole image, so the tile ends up on the other side.
+    let left = if flip.x {
+        image.width - tile.left - tile.width
+    } else {
+        tile.left
+    };
+    let bottom = if flip.y {
+        tile.top
+    } else {
+        image.height - tile.top - tile.height
+    };
+
+    Anchor::Unbounded(Vec2 {
+        x: anchor.x - left as i32,
+        y: anchor.y - bottom as i32,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn project_with_image(width: u32, height: u32) -> TempDir {
+        let project = TempDir::new("image_slicer").unwrap();
+        let assets = project.path().join("assets/backgrounds");
+        fs::create_dir_all(&assets).unwrap();
+
+        let mut image = RgbaImage::from_pixel(width, height, image::Rgba([10, 20, 30, 255]));
+        // Leave the bottom right tile empty.
+        for y in MAX_SIZE_Y as u32..height {
+            for x in MAX_SIZE_X as u32..width {
+                image.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+            }
+        }
+        image.save(assets.join("parcel.png")).unwrap();
+        image::RgbaImage::new(8, 8)
+            .save(project.path().join("assets/small.png"))
+            .unwrap();
+        project
+    }
+
+    fn scene(json: &str) -> Scene {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn component<T: 'static>(entity: &Entity) -> Vec<&T> {
+        entity
+            .components
+            .iter()
+            .filter_map(|component| component.as_any().downcast_ref::<T>())
+            .collect()
+    }
+
+    #[test]
+    fn splits_large_sprites_in_child_entities() {
+        let project = project_with_image(1000, 800);
+        let mut scene = scene(
+            r#"{
+              "name": "Slices",
+              "parcels": ["0,0"],
+              "base": "0,0",
+              "levels": [
+                {
+                  "name": "Overworld",
+                  "entities": [{
+                    "name": "Background",
+                    "components": [
+                      { "type": "Transform", "location": { "x": 10, "y": 20 } },
+                      { "type": "SpriteRenderer", "sprite": "backgrounds/parcel.png", "layer": -2, "anchor": "BottomCenter" },
+                      { "type": "MaskCollider", "sprite": "backgrounds/parcel.png", "anchor": "BottomLeft" },
+                      { "type": "ShowMessage", "message": "Hi!" }
+                    ],
+                    "children": [{
+                      "name": "Sign",
+                      "components": [{ "type": "SpriteRenderer", "sprite": "small.png" }]
+                    }]
+                  }]
+                },
+                {
+                  "name": "Interior",
+                  "entities": [{
+                    "name": "Background",
+                    "components": [{ "type": "SpriteRenderer", "sprite": "backgrounds/parcel.png" }]
+                  }]
+                }
+              ]
+            }"#,
+        );
+
+        slice_large_images(&mut scene, project.path()).unwrap();
+
+        let background = &scene.levels[0].entities[0];
+        assert_eq!(
+            component::<Transform>(background)[0].location,
+            Vec2 { x: 10, y: 20 }
+        );
+        assert!(component::<SpriteRenderer>(background).is_empty());
+        assert!(component::<MaskCollider>(background).is_empty());
+        assert_eq!(background.components.len(), 2);
+
+        let names: Vec<&str> = background
+            .children
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Sign",
+                "Background (0,0)",
+                "Background (1,0)",
+                "Background (0,1)"
+            ]
+        );
+        assert_eq!(
+            component::<SpriteRenderer>(&background.children[0])[0].sprite,
+            "small.png"
+        );
+
+        // The bottom center of the image is 500 pixels from the left, and the first row of
+        // tiles starts 32 pixels above the bottom.
+        let top_right = &background.children[2];
+        assert_eq!(
+            component::<Transform>(top_right)[0].location,
+            Vec2::default()
+        );
+        let sprite = component::<SpriteRenderer>(top_right)[0];
+        assert_eq!(sprite.sprite, "tiles/backgrounds/parcel/1_0.png");
+        assert_eq!(sprite.layer, -2);
+        assert_eq!(sprite.anchor, Anchor::Unbounded(Vec2 { x: -268, y: -32 }));
+        let mask = component::<MaskCollider>(top_right)[0];
+        assert_eq!(mask.sprite, "tiles/backgrounds/parcel/1_0.png");
+        assert_eq!(mask.anchor, Anchor::Unbounded(Vec2 { x: -768, y: -32 }));
+
+        let bottom_left = component::<SpriteRenderer>(&background.children[3])[0];
+        assert_eq!(bottom_left.anchor, Anchor::Unbounded(Vec2 { x: 500, y: 0 }));
+
+        let tile = image::open(
+            project
+                .path()
+                .join("assets/tiles/backgrounds/parcel/1_0.png"),
+        )
+        .unwrap();
+        assert_eq!((tile.width(), tile.height()), (232, 768));
+        assert!(!project
+            .path()
+            .join("assets/tiles/backgrounds/parcel/1_1.png")
+            .exists());
+
+        let interior = &scene.levels[1].entities[0];
+        assert!(interior.children.is_empty());
+        assert_eq!(
+            component::<SpriteRenderer>(interior)[0].sprite,
+            "backgrounds/parcel.png"
+        );
+
+        // The interior still uses the whole image.
+        remove_unused_images(
+            &scene,
+            project.path(),
+            &["backgrounds/parcel.png".to_string()],
+        )
+        .unwrap();
+        assert!(project
+            .path()
+            .join("assets/backgrounds/parcel.png")
+            .exists());
+    }
+
+    #[test]
+    fn flipped_sprites_mirror_the_tiles() {
+        let project = project_with_image(1000, 100);
+        let mut scene = scene(
+            r#"{
+              "name": "Slices",
+              "parcels": ["0,0"],
+              "base": "0,0",
+              "levels": [{
+                "name": "Overworld",
+                "entities": [{
+                  "name": "Background",
+                  "components": [
+                    { "type": "SpriteRenderer", "sprite": "backgrounds/parcel.png", "flip": { "x": true, "y": false }, "anchor": "BottomLeft" }
+                  ]
+                }]
+              }]
+            }"#,
+        );
+
+        slice_large_images(&mut scene, project.path()).unwrap();
+
+        let tiles = &scene.levels[0].entities[0].children;
+        let left = component::<SpriteRenderer>(&tiles[0])[0];
+        assert!(left.flip.x);
+        assert_eq!(left.anchor, Anchor::Unbounded(Vec2 { x: -232, y: 0 }));
+        let right = component::<SpriteRenderer>(&tiles[1])[0];
+        assert_eq!(right.anchor, Anchor::Unbounded(Vec2 { x: 0, y: 0 }));
+    }
+}
@@ -1,77 +1,336 @@
 mod error;
+mod image_slicer;
+mod psd_import;
+mod tiled_import;
 
-use crate::error::SceneCompileError;
-use dcl2d_ecs_v1::Scene;
+pub use crate::error::SceneCompileError;
+use dcl2d_ecs_v1::{scene_file, Diagnostic, Entity, Scene};
 use dcl_common::Result;
 use fs_extra::dir::CopyOptions;
-use rmp_serde::*;
-use serde::Serialize;
+use serde_json::Value;
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{fs::File, io::BufReader};
 
-pub fn compile<T, U>(source_path: T, destination_path: U) -> Result<()>
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Fail the build on warnings too.
+    pub deny_warnings: bool,
+}
+
+/// Compiles the scene in `source_path` into `destination_path`, returning the diagnostics found
+/// while checking it. If there are errors the scene isn't compiled, and the diagnostics are
+/// returned in a `SceneCompileError::CheckFailed`.
+///
+/// `source_path` can also be a `.psd` file, which gets imported into the `scene.json` next to it
+/// before compiling that folder. Levels with a `tiled` attribute are imported from Tiled maps, and
+/// images that are too big for the overworld are sliced in tiles. The images of the imported maps
+/// and the tiles are written to `destination_path`, next to the copied assets, so the project
+/// doesn't change.
+pub fn compile<T, U>(source_path: T, destination_path: U) -> Result<Vec<Diagnostic>>
 where
     T: AsRef<Path>,
     U: AsRef<Path>,
 {
-    let mut assets_source_path = source_path.as_ref().to_path_buf();
-    assets_source_path.push("assets");
+    compile_with_options(source_path, destination_path, &CompileOptions::default())
+}
+
+pub fn compile_with_options<T, U>(
+    source_path: T,
+    destination_path: U,
+    options: &CompileOptions,
+) -> Result<Vec<Diagnostic>>
+where
+    T: AsRef<Path>,
+    U: AsRef<Path>,
+{
+    let mut diagnostics = Vec::new();
     let mut source_path = source_path.as_ref().to_path_buf();
+    if psd_import::is_psd(&source_path) {
+        let project_path = match source_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        diagnostics = psd_import::import_psd(&source_path, &project_path)?;
+        source_path = project_path;
+    }
+
+    let mut assets_source_path = source_path.clone();
+    assets_source_path.push("assets");
 
-    let assets_destination_path = destination_path.as_ref().to_path_buf();
-    let mut destination_path = destination_path.as_ref().to_path_buf();
-    println!("compiling: {:?},{:?}", source_path, destination_path);
+    let destination_path = destination_path.as_ref().to_path_buf();
     if !source_path.exists() || !source_path.is_dir() {
         return Err(Box::new(SceneCompileError::SourceNotDirectory));
     }
 
-    source_path.push("scene.json");
-
-    let file = File::open(source_path.clone())?;
+    let file = File::open(source_path.join("scene.json"))?;
     let reader = BufReader::new(file);
-    let scene: Scene = serde_json::from_reader(reader)?;
+    let mut scene: Value = serde_json::from_reader(reader)?;
 
     /* if scene.parcels.is_empty() {
         return Err(Box::new(SceneCompileError::NoParcels));
     } */
 
-    check_components(&scene, &source_path);
-
-    let mut buf: Vec<u8> = Vec::new();
-    scene.serialize(&mut Serializer::new(&mut buf))?;
-
-    //Todo check componets
-
     if !destination_path.exists() {
         fs::create_dir(&destination_path)?;
     } else if !destination_path.is_dir() {
         return Err(Box::new(SceneCompileError::DestinationNotDirectory));
     }
 
-    destination_path.push("scene.2dcl");
-    let mut file = File::create(&destination_path)?;
+    // The assets are copied first and the imported ones are written next to them, so compiling
+    // doesn't change the project.
+    if assets_source_path.exists() {
+        let mut copy_options = CopyOptions::new();
+        copy_options.overwrite = true;
+        copy_options.copy_inside = true;
+        fs_extra::dir::copy(&assets_source_path, &destination_path, &copy_options)?;
+    }
+
+    diagnostics.append(&mut tiled_import::import_levels(
+        &mut scene,
+        &source_path,
+        &destination_path,
+    )?);
+    let mut scene: Scene = serde_json::from_value(scene)?;
+    let sliced_images = image_slicer::slice_large_images(&mut scene, &destination_path)?;
+
+    // Components read their assets next to this path, from the copy in the destination.
+    let compiled_scene_path = destination_path.join("scene.json");
+    diagnostics.append(&mut check_components(&scene, &compiled_scene_path));
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.is_error() || options.deny_warnings)
+    {
+        return Err(Box::new(SceneCompileError::CheckFailed {
+            diagnostics,
+            deny_warnings: options.deny_warnings,
+        }));
+    }
+
+    let mut buf: Vec<u8> = Vec::new();
+    scene_file::write(&scene, &mut buf)?;
 
+    let mut file = File::create(destination_path.join("scene.2dcl"))?;
     file.write_all(&buf)?;
 
-    let mut options = CopyOptions::new();
-    options.overwrite = true;
-    options.copy_inside = true;
+    compile_components(&scene, &compiled_scene_path, &destination_path)?;
+    image_slicer::remove_unused_images(&scene, &destination_path, &sliced_images)?;
+    Ok(diagnostics)
+}
 
-    fs_extra::dir::copy(assets_source_path, assets_destination_path, &options)?;
-    println!("scene compilation finished successfully");
+/// Runs `Component::compile` on every component of the scene, so they can write the assets they
+/// need at runtime into `destination_path`.
+pub fn compile_components(
+    scene: &Scene,
+    source_path: &Path,
+    destination_path: &Path,
+) -> Result<()> {
+    fn compile_entity(entity: &Entity, source_path: &Path, destination_path: &Path) -> Result<()> {
+        for component in &entity.components {
+            component
+                .compile(source_path, destination_path)
+                .map_err(|error| SceneCompileError::ComponentCompileFailed {
+                    entity: entity.name.clone(),
+                    error,
+                })?;
+        }
+        for child in &entity.children {
+            compile_entity(child, source_path, destination_path)?;
+        }
+        Ok(())
+    }
+
+    for level in &scene.levels {
+        for entity in &level.entities {
+            compile_entity(entity, source_path, destination_path)?;
+        }
+    }
     Ok(())
 }
 
-fn check_components(scene: &Scene, source_path: &Path) {
-    for i in 0..scene.levels.len() {
-        for entity in &scene.levels[i].entities {
-            for component in &entity.components {
-                if let Err(e) = component.check(i, source_path) {
-                    println!("{}", e);
-                }
-            }
+/// Turns a compiled scene back into the pretty-printed contents of a `scene.json`.
+/// The timestamp is left out, so the scene gets a new one when it's compiled again.
+pub fn decompile(scene: &Scene) -> Result<String> {
+    let mut json = serde_json::to_value(scene)?;
+    if let Some(scene) = json.as_object_mut() {
+        scene.remove("timestamp");
+    }
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+/// Runs `Component::check` on every component of the scene, locating the diagnostics in `scene.json`.
+pub fn check_components(scene: &Scene, source_path: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (level_id, level) in scene.levels.iter().enumerate() {
+        for (entity_index, entity) in level.entities.iter().enumerate() {
+            check_entity(
+                level_id,
+                entity,
+                &mut Vec::new(),
+                &format!("/levels/{}/entities/{}", level_id, entity_index),
+                source_path,
+                &mut diagnostics,
+            );
+        }
+    }
+    diagnostics
+}
+
+fn check_entity(
+    level_id: usize,
+    entity: &Entity,
+    entity_path: &mut Vec<String>,
+    pointer: &str,
+    source_path: &Path,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    entity_path.push(entity.name.clone());
+
+    for (component_index, component) in entity.components.iter().enumerate() {
+        let component_pointer = format!("{}/components/{}", pointer, component_index);
+        for diagnostic in component.check(level_id, source_path) {
+            diagnostics.push(diagnostic.in_component(
+                level_id,
+                entity_path,
+                component_index,
+                &component_pointer,
+            ));
         }
     }
+
+    for (child_index, child) in entity.children.iter().enumerate() {
+        check_entity(
+            level_id,
+            child,
+            entity_path,
+            &format!("{}/children/{}", pointer, child_index),
+            source_path,
+            diagnostics,
+        );
+    }
+
+    entity_path.pop();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dcl2d_ecs_v1::collision_mask::CollisionMask;
+    use dcl2d_ecs_v1::Location;
+    use tempdir::TempDir;
+
+    #[test]
+    fn errors_fail_the_build() {
+        let destination = TempDir::new("invalid_components").unwrap();
+        let error = compile("fixtures/invalid_components", destination.path()).unwrap_err();
+        let diagnostics = match *error.downcast::<SceneCompileError>().unwrap() {
+            SceneCompileError::CheckFailed { diagnostics, .. } => diagnostics,
+            error => panic!("unexpected error {}", error),
+        };
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unsupported-url");
+        assert_eq!(
+            diagnostics[0].location,
+            Location {
+                level: Some(0),
+                entity: vec!["Shop".to_string(), "Sign".to_string()],
+                component: Some(0),
+                pointer: "/levels/0/entities/0/children/0/components/0/url".to_string(),
+            }
+        );
+        assert!(!destination.path().join("scene.2dcl").exists());
+    }
+
+    #[test]
+    fn mask_colliders_are_compiled_to_collision_masks() {
+        let source = TempDir::new("masks").unwrap();
+        let destination = TempDir::new("compiled_masks").unwrap();
+        fs::create_dir_all(source.path().join("assets/props")).unwrap();
+        let mut image = image::RgbaImage::new(10, 4);
+        image.put_pixel(9, 3, image::Rgba([255, 0, 0, 255]));
+        image
+            .save(source.path().join("assets/props/rock.png"))
+            .unwrap();
+        fs::write(
+            source.path().join("scene.json"),
+            r#"{
+              "name": "Masks",
+              "parcels": ["0,0"],
+              "base": "0,0",
+              "levels": [{
+                "name": "Overworld",
+                "entities": [{
+                  "name": "Rock",
+                  "components": [{ "type": "MaskCollider", "sprite": "props/rock.png", "channel": "R" }]
+                }]
+              }]
+            }"#,
+        )
+        .unwrap();
+
+        compile(source.path(), destination.path()).unwrap();
+
+        let data = fs::read(destination.path().join("assets/props/rock.png.r.mask")).unwrap();
+        let mask = CollisionMask::read(&data).unwrap();
+        assert_eq!((mask.width(), mask.height()), (10, 4));
+        assert!(mask.get(9, 3));
+        assert!(!mask.get(0, 0));
+    }
+
+    #[test]
+    fn large_images_are_sliced_in_the_compiled_scene() {
+        let source = TempDir::new("large_images").unwrap();
+        let destination = TempDir::new("compiled_large_images").unwrap();
+        fs::create_dir_all(source.path().join("assets")).unwrap();
+        image::RgbaImage::from_pixel(1000, 100, image::Rgba([10, 20, 30, 255]))
+            .save(source.path().join("assets/background.png"))
+            .unwrap();
+        fs::write(
+            source.path().join("scene.json"),
+            r#"{
+              "name": "Large images",
+              "parcels": ["0,0"],
+              "base": "0,0",
+              "levels": [{
+                "name": "Overworld",
+                "entities": [{
+                  "name": "Background",
+                  "components": [{ "type": "SpriteRenderer", "sprite": "background.png" }]
+                }]
+              }]
+            }"#,
+        )
+        .unwrap();
+
+        compile(source.path(), destination.path()).unwrap();
+
+        assert!(destination
+            .path()
+            .join("assets/tiles/background/1_0.png")
+            .exists());
+        assert!(!destination.path().join("assets/background.png").exists());
+        assert!(!source.path().join("assets/tiles").exists());
+        assert!(source.path().join("assets/background.png").exists());
+    }
+
+    #[test]
+    fn decompiled_scenes_compile_to_the_same_scene() {
+        let source = TempDir::new("decompiled").unwrap();
+        let destination = TempDir::new("recompiled").unwrap();
+
+        let json = fs::read_to_string("fixtures/decompile/scene.json").unwrap();
+        let scene = Scene::from_json(json).unwrap();
+        let decompiled = decompile(&scene).unwrap();
+        assert!(!decompiled.contains("timestamp"));
+
+        fs::write(source.path().join("scene.json"), &decompiled).unwrap();
+        fs::create_dir(source.path().join("assets")).unwrap();
+        compile(source.path(), destination.path()).unwrap();
+
+        let compiled = fs::read(destination.path().join("scene.2dcl")).unwrap();
+        let recompiled = scene_file::read(&compiled).unwrap();
+        assert_eq!(decompile(&recompiled).unwrap(), decompiled);
+    }
 }
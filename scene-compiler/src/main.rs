@@ -1,11 +1,24 @@
-use clap::Parser;
-use scene_compiler::compile;
+use clap::{Parser, ValueEnum};
+use dcl2d_ecs_v1::Diagnostic;
+use scene_compiler::{compile_with_options, CompileOptions, SceneCompileError};
 
 #[derive(Parser, Debug)]
 struct Args {
     source_path: std::path::PathBuf,
     #[clap(default_value = "./build")]
     destination_path: std::path::PathBuf,
+    /// Fail the build if there are warnings
+    #[clap(long)]
+    deny_warnings: bool,
+    #[clap(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum MessageFormat {
+    Human,
+    /// One diagnostic per line, as JSON
+    Json,
 }
 
 fn main() {
@@ -14,10 +27,45 @@ fn main() {
     let Args {
         source_path,
         destination_path,
+        deny_warnings,
+        message_format,
     } = args;
-    let result = compile(source_path, destination_path);
+    let options = CompileOptions { deny_warnings };
+    let result = compile_with_options(source_path, destination_path, &options);
+
+    let success = result.is_ok();
+    let diagnostics = match result {
+        Ok(diagnostics) => diagnostics,
+        Err(error) => match error.downcast::<SceneCompileError>() {
+            Ok(error) => match *error {
+                SceneCompileError::CheckFailed { diagnostics, .. } => diagnostics,
+                error => vec![Diagnostic::error("compile-failed", error.to_string())],
+            },
+            Err(error) if error.is::<serde_json::Error>() => {
+                vec![Diagnostic::error("invalid-json", error.to_string())]
+            }
+            Err(error) => vec![Diagnostic::error("compile-failed", error.to_string())],
+        },
+    };
+
+    for diagnostic in &diagnostics {
+        match message_format {
+            MessageFormat::Human => println!("{}", diagnostic),
+            MessageFormat::Json => match serde_json::to_string(diagnostic) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("{}", e),
+            },
+        }
+    }
 
-    if result.is_err() {
-        println!("{}", result.unwrap_err());
+    if success {
+        if message_format == MessageFormat::Human {
+            println!("scene compilation finished successfully");
+        }
+    } else {
+        if message_format == MessageFormat::Human {
+            println!("could not compile the scene");
+        }
+        std::process::exit(1);
     }
 }
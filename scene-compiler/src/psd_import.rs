@@ -0,0 +1,474 @@
+//! Imports layered Photoshop files as scenes.
+//!
+//! Every visible layer is exported to `assets/<psd name>/` as a PNG, and becomes an entity in the
+//! first level with a `Transform` and a `SpriteRenderer`. The center of the canvas is the center
+//! of the scene. Tags in the layer names change how a layer is imported:
+//!  - `[collider]`: adds a solid `MaskCollider` using the alpha of the layer.
+//!  - `[trigger]`: same as `[collider]`, but the collider is a trigger.
+//!  - `[mask]`: the layer is only used as a collider, it isn't rendered.
+//!  - `[layer=N]`: sets the z-order layer, by default layers are numbered from the bottom up.
+//!  - `[player]`: marks where the player is in the stack, it sets the `player_layer` of the level.
+//!
+//! If `scene.json` already exists, entities are matched by name and only the attributes that come
+//! from the PSD get updated, everything else in the scene is kept.
+
+use crate::error::SceneCompileError;
+use dcl2d_ecs_v1::Diagnostic;
+use dcl_common::Result;
+use psd::{Psd, PsdLayer};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Attributes of each component that are owned by the PSD, the rest can be edited by hand.
+const IMPORTED_ATTRIBUTES: [(&str, &[&str]); 3] = [
+    ("Transform", &["location"]),
+    ("SpriteRenderer", &["sprite", "layer", "anchor"]),
+    ("MaskCollider", &["sprite", "collision_type", "anchor"]),
+];
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct LayerTags {
+    collision_type: Option<&'static str>,
+    rendered: bool,
+    layer: Option<i32>,
+    player: bool,
+}
+
+pub fn is_psd(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .map(|extension| extension.eq_ignore_ascii_case("psd"))
+            .unwrap_or(false)
+}
+
+/// Imports `psd_path` into the scene in `project_path`, creating `scene.json` if needed.
+pub fn import_psd<T, U>(psd_path: T, project_path: U) -> Result<Vec<Diagnostic>>
+where
+    T: AsRef<Path>,
+    U: AsRef<Path>,
+{
+    let psd_path = psd_path.as_ref();
+    let project_path = project_path.as_ref();
+    let psd = Psd::from_bytes(&fs::read(psd_path)?)?;
+
+    let psd_name = match psd_path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(v) => v.to_string(),
+        None => {
+            return Err(Box::new(SceneCompileError::InvalidPsdPath(
+                psd_path.to_path_buf(),
+            )))
+        }
+    };
+    let assets_path = project_path.join("assets").join(&psd_name);
+    fs::create_dir_all(&assets_path)?;
+
+    let mut diagnostics = Vec::new();
+    let mut entities = Vec::new();
+    let mut player_layer = None;
+    let mut names = HashSet::new();
+    let mut sprites = HashSet::new();
+
+    // `psd` lists layers from the top down, but z-order layers are numbered from the bottom.
+    // It also reads the "hidden" flag that Photoshop writes as `visible`, so we negate it.
+    let visible_layers = psd.layers().iter().rev().filter(|layer| !layer.visible());
+    for (z, layer) in visible_layers.enumerate() {
+        let (name, tags) = parse_layer_name(layer.name(), &mut diagnostics);
+        let z = z as i32;
+
+        if tags.player {
+            player_layer = Some(tags.layer.unwrap_or(z));
+            continue;
+        }
+
+        let (pixels, width, height, left, bottom) = match crop_layer(&psd, layer) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let name = unique(&name, &mut names, |i| format!("{} ({})", name, i));
+        let file_name = slug(&name);
+        let file_name = unique(&file_name, &mut sprites, |i| format!("{}_{}", file_name, i));
+        let file_name = format!("{}.png", file_name);
+        image::save_buffer(
+            assets_path.join(&file_name),
+            &pixels,
+            width,
+            height,
+            image::ColorType::Rgba8,
+        )?;
+
+        let sprite = format!("{}/{}", psd_name, file_name);
+        let location = json!({
+            "x": left - psd.width() as i32 / 2,
+            "y": psd.height() as i32 / 2 - bottom,
+        });
+
+        let mut components = vec![json!({ "type": "Transform", "location": location })];
+        if tags.rendered {
+            components.push(json!({
+                "type": "SpriteRenderer",
+                "sprite": sprite,
+                "layer": tags.layer.unwrap_or(z),
+                "anchor": "BottomLeft",
+            }));
+        }
+        if let Some(collision_type) = tags.collision_type {
+            components.push(json!({
+                "type": "MaskCollider",
+                "sprite": sprite,
+                "collision_type": collision_type,
+                "anchor": "BottomLeft",
+            }));
+        }
+
+        entities.push(json!({ "name": name, "components": components }));
+    }
+
+    let scene_path = project_path.join("scene.json");
+    let mut scene = if scene_path.exists() {
+        serde_json::from_str(&fs::read_to_string(&scene_path)?)?
+    } else {
+        json!({
+            "name": psd_name,
+            "parcels": ["0,0"],
+            "base": "0,0",
+            "levels": [],
+        })
+    };
+
+    merge(&mut scene, &psd_name, player_layer, entities)?;
+    fs::write(&scene_path, serde_json::to_string_pretty(&scene)?)?;
+
+    Ok(diagnostics)
+}
+
+fn parse_layer_name(layer_name: &str, diagnostics: &mut Vec<Diagnostic>) -> (String, LayerTags) {
+    let mut tags = LayerTags {
+        rendered: true,
+        ..Default::default()
+    };
+    let mut name = String::new();
+    let mut rest = layer_name;
+
+    while let Some(start) = rest.find('[') {
+        let end = match rest[start..].find(']') {
+            Some(end) => start + end,
+            None => break,
+        };
+        name.push_str(&rest[..start]);
+
+        let tag = rest[start + 1..end].trim();
+        match tag.split_once('=') {
+            None if tag == "collider" => tags.collision_type = Some("Solid"),
+            None if tag == "trigger" => tags.collision_type = Some("Trigger"),
+            None if tag == "mask" => {
+                tags.rendered = false;
+                tags.collision_type.get_or_insert("Solid");
+            }
+            None if tag == "player" => tags.player = true,
+            Some(("layer", layer)) if layer.trim().parse::<i32>().is_ok() => {
+                tags.layer = layer.trim().parse().ok();
+            }
+            _ => diagnostics.push(Diagnostic::warning(
+                "unknown-layer-tag",
+                format!("Unknown tag `[{}]` in layer `{}`", tag, layer_name),
+            )),
+        }
+
+        rest = &rest[end + 1..];
+    }
+    name.push_str(rest);
+
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    (name, tags)
+}
+
+/// Cuts the pixels of `layer` that are inside the canvas. Returns `None` if it's empty.
+fn crop_layer(psd: &Psd, layer: &PsdLayer) -> Option<(Vec<u8>, u32, u32, i32, i32)> {
+    let canvas_width = psd.width() as i32;
+    let canvas_height = psd.height() as i32;
+    let left = layer.layer_left().max(0);
+    let top = layer.layer_top().max(0);
+    let right = (layer.layer_left() + layer.width() as i32).min(canvas_width);
+    let bottom = (layer.layer_top() + layer.height() as i32).min(canvas_height);
+
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    // `rgba` returns the layer drawn on the whole canvas.
+    let rgba = layer.rgba();
+    let mut pixels = Vec::with_capacity(((right - left) * (bottom - top) * 4) as usize);
+    for y in top..bottom {
+        let start = ((y * canvas_width + left) * 4) as usize;
+        let end = ((y * canvas_width + right) * 4) as usize;
+        pixels.extend_from_slice(&rgba[start..end]);
+    }
+
+    if pixels.chunks(4).all(|pixel| pixel[3] == 0) {
+        return None;
+    }
+
+    Some((
+        pixels,
+        (right - left) as u32,
+        (bottom - top) as u32,
+        left,
+        bottom,
+    ))
+}
+
+fn merge(
+    scene: &mut Value,
+    level_name: &str,
+    player_layer: Option<i32>,
+    entities: Vec<Value>,
+) -> Result<()> {
+    let levels = match scene
+        .as_object_mut()
+        .map(|scene| scene.entry("levels").or_insert_with(|| json!([])))
+        .and_then(Value::as_array_mut)
+    {
+        Some(v) => v,
+        None => return Err(Box::new(SceneCompileError::InvalidScene)),
+    };
+
+    if levels.is_empty() {
+        levels.push(json!({ "name": level_name, "entities": [] }));
+    }
+
+    let level = match levels[0].as_object_mut() {
+        Some(v) => v,
+        None => return Err(Box::new(SceneCompileError::InvalidScene)),
+    };
+
+    if let Some(player_layer) = player_layer {
+        level.insert("player_layer".to_string(), json!(player_layer));
+    }
+
+    let level_entities = match level
+        .entry("entities")
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+    {
+        Some(v) => v,
+        None => return Err(Box::new(SceneCompileError::InvalidScene)),
+    };
+
+    for entity in entities {
+        let existing = level_entities
+            .iter_mut()
+            .find(|existing| existing.get("name") == entity.get("name"));
+
+        match existing {
+            Some(existing) => merge_entity(existing, entity),
+            None => level_entities.push(entity),
+        }
+    }
+
+    Ok(())
+}
+
+fn merge_entity(existing: &mut Value, entity: Value) {
+    let components = match existing
+        .as_object_mut()
+        .map(|existing| existing.entry("components").or_insert_with(|| json!([])))
+        .and_then(Value::as_array_mut)
+    {
+        Some(v) => v,
+        None => return,
+    };
+
+    for component in entity["components"].as_array().into_iter().flatten() {
+        let kind = component.get("type");
+        match components
+            .iter_mut()
+            .find(|existing| existing.get("type") == kind)
+            .and_then(Value::as_object_mut)
+        {
+            Some(existing) => update_component(existing, component),
+            None => components.push(component.clone()),
+        }
+    }
+}
+
+fn update_component(existing: &mut Map<String, Value>, component: &Value) {
+    let attributes = IMPORTED_ATTRIBUTES
+        .iter()
+        .find(|(kind, _)| component.get("type") == Some(&json!(kind)))
+        .map(|(_, attributes)| *attributes)
+        .unwrap_or_default();
+
+    for attribute in attributes {
+        if let Some(value) = component.get(*attribute) {
+            existing.insert(attribute.to_string(), value.clone());
+        }
+    }
+}
+
+pub(crate) fn unique<F>(value: &str, used: &mut HashSet<String>, rename: F) -> String
+where
+    F: Fn(usize) -> String,
+{
+    let mut result = value.to_string();
+    let mut i = 2;
+    while !used.insert(result.clone()) {
+        result = rename(i);
+        i += 1;
+    }
+    result
+}
+
+pub(crate) fn slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '_' => c,
+            'A'..='Z' => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect();
+
+    if slug.is_empty() {
+        "layer".to_string()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dcl2d_ecs_v1::Scene;
+    use tempdir::TempDir;
+
+    fn copy_psd(project: &TempDir) -> std::path::PathBuf {
+        let psd_path = project.path().join("parcel.psd");
+        fs::copy("fixtures/psd/parcel.psd", &psd_path).unwrap();
+        psd_path
+    }
+
+    fn find_entity<'a>(scene: &'a Value, name: &str) -> &'a Value {
+        scene["levels"][0]["entities"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entity| entity["name"] == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn layer_names_can_have_tags() {
+        let mut diagnostics = Vec::new();
+
+        let (name, tags) = parse_layer_name("Big  Tree [collider] [layer=-3]", &mut diagnostics);
+        assert_eq!(name, "Big Tree");
+        assert_eq!(
+            tags,
+            LayerTags {
+                collision_type: Some("Solid"),
+                rendered: true,
+                layer: Some(-3),
+                player: false,
+            }
+        );
+
+        let (_, tags) = parse_layer_name("Door [mask] [trigger]", &mut diagnostics);
+        assert_eq!(tags.collision_type, Some("Trigger"));
+        assert!(!tags.rendered);
+        assert!(diagnostics.is_empty());
+
+        parse_layer_name("Water [wet]", &mut diagnostics);
+        assert_eq!(diagnostics[0].code, "unknown-layer-tag");
+    }
+
+    #[test]
+    fn imports_visible_layers_as_entities() {
+        let project = TempDir::new("psd_import").unwrap();
+        let psd_path = copy_psd(&project);
+
+        import_psd(&psd_path, project.path()).unwrap();
+
+        let json = fs::read_to_string(project.path().join("scene.json")).unwrap();
+        let scene: Value = serde_json::from_str(&json).unwrap();
+        Scene::from_json(json).unwrap();
+
+        let entities = scene["levels"][0]["entities"].as_array().unwrap();
+        let names: Vec<&str> = entities
+            .iter()
+            .map(|entity| entity["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["Ground", "Tree", "Roof", "Wall"]);
+        assert_eq!(scene["levels"][0]["player_layer"], 1);
+
+        let tree = find_entity(&scene, "Tree");
+        assert_eq!(
+            tree["components"][0]["location"],
+            json!({ "x": -22, "y": -5 })
+        );
+        assert_eq!(tree["components"][1]["sprite"], "parcel/tree.png");
+        assert_eq!(tree["components"][1]["layer"], 2);
+        assert_eq!(tree["components"][2]["collision_type"], "Solid");
+
+        let roof = find_entity(&scene, "Roof");
+        assert_eq!(roof["components"][1]["layer"], 7);
+
+        let wall = find_entity(&scene, "Wall");
+        assert_eq!(wall["components"][1]["type"], "MaskCollider");
+
+        let tree_image = image::open(project.path().join("assets/parcel/tree.png")).unwrap();
+        assert_eq!((tree_image.width(), tree_image.height()), (16, 24));
+    }
+
+    #[test]
+    fn reimporting_keeps_manual_edits() {
+        let project = TempDir::new("psd_reimport").unwrap();
+        let psd_path = copy_psd(&project);
+        fs::write(
+            project.path().join("scene.json"),
+            r#"{
+              "name": "Hand made",
+              "parcels": ["3,4"],
+              "base": "3,4",
+              "levels": [{
+                "name": "Overworld",
+                "entities": [
+                  { "name": "Sign", "components": [] },
+                  {
+                    "name": "Tree",
+                    "components": [
+                      { "type": "Transform", "location": { "x": 0, "y": 0 }, "scale": { "x": 2.0, "y": 2.0 } },
+                      { "type": "SpriteRenderer", "sprite": "old.png", "blend_mode": "Add" },
+                      { "type": "ShowMessage", "message": "Hi!" }
+                    ]
+                  }
+                ]
+              }]
+            }"#,
+        )
+        .unwrap();
+
+        import_psd(&psd_path, project.path()).unwrap();
+
+        let json = fs::read_to_string(project.path().join("scene.json")).unwrap();
+        let scene: Value = serde_json::from_str(&json).unwrap();
+        Scene::from_json(json).unwrap();
+
+        assert_eq!(scene["name"], "Hand made");
+        assert_eq!(scene["levels"][0]["entities"][0]["name"], "Sign");
+
+        let tree = find_entity(&scene, "Tree");
+        assert_eq!(
+            tree["components"],
+            json!([
+                { "type": "Transform", "location": { "x": -22, "y": -5 }, "scale": { "x": 2.0, "y": 2.0 } },
+                { "type": "SpriteRenderer", "sprite": "parcel/tree.png", "blend_mode": "Add", "layer": 2, "anchor": "BottomLeft" },
+                { "type": "ShowMessage", "message": "Hi!" },
+                { "type": "MaskCollider", "sprite": "parcel/tree.png", "collision_type": "Solid", "anchor": "BottomLeft" }
+            ])
+        );
+    }
+}
@@ -0,0 +1,986 @@
+//! Imports maps made with the [Tiled](https://www.mapeditor.org) editor as levels.
+//!
+//! A level in `scene.json` can point to a `.tmj` or `.tmx` map with a `tiled` attribute, and it
+//! gets replaced by the imported level before compiling. Only finite orthogonal maps are supported,
+//! and the center of the map is the center of the level.
+//!  - Visible tile layers are baked into PNGs in `assets/tiled/<map name>/` of the compiled scene,
+//!    split in chunks that fit in `MAX_SIZE_X` x `MAX_SIZE_Y`. Each chunk is an entity with a `SpriteRenderer`.
+//!  - Visible image layers are copied to the same folder, and become an entity too.
+//!  - Rectangles and ellipses of object layers (visible or not) become entities with a
+//!    `BoxCollider` or a `CircleCollider`.
+//!  - A point called `spawn_point` sets the spawn point of the level.
+//!
+//! Custom properties:
+//!  - `player_layer` (map): the `player_layer` of the level.
+//!  - `layer` (tile and image layers): the z-order layer, by default they're numbered from the
+//!    bottom up.
+//!  - `level`, `spawn_x`, `spawn_y` and `activation` (objects): adds a `LevelChange`, and makes the
+//!    collider a trigger.
+//!  - `collision_type` (objects): `Solid` or `Trigger`.
+
+mod tmx;
+
+use crate::error::SceneCompileError;
+use crate::psd_import::{slug, unique};
+use base64::Engine;
+use dcl2d_ecs_v1::collision_type::CollisionType;
+use dcl2d_ecs_v1::color::RGBA;
+use dcl2d_ecs_v1::components::triggers::LevelChange;
+use dcl2d_ecs_v1::components::{
+    BoxCollider, CircleCollider, SpriteRenderer, Transform, MAX_SIZE_X, MAX_SIZE_Y,
+};
+use dcl2d_ecs_v1::{Anchor, Component, Diagnostic, Entity, Level, Size, Vec2, Vec3};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use image::{imageops, RgbaImage};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Attribute of the levels in `scene.json` with the path to the map, relative to the scene.
+pub const TILED_ATTRIBUTE: &str = "tiled";
+
+const FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+const FLIPPED_VERTICALLY: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY: u32 = 0x20000000;
+const GID_MASK: u32 = 0x0fffffff;
+
+struct Tileset {
+    first_gid: u32,
+    image: RgbaImage,
+    tile_width: u32,
+    tile_height: u32,
+    columns: u32,
+    tile_count: u32,
+    margin: u32,
+    spacing: u32,
+}
+
+struct Importer {
+    map_path: PathBuf,
+    map_name: String,
+    assets_path: PathBuf,
+    width: i32,
+    height: i32,
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<Tileset>,
+    level: Level,
+    diagnostics: Vec<Diagnostic>,
+    next_layer: i32,
+    names: HashSet<String>,
+    files: HashSet<String>,
+}
+
+/// Replaces every level of `scene` with a `tiled` attribute with the level imported from the map.
+///
+/// The attributes written in `scene.json` take precedence over the ones from the map, and the
+/// imported entities are added after the ones written by hand, so the diagnostics of those still
+/// point at `scene.json`. The images of the maps are saved in the assets of `destination_path`.
+pub fn import_levels(
+    scene: &mut Value,
+    project_path: &Path,
+    destination_path: &Path,
+) -> dcl_common::Result<Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    let levels = match scene.get_mut("levels").and_then(Value::as_array_mut) {
+        Some(v) => v,
+        None => return Ok(diagnostics),
+    };
+
+    for (level_id, level) in levels.iter_mut().enumerate() {
+        let level = match level.as_object_mut() {
+            Some(v) => v,
+            None => continue,
+        };
+        let map_path = match level.remove(TILED_ATTRIBUTE) {
+            Some(Value::String(v)) => project_path.join(v),
+            Some(_) => return Err(Box::new(SceneCompileError::InvalidScene)),
+            None => continue,
+        };
+
+        let (imported, level_diagnostics) = import_map(&map_path, destination_path)?;
+        for mut diagnostic in level_diagnostics {
+            diagnostic.location.level = Some(level_id);
+            diagnostics.push(diagnostic.at(format!("/levels/{}/{}", level_id, TILED_ATTRIBUTE)));
+        }
+
+        let mut imported = serde_json::to_value(imported)?;
+        let mut entities = match level.remove("entities") {
+            Some(Value::Array(v)) => v,
+            _ => Vec::new(),
+        };
+        if let Some(Value::Array(mut v)) = imported.get_mut("entities").map(Value::take) {
+            entities.append(&mut v);
+        }
+
+        if let Value::Object(imported) = imported {
+            for (key, value) in imported {
+                level.entry(key).or_insert(value);
+            }
+        }
+        level.insert("entities".to_string(), Value::Array(entities));
+    }
+
+    Ok(diagnostics)
+}
+
+/// Imports the map in `map_path` as a level, saving its images in the assets of
+/// `destination_path`.
+pub fn import_map<T, U>(
+    map_path: T,
+    destination_path: U,
+) -> dcl_common::Result<(Level, Vec<Diagnostic>)>
+where
+    T: AsRef<Path>,
+    U: AsRef<Path>,
+{
+    let map_path = map_path.as_ref();
+    let map = load(map_path)?;
+
+    let map_name = match map_path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(v) => v.to_string(),
+        None => return Err(invalid(map_path, "the file name isn't valid")),
+    };
+
+    if map
+        .get("orientation")
+        .and_then(Value::as_str)
+        .unwrap_or("orthogonal")
+        != "orthogonal"
+    {
+        return Err(invalid(map_path, "only orthogonal maps are supported"));
+    }
+    if map
+        .get("infinite")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        return Err(invalid(map_path, "infinite maps aren't supported"));
+    }
+
+    let tile_width = number(&map, "tilewidth").max(1) as u32;
+    let tile_height = number(&map, "tileheight").max(1) as u32;
+    let width = number(&map, "width") as i32 * tile_width as i32;
+    let height = number(&map, "height") as i32 * tile_height as i32;
+
+    let map_folder = map_path.parent().unwrap_or_else(|| Path::new("."));
+    let mut tilesets = Vec::new();
+    for tileset in map["tilesets"].as_array().into_iter().flatten() {
+        tilesets.push(load_tileset(tileset, map_folder, map_path)?);
+    }
+    tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+    let assets_path = destination_path
+        .as_ref()
+        .join("assets")
+        .join("tiled")
+        .join(&map_name);
+    fs::create_dir_all(&assets_path)?;
+
+    let mut importer = Importer {
+        map_path: map_path.to_path_buf(),
+        level: Level {
+            name: map_name.clone(),
+            dimensions: Vec2 {
+                x: width.clamp(0, u16::MAX as i32) as u16,
+                y: height.clamp(0, u16::MAX as i32) as u16,
+            },
+            player_layer: property(&map, "player_layer")
+                .and_then(Value::as_i64)
+                .unwrap_or_default() as i16,
+            ..Default::default()
+        },
+        map_name,
+        assets_path,
+        width,
+        height,
+        tile_width,
+        tile_height,
+        tilesets,
+        diagnostics: Vec::new(),
+        next_layer: 0,
+        names: HashSet::new(),
+        files: HashSet::new(),
+    };
+
+    importer.import_layers(
+        map["layers"].as_array().unwrap_or(&Vec::new()),
+        (0.0, 0.0),
+        1.0,
+        true,
+    )?;
+    Ok((importer.level, importer.diagnostics))
+}
+
+impl Importer {
+    fn import_layers(
+        &mut self,
+        layers: &[Value],
+        offset: (f64, f64),
+        opacity: f64,
+        visible: bool,
+    ) -> dcl_common::Result<()> {
+        for layer in layers {
+            let name = layer["name"].as_str().unwrap_or_default();
+            let visible = visible
+                && layer
+                    .get("visible")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true);
+            let opacity = opacity * layer.get("opacity").and_then(Value::as_f64).unwrap_or(1.0);
+            let offset = (
+                offset.0
+                    + layer
+                        .get("offsetx")
+                        .and_then(Value::as_f64)
+                        .unwrap_or_default(),
+                offset.1
+                    + layer
+                        .get("offsety")
+                        .and_then(Value::as_f64)
+                        .unwrap_or_default(),
+            );
+
+            match layer["type"].as_str().unwrap_or_default() {
+                "tilelayer" if visible => {
+                    let z = self.z_layer(layer);
+                    let canvas = self.bake_tile_layer(layer)?;
+                    self.import_tile_layer(name, &canvas, offset, opacity, z)?;
+                }
+                "imagelayer" if visible => {
+                    let z = self.z_layer(layer);
+                    self.import_image_layer(layer, offset, opacity, z)?;
+                }
+                "tilelayer" | "imagelayer" => {}
+                "objectgroup" => {
+                    for object in layer["objects"].as_array().into_iter().flatten() {
+                        self.import_object(name, object, offset);
+                    }
+                }
+                "group" => self.import_layers(
+                    layer["layers"].as_array().unwrap_or(&Vec::new()),
+                    offset,
+                    opacity,
+                    visible,
+                )?,
+                kind => self.diagnostics.push(Diagnostic::warning(
+                    "unsupported-tiled-layer",
+                    format!(
+                        "Layer `{}` of type `{}` isn't supported, it was ignored",
+                        name, kind
+                    ),
+                )),
+            }
+        }
+        Ok(())
+    }
+
+    fn z_layer(&mut self, layer: &Value) -> i32 {
+        let z = self.next_layer;
+        self.next_layer += 1;
+        property(layer, "layer")
+            .and_then(Value::as_i64)
+            .map(|layer| layer as i32)
+            .unwrap_or(z)
+    }
+
+    fn bake_tile_layer(&self, layer: &Value) -> dcl_common::Result<RgbaImage> {
+        let columns = number(layer, "width") as u32;
+        let rows = number(layer, "height") as u32;
+        let data = tile_data(layer).map_err(|reason| invalid(&self.map_path, reason))?;
+        if data.len() != (columns * rows) as usize {
+            return Err(invalid(
+                &self.map_path,
+                format!(
+                    "layer `{}` doesn't have {} tiles",
+                    layer["name"],
+                    columns * rows
+                ),
+            ));
+        }
+
+        let mut canvas = RgbaImage::new(columns * self.tile_width, rows * self.tile_height);
+        for (index, gid) in data.into_iter().enumerate() {
+            if gid & GID_MASK == 0 {
+                continue;
+            }
+
+            let tileset = match self
+                .tilesets
+                .iter()
+                .rev()
+                .find(|tileset| tileset.first_gid <= gid & GID_MASK)
+            {
+                Some(v) => v,
+                None => {
+                    return Err(invalid(
+                        &self.map_path,
+                        format!("unknown tile {}", gid & GID_MASK),
+                    ))
+                }
+            };
+
+            // Tiles bigger than the grid are aligned to the bottom left corner of their cell.
+            let x = (index as u32 % columns * self.tile_width) as i64;
+            let y = ((index as u32 / columns + 1) * self.tile_height) as i64
+                - tileset.tile_height as i64;
+            draw_tile(&mut canvas, tileset, gid, x, y);
+        }
+
+        Ok(canvas)
+    }
+
+    fn import_tile_layer(
+        &mut self,
+        name: &str,
+        canvas: &RgbaImage,
+        offset: (f64, f64),
+        opacity: f64,
+        z: i32,
+    ) -> dcl_common::Result<()> {
+        // Chunks are a multiple of the tile size when possible, so tiles don't get split.
+        let chunk_width = chunk_size(MAX_SIZE_X as u32, self.tile_width);
+        let chunk_height = chunk_size(MAX_SIZE_Y as u32, self.tile_height);
+        let split = canvas.width() > chunk_width || canvas.height() > chunk_height;
+
+        for (row, top) in (0..canvas.height())
+            .step_by(chunk_height as usize)
+            .enumerate()
+        {
+            for (column, left) in (0..canvas.width())
+                .step_by(chunk_width as usize)
+                .enumerate()
+            {
+                let width = chunk_width.min(canvas.width() - left);
+                let height = chunk_height.min(canvas.height() - top);
+                let chunk = imageops::crop_imm(canvas, left, top, width, height).to_image();
+                if chunk.pixels().all(|pixel| pixel[3] == 0) {
+                    continue;
+                }
+
+                let (entity_name, file_name) = if split {
+                    (
+                        format!("{} ({},{})", name, column, row),
+                        format!("{} {} {}", name, column, row),
+                    )
+                } else {
+                    (name.to_string(), name.to_string())
+                };
+                let sprite = self.sprite_file(&file_name, "png");
+                chunk.save(self.assets_path.join(&sprite))?;
+
+                let location = self.location(offset.0 + left as f64, offset.1 + top as f64);
+                self.add_sprite(&entity_name, sprite, location, opacity, z);
+            }
+        }
+        Ok(())
+    }
+
+    fn import_image_layer(
+        &mut self,
+        layer: &Value,
+        offset: (f64, f64),
+        opacity: f64,
+        z: i32,
+    ) -> dcl_common::Result<()> {
+        let name = layer["name"].as_str().unwrap_or_default();
+        let image = match layer.get("image").and_then(Value::as_str) {
+            Some(v) if !v.is_empty() => v,
+            _ => return Ok(()),
+        };
+
+        let image_path = self
+            .map_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(image);
+        let extension = image_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("png")
+            .to_string();
+        let sprite = self.sprite_file(name, &extension);
+        fs::copy(&image_path, self.assets_path.join(&sprite))?;
+
+        let location = self.location(offset.0, offset.1);
+        self.add_sprite(name, sprite, location, opacity, z);
+        Ok(())
+    }
+
+    fn import_object(&mut self, layer_name: &str, object: &Value, offset: (f64, f64)) {
+        let id = number(object, "id");
+        let name = match object["name"].as_str() {
+            Some(v) if !v.is_empty() => v.to_string(),
+            _ => format!("{} {}", layer_name, id),
+        };
+        let x = offset.0 + object.get("x").and_then(Value::as_f64).unwrap_or_default();
+        let y = offset.1 + object.get("y").and_then(Value::as_f64).unwrap_or_default();
+        let width = object
+            .get("width")
+            .and_then(Value::as_f64)
+            .unwrap_or_default();
+        let height = object
+            .get("height")
+            .and_then(Value::as_f64)
+            .unwrap_or_default();
+        let class = object
+            .get("type")
+            .or_else(|| object.get("class"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+
+        let is_shape = |shape| {
+            object
+                .get(shape)
+                .map(|v| v != &json!(false))
+                .unwrap_or(false)
+        };
+        if is_shape("point") {
+            if name == "spawn_point" || class == "spawn_point" {
+                let location = self.location(x, y);
+                self.level.spawn_point = Vec2 {
+                    x: location.x as i16,
+                    y: location.y as i16,
+                };
+            } else {
+                self.unsupported_object(&name, "points");
+            }
+            return;
+        }
+        if object.get("gid").is_some() {
+            return self.unsupported_object(&name, "tile objects");
+        }
+        if let Some(shape) = ["polygon", "polyline", "text"]
+            .into_iter()
+            .find(|shape| is_shape(shape))
+        {
+            return self.unsupported_object(&name, &format!("{} objects", shape));
+        }
+
+        if object
+            .get("rotation")
+            .and_then(Value::as_f64)
+            .unwrap_or_default()
+            != 0.0
+        {
+            self.diagnostics.push(Diagnostic::warning(
+                "unsupported-tiled-object",
+                format!(
+                    "Object `{}` is rotated, it was imported without rotation",
+                    name
+                ),
+            ));
+        }
+
+        let level_change = property(object, "level")
+            .and_then(Value::as_str)
+            .map(|level| LevelChange {
+                level: level.to_string(),
+                spawn_point: Vec2 {
+                    x: property(object, "spawn_x")
+                        .and_then(Value::as_i64)
+                        .unwrap_or_default() as i32,
+                    y: property(object, "spawn_y")
+                        .and_then(Value::as_i64)
+                        .unwrap_or_default() as i32,
+                },
+                activation: self
+                    .enum_property(object, &name, "activation")
+                    .unwrap_or_default(),
+            });
+        let collision_type = self
+            .enum_property(object, &name, "collision_type")
+            .unwrap_or(match level_change {
+                Some(_) => CollisionType::Trigger,
+                None => CollisionType::Solid,
+            });
+
+        let collider: Box<dyn Component> = if is_shape("ellipse") {
+            Box::new(CircleCollider {
+                collision_type,
+                center: Vec2::default(),
+                radius: (width.min(height) / 2.0).round() as u32,
+            })
+        } else {
+            Box::new(BoxCollider {
+                collision_type,
+                center: Vec2::default(),
+                size: Size {
+                    width: width.round() as u16,
+                    height: height.round() as u16,
+                },
+            })
+        };
+
+        let mut entity = Entity::new(self.entity_name(&name));
+        entity
+            .components
+            .push(transform(self.location(x + width / 2.0, y + height / 2.0)));
+        entity.components.push(collider);
+        if let Some(level_change) = level_change {
+            entity.components.push(Box::new(level_change));
+        }
+        self.level.entities.push(entity);
+    }
+
+    fn unsupported_object(&mut self, name: &str, kind: &str) {
+        self.diagnostics.push(Diagnostic::warning(
+            "unsupported-tiled-object",
+            format!("Object `{}` was ignored, {} aren't supported", name, kind),
+        ));
+    }
+
+    fn enum_property<T>(&mut self, object: &Value, name: &str, property_name: &str) -> Option<T>
+    where
+        T: DeserializeOwned,
+    {
+        let value = property(object, property_name)?;
+        match serde_json::from_value(value.clone()) {
+            Ok(v) => Some(v),
+            Err(_) => {
+                self.diagnostics.push(Diagnostic::warning(
+                    "invalid-tiled-property",
+                    format!(
+                        "Invalid `{}` property in object `{}`: {}",
+                        property_name, name, value
+                    ),
+                ));
+                None
+            }
+        }
+    }
+
+    fn add_sprite(
+        &mut self,
+        name: &str,
+        sprite: String,
+        location: Vec2<i32>,
+        opacity: f64,
+        z: i32,
+    ) {
+        let mut entity = Entity::new(self.entity_name(name));
+        entity.components.push(transform(location));
+        entity.components.push(Box::new(SpriteRenderer {
+            sprite: format!("tiled/{}/{}", self.map_name, sprite),
+            color: RGBA {
+                a: opacity as f32,
+                ..Default::default()
+            },
+            layer: z,
+            anchor: Anchor::TopLeft,
+            ..Default::default()
+        }));
+        self.level.entities.push(entity);
+    }
+
+    fn entity_name(&mut self, name: &str) -> String {
+        unique(name, &mut self.names, |i| format!("{} ({})", name, i))
+    }
+
+    fn sprite_file(&mut self, name: &str, extension: &str) -> String {
+        let file_name = slug(name);
+        let file_name = unique(&file_name, &mut self.files, |i| {
+            format!("{}_{}", file_name, i)
+        });
+        format!("{}.{}", file_name, extension)
+    }
+
+    /// Turns map coordinates (from the top left corner, `y` down) into level coordinates.
+    fn location(&self, x: f64, y: f64) -> Vec2<i32> {
+        Vec2 {
+            x: x.round() as i32 - self.width / 2,
+            y: self.height / 2 - y.round() as i32,
+        }
+    }
+}
+
+fn transform(location: Vec2<i32>) -> Box<dyn Component> {
+    Box::new(Transform {
+        location,
+        rotation: Vec3::default(),
+        scale: Vec2 { x: 1.0, y: 1.0 },
+    })
+}
+
+fn chunk_size(max_size: u32, tile_size: u32) -> u32 {
+    if tile_size > max_size {
+        max_size
+    } else {
+        max_size / tile_size * tile_size
+    }
+}
+
+fn draw_tile(canvas: &mut RgbaImage, tileset: &Tileset, gid: u32, x: i64, y: i64) {
+    let id = (gid & GID_MASK) - tileset.first_gid;
+    if id >= tileset.tile_count {
+        return;
+    }
+
+    let width = tileset.tile_width;
+    let height = tileset.tile_height;
+    let source_x = tileset.margin + id % tileset.columns * (width + tileset.spacing);
+    let source_y = tileset.margin + id / tileset.columns * (height + tileset.spacing);
+    // Diagonal flips only make sense for square tiles.
+    let diagonal = gid & FLIPPED_DIAGONALLY != 0 && width == height;
+
+    for tile_y in 0..height {
+        for tile_x in 0..width {
+            let (canvas_x, canvas_y) = (x + tile_x as i64, y + tile_y as i64);
+            if canvas_x < 0
+                || canvas_y < 0
+                || canvas_x >= canvas.width() as i64
+                || canvas_y >= canvas.height() as i64
+            {
+                continue;
+            }
+
+            // Tiled flips diagonally first, and then horizontally and vertically, so we undo
+            // those in the opposite order to find the pixel in the tileset.
+            let mut from = (tile_x, tile_y);
+            if gid & FLIPPED_HORIZONTALLY != 0 {
+                from.0 = width - 1 - from.0;
+            }
+            if gid & FLIPPED_VERTICALLY != 0 {
+                from.1 = height - 1 - from.1;
+            }
+            if diagonal {
+                from = (from.1, from.0);
+            }
+
+            let (from_x, from_y) = (source_x + from.0, source_y + from.1);
+            if from_x >= tileset.image.width() || from_y >= tileset.image.height() {
+                continue;
+            }
+
+            let pixel = *tileset.image.get_pixel(from_x, from_y);
+            if pixel[3] != 0 {
+                canvas.put_pixel(canvas_x as u32, canvas_y as u32, pixel);
+            }
+        }
+    }
+}
+
+fn tile_data(layer: &Value) -> Result<Vec<u32>, String> {
+    let data = match &layer["data"] {
+        Value::Array(data) => {
+            return Ok(data
+                .iter()
+                .map(|gid| gid.as_u64().unwrap_or_default() as u32)
+                .collect())
+        }
+        Value::String(data) => data,
+        _ => return Err("tile layers have to be finite".to_string()),
+    };
+
+    match layer["encoding"].as_str() {
+        Some("base64") => {}
+        encoding => return Err(format!("unsupported tile layer encoding {:?}", encoding)),
+    }
+
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|error| error.to_string())?;
+
+    let mut bytes = Vec::new();
+    match layer["compression"].as_str().unwrap_or_default() {
+        "" => bytes = data,
+        "zlib" => {
+            ZlibDecoder::new(&data[..])
+                .read_to_end(&mut bytes)
+                .map_err(|error| error.to_string())?;
+        }
+        "gzip" => {
+            GzDecoder::new(&data[..])
+                .read_to_end(&mut bytes)
+                .map_err(|error| error.to_string())?;
+        }
+        compression => {
+            return Err(format!(
+                "unsupported tile layer compression `{}`",
+                compression
+            ))
+        }
+    }
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+        .collect())
+}
+
+fn load(path: &Path) -> dcl_common::Result<Value> {
+    let contents = fs::read_to_string(path)?;
+    let is_xml = path
+        .extension()
+        .map(|extension| {
+            extension.eq_ignore_ascii_case("tmx") || extension.eq_ignore_ascii_case("tsx")
+        })
+        .unwrap_or(false);
+
+    if is_xml {
+        tmx::to_json(&contents).map_err(|reason| invalid(path, reason))
+    } else {
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+fn load_tileset(
+    tileset: &Value,
+    map_folder: &Path,
+    map_path: &Path,
+) -> dcl_common::Result<Tileset> {
+    let first_gid = number(tileset, "firstgid") as u32;
+    let (tileset, folder) = match tileset.get("source").and_then(Value::as_str) {
+        Some(source) => {
+            let path = map_folder.join(source);
+            let folder = path.parent().unwrap_or(map_folder).to_path_buf();
+            (load(&path)?, folder)
+        }
+        None => (tileset.clone(), map_folder.to_path_buf()),
+    };
+
+    let image = match tileset.get("image").and_then(Value::as_str) {
+        Some(v) => v,
+        None => {
+            return Err(invalid(
+                map_path,
+                format!(
+                    "tileset `{}` is a collection of images, which isn't supported",
+                    tileset["name"].as_str().unwrap_or_default()
+                ),
+            ))
+        }
+    };
+    let image = image::open(folder.join(image))?.to_rgba8();
+
+    let tile_width = number(&tileset, "tilewidth").max(1) as u32;
+    let tile_height = number(&tileset, "tileheight").max(1) as u32;
+    let margin = number(&tileset, "margin") as u32;
+    let spacing = number(&tileset, "spacing") as u32;
+    let columns = match number(&tileset, "columns") as u32 {
+        0 => ((image.width() + spacing).saturating_sub(2 * margin) / (tile_width + spacing)).max(1),
+        columns => columns,
+    };
+    let tile_count = match number(&tileset, "tilecount") as u32 {
+        0 => {
+            columns
+                * ((image.height() + spacing).saturating_sub(2 * margin) / (tile_height + spacing))
+        }
+        tile_count => tile_count,
+    };
+
+    Ok(Tileset {
+        first_gid,
+        image,
+        tile_width,
+        tile_height,
+        columns,
+        tile_count,
+        margin,
+        spacing,
+    })
+}
+
+/// Finds a custom property by name.
+fn property<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value
+        .get("properties")?
+        .as_array()?
+        .iter()
+        .find(|property| property["name"] == name)
+        .map(|property| &property["value"])
+}
+
+fn number(value: &Value, name: &str) -> i64 {
+    value.get(name).and_then(Value::as_i64).unwrap_or_default()
+}
+
+fn invalid<R>(path: &Path, reason: R) -> Box<dyn std::error::Error>
+where
+    R: Into<String>,
+{
+    Box::new(SceneCompileError::InvalidTiledMap {
+        path: path.to_path_buf(),
+        reason: reason.into(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dcl2d_ecs_v1::Scene;
+    use tempdir::TempDir;
+
+    const MAP_WIDTH: i32 = 960;
+
+    fn find_entity<'a>(level: &'a Level, name: &str) -> &'a Entity {
+        level
+            .entities
+            .iter()
+            .find(|entity| entity.name == name)
+            .unwrap()
+    }
+
+    fn component<T: 'static>(entity: &Entity) -> &T {
+        entity
+            .components
+            .iter()
+            .find_map(|component| component.as_any().downcast_ref::<T>())
+            .unwrap()
+    }
+
+    #[test]
+    fn imports_tiled_maps_as_levels() {
+        let destination = TempDir::new("tiled_import").unwrap();
+        let (level, diagnostics) =
+            import_map("fixtures/tiled/maps/shop.tmj", destination.path()).unwrap();
+
+        assert_eq!(level.name, "shop");
+        assert_eq!(level.dimensions, Vec2 { x: 960, y: 64 });
+        assert_eq!(level.player_layer, 3);
+        assert_eq!(level.spawn_point, Vec2 { x: 0, y: -16 });
+
+        let names: Vec<&str> = level.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Floor (0,0)",
+                "Floor (1,0)",
+                "Walls (0,0)",
+                "Walls (1,0)",
+                "Counter",
+                "Barrel",
+                "Door"
+            ]
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unsupported-tiled-object");
+
+        let floor = find_entity(&level, "Floor (1,0)");
+        assert_eq!(
+            component::<Transform>(floor).location,
+            Vec2 { x: 288, y: 32 }
+        );
+        let sprite = component::<SpriteRenderer>(floor);
+        assert_eq!(sprite.sprite, "tiled/shop/floor_1_0.png");
+        assert_eq!(sprite.layer, 0);
+        assert_eq!(sprite.anchor, Anchor::TopLeft);
+
+        let walls = find_entity(&level, "Walls (0,0)");
+        assert_eq!(
+            component::<Transform>(walls).location,
+            Vec2 {
+                x: -MAP_WIDTH / 2,
+                y: 32
+            }
+        );
+        assert_eq!(component::<SpriteRenderer>(walls).layer, 5);
+
+        let counter = find_entity(&level, "Counter");
+        assert_eq!(
+            component::<Transform>(counter).location,
+            Vec2 { x: -416, y: 8 }
+        );
+        let collider = component::<BoxCollider>(counter);
+        assert_eq!(collider.collision_type, CollisionType::Solid);
+        assert_eq!(
+            collider.size,
+            Size {
+                width: 64,
+                height: 16
+            }
+        );
+
+        let barrel = find_entity(&level, "Barrel");
+        assert_eq!(
+            component::<Transform>(barrel).location,
+            Vec2 { x: -270, y: 2 }
+        );
+        assert_eq!(component::<CircleCollider>(barrel).radius, 10);
+
+        let door = find_entity(&level, "Door");
+        assert_eq!(
+            component::<Transform>(door).location,
+            Vec2 { x: 464, y: -16 }
+        );
+        assert_eq!(
+            component::<BoxCollider>(door).collision_type,
+            CollisionType::Trigger
+        );
+        let level_change = component::<LevelChange>(door);
+        assert_eq!(level_change.level, "Overworld");
+        assert_eq!(level_change.spawn_point, Vec2 { x: 0, y: -40 });
+    }
+
+    #[test]
+    fn tile_layers_are_split_in_chunks() {
+        let destination = TempDir::new("tiled_chunks").unwrap();
+        import_map("fixtures/tiled/maps/shop.tmj", destination.path()).unwrap();
+
+        let assets = destination.path().join("assets/tiled/shop");
+        let first = image::open(assets.join("floor_0_0.png")).unwrap();
+        assert_eq!((first.width(), first.height()), (MAX_SIZE_X as u32, 64));
+        let second = image::open(assets.join("floor_1_0.png")).unwrap();
+        assert_eq!((second.width(), second.height()), (192, 64));
+
+        // The last wall tile is flipped horizontally, so its blue half is on the left.
+        let walls = image::open(assets.join("walls_1_0.png"))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(walls.get_pixel(176, 16).0, [0, 0, 255, 255]);
+        assert_eq!(walls.get_pixel(191, 16).0, [0, 255, 0, 255]);
+        assert_eq!(walls.get_pixel(176, 32).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn tmx_maps_are_imported_like_tmj_maps() {
+        let tmj_destination = TempDir::new("tiled_tmj").unwrap();
+        let tmx_destination = TempDir::new("tiled_tmx").unwrap();
+        let (tmj, _) = import_map("fixtures/tiled/maps/shop.tmj", tmj_destination.path()).unwrap();
+        let (tmx, _) = import_map("fixtures/tiled/maps/shop.tmx", tmx_destination.path()).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&tmx).unwrap(),
+            serde_json::to_value(&tmj).unwrap()
+        );
+        for chunk in ["floor_0_0.png", "walls_1_0.png"] {
+            let tmj_chunk =
+                image::open(tmj_destination.path().join("assets/tiled/shop").join(chunk)).unwrap();
+            let tmx_chunk =
+                image::open(tmx_destination.path().join("assets/tiled/shop").join(chunk)).unwrap();
+            assert_eq!(tmj_chunk.as_bytes(), tmx_chunk.as_bytes());
+        }
+    }
+
+    #[test]
+    fn levels_pointing_to_maps_are_compiled() {
+        let project = TempDir::new("tiled_project").unwrap();
+        let destination = TempDir::new("tiled_build").unwrap();
+        let mut options = fs_extra::dir::CopyOptions::new();
+        options.content_only = true;
+        fs_extra::dir::copy("fixtures/tiled", project.path(), &options).unwrap();
+
+        let diagnostics = crate::compile(project.path(), destination.path()).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].location.level, Some(1));
+        assert_eq!(diagnostics[0].location.pointer, "/levels/1/tiled");
+
+        let compiled = fs::read(destination.path().join("scene.2dcl")).unwrap();
+        let scene: Scene = dcl2d_ecs_v1::scene_file::read(&compiled).unwrap();
+        let shop = &scene.levels[1];
+        assert_eq!(shop.name, "Shop");
+        assert_eq!(shop.dimensions, Vec2 { x: 960, y: 64 });
+        assert_eq!(shop.entities.len(), 8);
+        assert_eq!(shop.entities[0].name, "Shopkeeper");
+        assert_eq!(shop.entities[1].name, "Floor (0,0)");
+        assert!(destination
+            .path()
+            .join("assets/tiled/shop/walls_0_0.png")
+            .exists());
+        assert!(!project.path().join("assets").exists());
+    }
+}
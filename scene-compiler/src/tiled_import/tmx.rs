@@ -0,0 +1,326 @@
+//! Converts `.tmx` maps and `.tsx` tilesets into the same structure Tiled uses for `.tmj` maps
+//! and `.tsj` tilesets, so the importer only has to deal with one format.
+
+use serde_json::{json, Map, Value};
+use xml::reader::{EventReader, XmlEvent};
+
+/// Attributes that are numbers in the JSON format, everything else is kept as a string.
+const NUMBER_ATTRIBUTES: [&str; 17] = [
+    "id",
+    "width",
+    "height",
+    "tilewidth",
+    "tileheight",
+    "firstgid",
+    "tilecount",
+    "columns",
+    "margin",
+    "spacing",
+    "x",
+    "y",
+    "rotation",
+    "opacity",
+    "offsetx",
+    "offsety",
+    "gid",
+];
+
+/// Attributes that are booleans in the JSON format, written as `0` or `1` in XML.
+const BOOL_ATTRIBUTES: [&str; 2] = ["visible", "infinite"];
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// Reads a `<map>` or a `<tileset>` document.
+pub fn to_json(xml: &str) -> Result<Value, String> {
+    let root = parse(xml)?;
+    match root.name.as_str() {
+        "map" => Ok(map(&root)),
+        "tileset" => Ok(tileset(&root)),
+        name => Err(format!("unexpected root element <{}>", name)),
+    }
+}
+
+fn parse(xml: &str) -> Result<Element, String> {
+    let mut stack = vec![Element::default()];
+    for event in EventReader::from_str(xml) {
+        match event.map_err(|error| error.to_string())? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => stack.push(Element {
+                name: name.local_name,
+                attributes: attributes
+                    .into_iter()
+                    .map(|attribute| (attribute.name.local_name, attribute.value))
+                    .collect(),
+                ..Default::default()
+            }),
+            XmlEvent::EndElement { .. } => {
+                let element = stack.pop().unwrap_or_default();
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    stack
+        .pop()
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or_else(|| "empty document".to_string())
+}
+
+fn attributes(element: &Element) -> Map<String, Value> {
+    let mut result = Map::new();
+    for (name, value) in &element.attributes {
+        let value = if NUMBER_ATTRIBUTES.contains(&name.as_str()) {
+            value
+                .parse::<i64>()
+                .map(Value::from)
+                .or_else(|_| value.parse::<f64>().map(Value::from))
+                .unwrap_or_else(|_| json!(value))
+        } else if BOOL_ATTRIBUTES.contains(&name.as_str()) {
+            json!(value != "0")
+        } else {
+            json!(value)
+        };
+        result.insert(name.clone(), value);
+    }
+
+    if let Some(properties) = element.child("properties") {
+        result.insert("properties".to_string(), self::properties(properties));
+    }
+    result
+}
+
+fn properties(element: &Element) -> Value {
+    let properties = element
+        .children
+        .iter()
+        .filter(|child| child.name == "property")
+        .map(|property| {
+            let kind = property.attribute("type").unwrap_or("string");
+            let value = property.attribute("value").unwrap_or(&property.text);
+            let value = match kind {
+                "int" | "object" => value.parse::<i64>().map(Value::from).ok(),
+                "float" => value.parse::<f64>().map(Value::from).ok(),
+                "bool" => Some(json!(value == "true")),
+                _ => None,
+            }
+            .unwrap_or_else(|| json!(value));
+
+            json!({
+                "name": property.attribute("name").unwrap_or_default(),
+                "type": kind,
+                "value": value,
+            })
+        })
+        .collect();
+    Value::Array(properties)
+}
+
+fn map(element: &Element) -> Value {
+    let mut map = attributes(element);
+    let tilesets = element
+        .children
+        .iter()
+        .filter(|child| child.name == "tileset")
+        .map(tileset)
+        .collect();
+    map.insert("tilesets".to_string(), Value::Array(tilesets));
+    map.insert("layers".to_string(), layers(element));
+    Value::Object(map)
+}
+
+fn tileset(element: &Element) -> Value {
+    let mut tileset = attributes(element);
+    if let Some(image) = element.child("image") {
+        let image = attributes(image);
+        for (from, to) in [
+            ("source", "image"),
+            ("width", "imagewidth"),
+            ("height", "imageheight"),
+        ] {
+            if let Some(value) = image.get(from) {
+                tileset.insert(to.to_string(), value.clone());
+            }
+        }
+    }
+    Value::Object(tileset)
+}
+
+fn layers(element: &Element) -> Value {
+    let layers = element
+        .children
+        .iter()
+        .filter_map(|child| {
+            let mut layer = attributes(child);
+            let kind = match child.name.as_str() {
+                "layer" => {
+                    if let Some(data) = child.child("data") {
+                        tile_data(data, &mut layer);
+                    }
+                    "tilelayer"
+                }
+                "objectgroup" => {
+                    let objects = child
+                        .children
+                        .iter()
+                        .filter(|object| object.name == "object")
+                        .map(object)
+                        .collect();
+                    layer.insert("objects".to_string(), Value::Array(objects));
+                    "objectgroup"
+                }
+                "imagelayer" => {
+                    if let Some(source) = child.child("image").and_then(|i| i.attribute("source")) {
+                        layer.insert("image".to_string(), json!(source));
+                    }
+                    "imagelayer"
+                }
+                "group" => {
+                    layer.insert("layers".to_string(), layers(child));
+                    "group"
+                }
+                _ => return None,
+            };
+            layer.insert("type".to_string(), json!(kind));
+            Some(Value::Object(layer))
+        })
+        .collect();
+    Value::Array(layers)
+}
+
+fn tile_data(data: &Element, layer: &mut Map<String, Value>) {
+    let value = match data.attribute("encoding") {
+        Some("csv") => data
+            .text
+            .split(',')
+            .map(|gid| json!(gid.trim().parse::<u32>().unwrap_or_default()))
+            .collect(),
+        Some(encoding) => {
+            layer.insert("encoding".to_string(), json!(encoding));
+            if let Some(compression) = data.attribute("compression") {
+                layer.insert("compression".to_string(), json!(compression));
+            }
+            json!(data.text.trim())
+        }
+        None => data
+            .children
+            .iter()
+            .filter(|tile| tile.name == "tile")
+            .map(|tile| {
+                json!(tile
+                    .attribute("gid")
+                    .and_then(|gid| gid.parse::<u32>().ok())
+                    .unwrap_or_default())
+            })
+            .collect(),
+    };
+    layer.insert("data".to_string(), value);
+}
+
+fn object(element: &Element) -> Value {
+    let mut object = attributes(element);
+    for shape in ["ellipse", "point"] {
+        if element.child(shape).is_some() {
+            object.insert(shape.to_string(), json!(true));
+        }
+    }
+    for shape in ["polygon", "polyline", "text"] {
+        if element.child(shape).is_some() {
+            object.insert(shape.to_string(), json!([]));
+        }
+    }
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn converts_maps_to_the_json_format() {
+        let map = to_json(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <map orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8" infinite="0">
+             <properties>
+              <property name="player_layer" type="int" value="2"/>
+              <property name="note">multi
+line</property>
+             </properties>
+             <tileset firstgid="1" source="tiles.tsx"/>
+             <group name="Group" offsetx="4.5">
+              <layer name="Ground" width="2" height="1" visible="0">
+               <data><tile gid="1"/><tile/></data>
+              </layer>
+             </group>
+             <objectgroup name="Objects">
+              <object id="3" name="Hole" x="1" y="2"><ellipse/></object>
+             </objectgroup>
+            </map>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            map,
+            json!({
+                "orientation": "orthogonal",
+                "width": 2,
+                "height": 1,
+                "tilewidth": 8,
+                "tileheight": 8,
+                "infinite": false,
+                "properties": [
+                    { "name": "player_layer", "type": "int", "value": 2 },
+                    { "name": "note", "type": "string", "value": "multi\nline" },
+                ],
+                "tilesets": [{ "firstgid": 1, "source": "tiles.tsx" }],
+                "layers": [
+                    {
+                        "name": "Group",
+                        "offsetx": 4.5,
+                        "layers": [{
+                            "name": "Ground",
+                            "width": 2,
+                            "height": 1,
+                            "visible": false,
+                            "data": [1, 0],
+                            "type": "tilelayer",
+                        }],
+                        "type": "group",
+                    },
+                    {
+                        "name": "Objects",
+                        "objects": [{ "id": 3, "name": "Hole", "x": 1, "y": 2, "ellipse": true }],
+                        "type": "objectgroup",
+                    },
+                ],
+            })
+        );
+    }
+}
@@ -11,7 +11,6 @@ use catalyst::{
     entity_files::{ContentFile, SceneFile},
     ContentId, EntityId, EntityType,
 };
-use cid::{multihash::MultihashDigest, Cid};
 use dcl_common::{Parcel, Result};
 use dcl_crypto::{AuthChain, AuthLink};
 use error::SceneDeployError;
@@ -57,7 +56,7 @@ pub async fn deploy(
 
     let form =
         build_entity_form_data_for_deployment(entity_id.to_string(), deploy_data, auth_chain);
-    server.raw_post_form("/content/entities", form).await
+    Ok(server.raw_post_form("/content/entities", form).await?)
 }
 
 pub fn build_entity_form_data_for_deployment(
@@ -143,9 +142,7 @@ pub fn build_entity_form_data_for_deployment(
 }
 
 fn get_cid(content: &[u8]) -> String {
-    let codec: u64 = 0x55;
-    let h = cid::multihash::Code::Sha2_256.digest(content);
-    Cid::new_v1(codec, h).to_string()
+    ContentId::from_content(content).to_string()
 }
 
 pub fn build_entity_scene(